@@ -51,6 +51,48 @@ impl Matrix {
         Matrix { rows }
     }
 
+    /// Create a Matrix of size (rows,columns) from its bits listed row by row, `data[i * columns + j]`
+    /// being the bit of row i and column j.
+    ///
+    /// Will panic if `data` doesn't have `rows * columns` bits.
+    pub fn from_flat(data: &[bool], rows: usize, columns: usize) -> Matrix {
+        assert_eq!(
+            data.len(),
+            rows * columns,
+            "a {}x{} matrix has {} bits, got {}",
+            rows,
+            columns,
+            rows * columns,
+            data.len()
+        );
+        Matrix {
+            rows: (0..rows)
+                .map(|i| data[i * columns..(i + 1) * columns].iter().cloned().collect())
+                .collect(),
+        }
+    }
+
+    /// Create a Matrix of size (rows,columns) from its bits listed column by column,
+    /// `data[j * rows + i]` being the bit of row i and column j.
+    ///
+    /// Will panic if `data` doesn't have `rows * columns` bits.
+    pub fn from_flat_column_major(data: &[bool], rows: usize, columns: usize) -> Matrix {
+        assert_eq!(
+            data.len(),
+            rows * columns,
+            "a {}x{} matrix has {} bits, got {}",
+            rows,
+            columns,
+            rows * columns,
+            data.len()
+        );
+        Matrix {
+            rows: (0..rows)
+                .map(|i| (0..columns).map(|j| data[j * rows + i]).collect())
+                .collect(),
+        }
+    }
+
     /// Return an iterator over the rows of the Matrix
     #[inline]
     pub fn iter_rows(&self) -> Iter<Vob> {
@@ -71,6 +113,20 @@ impl Matrix {
             None => 0,
         }
     }
+
+    /// Return true if the two matrices have the same rows regardless of their order,
+    /// ie their rows are equal as multisets.
+    pub fn equal_as_row_set(&self, other: &Matrix) -> bool {
+        if self.row_size() != other.row_size() {
+            return false;
+        }
+        let mut rows = self.rows.clone();
+        let mut other_rows = other.rows.clone();
+        // `Vob` is not `Ord`, the rows are sorted by comparing their bits
+        rows.sort_by(|a, b| a.iter().cmp(b.iter()));
+        other_rows.sort_by(|a, b| a.iter().cmp(b.iter()));
+        rows == other_rows
+    }
 }
 
 impl fmt::Debug for Matrix {
@@ -127,85 +183,194 @@ pub fn get_max_set_bit(vob: &Vob) -> Option<usize> {
 ///
 /// To compute the matrix of linear dependencies :
 ///
-/// -> augment the given matrix with the identity matrix
+/// -> augment each row of the given matrix with the row of the identity matrix of the same
+/// index, placed in the lower columns so that a row has its highest set bit in the given
+/// matrix as long as this part is not cleared
 ///
-/// -> gauss the matrix and apply the same operations on the identity matrix
+/// -> reduce the augmented rows one by one into a fully reduced echelon form (each highest
+/// set bit, or pivot, is cleared from all the other rows)
 ///
-/// -> return the lower part of the identity containing the dependencies
-pub fn extract_linear_dependencies(mut mat: Matrix) -> Matrix {
-    let mut id = identity(mat.row_size(), mat.row_size());
-    let mut loop_id = 0;
-    for i in (0..mat.row_size()).rev() {
-        let mut highest_set_bit = get_max_set_bit(&mat.rows[i]);
-        let mut max_row = i;
-        for j in (0..i).rev() {
-            if get_max_set_bit(&mat.rows[j]).is_some()
-                && (highest_set_bit.is_none()
-                    || get_max_set_bit(&mat.rows[j]).unwrap() > highest_set_bit.unwrap())
-            {
-                highest_set_bit = get_max_set_bit(&mat.rows[j]);
-                max_row = j;
-            }
+/// -> return, by increasing pivot, the identity part of the rows whose given matrix part
+/// was cleared, which are the dependencies
+///
+/// The dependencies are the reduced echelon form of the space of dependencies, so they
+/// don't depend on the order in which the rows are reduced. A matrix whose rows are all zeros
+/// gives no dependency.
+pub fn extract_linear_dependencies(mat: Matrix) -> Matrix {
+    let n_rows = mat.row_size();
+    if mat.iter_rows().all(|row| get_max_set_bit(row).is_none()) {
+        return Matrix::new(0, 0);
+    }
+    let mut reduced: Vec<Vob> = Vec::with_capacity(n_rows);
+    // the index in `reduced` of the row having this pivot
+    let mut pivot_rows: Vec<Option<usize>> = vec![None; n_rows + mat.column_size()];
+    for (i, row) in mat.rows.iter().enumerate() {
+        let mut augmented = Vob::from_elem(n_rows, false);
+        augmented.set(i, true);
+        augmented.extend_from_vob(row);
+        // a reduced row has no other pivot than its own, so adding it doesn't set new pivots
+        let set_pivots: Vec<usize> = augmented
+            .iter_set_bits(..)
+            .filter_map(|bit| pivot_rows[bit])
+            .collect();
+        for row_index in set_pivots {
+            augmented.xor(&reduced[row_index]);
         }
-        if let Some(highest_set_bit) = highest_set_bit {
-            if max_row < i {
-                mat.rows.swap(i, max_row);
-                id.rows.swap(i, max_row);
+        // the bit of the identity part is still set, the row is not zero
+        let pivot = get_max_set_bit(&augmented).unwrap();
+        for other in reduced.iter_mut() {
+            if other[pivot] {
+                other.xor(&augmented);
             }
-            for j in (0..i).rev() {
-                if get_max_set_bit(&mat.rows[j]).is_some()
-                    && get_max_set_bit(&mat.rows[j]).unwrap() == highest_set_bit
-                {
-                    let to_add = mat.rows[i].clone();
-                    mat.rows[j].xor(&to_add);
-                    let to_add = id.rows[i].clone();
-                    id.rows[j].xor(&to_add);
-                }
-            }
-        } else {
-            break;
         }
-        loop_id = i;
+        pivot_rows[pivot] = Some(reduced.len());
+        reduced.push(augmented);
     }
-    id.rows.drain(loop_id..id.row_size());
-    for i in (0..id.row_size()).rev() {
-        let mut highest_set_bit = get_max_set_bit(&id.rows[i]);
-        let mut max_row = i;
-        for j in (0..i).rev() {
-            if get_max_set_bit(&id.rows[j]).is_some()
-                && (highest_set_bit.is_none()
-                    || get_max_set_bit(&id.rows[j]).unwrap() > highest_set_bit.unwrap())
-            {
-                highest_set_bit = get_max_set_bit(&id.rows[j]);
-                max_row = j;
-            }
-        }
-        if let Some(highest_set_bit) = highest_set_bit {
-            if max_row < i {
-                id.rows.swap(i, max_row);
-            }
-            for j in (0..i).rev() {
-                if get_max_set_bit(&id.rows[j]).is_some()
-                    && get_max_set_bit(&id.rows[j]).unwrap() == highest_set_bit
-                {
-                    let to_add = id.rows[i].clone();
-                    id.rows[j].xor(&to_add);
+    let dependencies = pivot_rows
+        .iter()
+        .take(n_rows)
+        .filter_map(|row_index| *row_index)
+        .map(|row_index| {
+            let mut dependency = reduced[row_index].clone();
+            dependency.truncate(n_rows);
+            dependency
+        })
+        .collect();
+    Matrix::from_rows(dependencies)
+}
+
+/// Return true if `mat` is a square matrix of full rank, ie if it has an inverse.
+///
+/// The rank is found by reducing the rows one by one by the rows already kept, a row reduced
+/// to zero being a linear combination of the previous ones.
+pub fn is_invertible(mat: &Matrix) -> bool {
+    if mat.row_size() != mat.column_size() {
+        return false;
+    }
+    // the row kept for each highest set bit
+    let mut pivot_rows: Vec<Option<Vob>> = vec![None; mat.column_size()];
+    for row in mat.iter_rows() {
+        let mut row = row.clone();
+        loop {
+            let pivot = match get_max_set_bit(&row) {
+                Some(pivot) => pivot,
+                None => return false,
+            };
+            match &pivot_rows[pivot] {
+                Some(pivot_row) => {
+                    row.xor(pivot_row);
+                }
+                None => {
+                    pivot_rows[pivot] = Some(row);
+                    break;
                 }
             }
-        } else {
-            break;
         }
     }
-    for i in 0..id.row_size() {
-        let highest_set_bit = get_max_set_bit(&id.rows[i]);
-        for j in i + 1..id.row_size() {
-            if id.rows[j][highest_set_bit.unwrap()] {
-                let to_add = id.rows[i].clone();
-                id.rows[j].xor(&to_add);
-            }
+    true
+}
+
+/// Return the branch number of the linear map `matrix`, the minimum over the nonzero inputs x
+/// of the number of nonzero `word_size`-bit words of x plus the number of nonzero words of
+/// `matrix * x`.
+///
+/// The search goes through the inputs by increasing number of nonzero words, so it is
+/// exponential in the branch number (see `branch_number_bounded` to cap it).
+///
+/// Will panic if `word_size` is 0 or doesn't divide the numbers of rows and columns of `matrix`.
+pub fn branch_number(matrix: &Matrix, word_size: usize) -> usize {
+    branch_number_bounded(matrix, word_size, usize::MAX).0
+}
+
+/// Same as `branch_number`, but only goes through the inputs with at most `max_active_words`
+/// nonzero words.
+///
+/// Return the smallest weight found and true if it is the branch number, false if it is only an
+/// upper bound (an input with more nonzero words might have a smaller weight).
+pub fn branch_number_bounded(
+    matrix: &Matrix,
+    word_size: usize,
+    max_active_words: usize,
+) -> (usize, bool) {
+    assert!(
+        word_size > 0
+            && matrix.row_size().is_multiple_of(word_size)
+            && matrix.column_size().is_multiple_of(word_size),
+        "a {}x{} matrix can't be split into words of {} bits",
+        matrix.row_size(),
+        matrix.column_size(),
+        word_size
+    );
+    let n_in_words = matrix.column_size() / word_size;
+    let n_out_words = matrix.row_size() / word_size;
+    let columns = transpose(matrix).rows;
+    // the image of every nonzero value of every input word
+    let images: Vec<Vec<Vob>> = (0..n_in_words)
+        .map(|word| {
+            (1..1usize << word_size)
+                .map(|value| {
+                    let mut image = Vob::from_elem(matrix.row_size(), false);
+                    for bit in (0..word_size).filter(|bit| (value >> bit) & 1 == 1) {
+                        image.xor(&columns[word * word_size + bit]);
+                    }
+                    image
+                })
+                .collect()
+        })
+        .collect();
+    let mut best = n_in_words + n_out_words;
+    let mut active_words = 1;
+    // an input with `active_words` nonzero words has a weight of at least `active_words`
+    while active_words < best && active_words <= n_in_words {
+        if active_words > max_active_words {
+            return (best, false);
+        }
+        let zero = Vob::from_elem(matrix.row_size(), false);
+        search_branch_number(
+            &images,
+            word_size,
+            0,
+            active_words,
+            &zero,
+            active_words,
+            &mut best,
+        );
+        active_words += 1;
+    }
+    (best, true)
+}
+
+fn search_branch_number(
+    images: &[Vec<Vob>],
+    word_size: usize,
+    first_word: usize,
+    remaining_words: usize,
+    image: &Vob,
+    active_words: usize,
+    best: &mut usize,
+) {
+    if remaining_words == 0 {
+        let mut output_words: Vec<usize> =
+            image.iter_set_bits(..).map(|bit| bit / word_size).collect();
+        output_words.dedup();
+        *best = (*best).min(active_words + output_words.len());
+        return;
+    }
+    for word in first_word..=images.len() - remaining_words {
+        for word_image in images[word].iter() {
+            let mut next = image.clone();
+            next.xor(word_image);
+            search_branch_number(
+                images,
+                word_size,
+                word + 1,
+                remaining_words - 1,
+                &next,
+                active_words,
+                best,
+            );
         }
     }
-    id
 }
 
 /// Solve a linear system represented by a `Matrix` (left hand side) and a `Vob` (right hand side).
@@ -1,4 +1,7 @@
 use crate::algebra;
+use crate::algebra::Matrix;
+
+use vob::Vob;
 
 #[test]
 fn solving_linear_system_test() {
@@ -32,6 +35,30 @@ fn transpose_test() {
     assert_eq!(trans, expected_result);
 }
 
+#[test]
+fn from_flat_test() {
+    let rows = vec![
+        vob![true, false, true],
+        vob![false, false, true],
+    ];
+    let row_major = [true, false, true, false, false, true];
+    let column_major = [true, false, false, false, true, true];
+    assert_eq!(Matrix::from_flat(&row_major, 2, 3), matrix![rows.clone()]);
+    assert_eq!(Matrix::from_flat_column_major(&column_major, 2, 3), matrix![rows]);
+    // the column major bits of a matrix are the row major bits of its transpose
+    let m = Matrix::from_flat(&row_major, 3, 2);
+    assert_eq!(
+        Matrix::from_flat_column_major(&row_major, 2, 3),
+        algebra::transpose(&m)
+    );
+}
+
+#[test]
+#[should_panic]
+fn from_flat_wrong_size_test() {
+    Matrix::from_flat(&[true, false, true], 2, 2);
+}
+
 #[test]
 fn identity_test() {
     let id = algebra::identity(4, 4);
@@ -43,3 +70,213 @@ fn identity_test() {
     ]];
     assert_eq!(id, expected);
 }
+
+#[test]
+fn equal_as_row_set_test() {
+    let m = matrix![vec![
+        vob![true, false, true],
+        vob![false, true, true],
+        vob![true, false, true]
+    ]];
+    let permuted = matrix![vec![
+        vob![false, true, true],
+        vob![true, false, true],
+        vob![true, false, true]
+    ]];
+    assert!(m.equal_as_row_set(&permuted));
+    assert!(permuted.equal_as_row_set(&m));
+    // same set of rows but not the same multiplicities
+    let other = matrix![vec![
+        vob![false, true, true],
+        vob![false, true, true],
+        vob![true, false, true]
+    ]];
+    assert!(!m.equal_as_row_set(&other));
+    let fewer_rows = matrix![vec![vob![true, false, true], vob![false, true, true]]];
+    assert!(!m.equal_as_row_set(&fewer_rows));
+}
+
+#[test]
+fn is_invertible_test() {
+    let m = matrix![vec![
+        vob![true, true, false],
+        vob![false, true, true],
+        vob![false, false, true]
+    ]];
+    assert!(algebra::is_invertible(&m));
+    assert!(algebra::is_invertible(&algebra::identity(5, 5)));
+    // row 2 is row 0 + row 1
+    let m = matrix![vec![
+        vob![true, true, false],
+        vob![false, true, true],
+        vob![true, false, true]
+    ]];
+    assert!(!algebra::is_invertible(&m));
+    assert!(!algebra::is_invertible(&Matrix::new(3, 3)));
+    let m = matrix![vec![vob![true, false, false], vob![false, true, false]]];
+    assert!(!algebra::is_invertible(&m));
+}
+
+#[test]
+fn branch_number_test() {
+    // the identity maps a single nonzero bit to a single nonzero bit
+    assert_eq!(algebra::branch_number(&algebra::identity(8, 8), 1), 2);
+    // every column has two set bits and every sum of two columns too, but the three columns
+    // sum to zero
+    let m = matrix![vec![
+        vob![false, true, true],
+        vob![true, false, true],
+        vob![true, true, false]
+    ]];
+    assert_eq!(algebra::branch_number(&m, 1), 3);
+    // as a single 3-bit word, an input mapped to zero still has one nonzero word
+    assert_eq!(algebra::branch_number(&m, 3), 1);
+    // y0 = x0 + x1 and y1 = x0 + a * x1 on 2-bit words, a being a multiplication in GF(4)
+    let m = matrix![vec![
+        vob![true, false, true, false],
+        vob![false, true, false, true],
+        vob![true, false, false, true],
+        vob![false, true, true, true]
+    ]];
+    assert_eq!(algebra::branch_number(&m, 2), 3);
+    // a single nonzero word already gives a weight of 3, but two words could give less
+    assert_eq!(algebra::branch_number_bounded(&m, 2, 1), (3, false));
+    assert_eq!(
+        algebra::branch_number_bounded(&algebra::identity(8, 8), 1, 1),
+        (2, true)
+    );
+}
+
+#[test]
+fn extract_linear_dependencies_test() {
+    // row 2 is row 0 + row 1, row 3 is row 1
+    let m = matrix![vec![
+        vob![true, true, false],
+        vob![false, true, true],
+        vob![true, false, true],
+        vob![false, true, true]
+    ]];
+    let dependencies = algebra::extract_linear_dependencies(m);
+    let expected = matrix![vec![
+        vob![false, true, false, true],
+        vob![true, true, true, false]
+    ]];
+    assert!(dependencies.equal_as_row_set(&expected));
+}
+
+/// The former implementation of `extract_linear_dependencies`, reducing the matrix and then
+/// the dependencies in two separate passes.
+fn extract_linear_dependencies_reference(mut mat: Matrix) -> Matrix {
+    let mut id = algebra::identity(mat.row_size(), mat.row_size());
+    let mut loop_id = 0;
+    for i in (0..mat.row_size()).rev() {
+        let mut highest_set_bit = algebra::get_max_set_bit(&mat.rows[i]);
+        let mut max_row = i;
+        for j in (0..i).rev() {
+            if algebra::get_max_set_bit(&mat.rows[j]).is_some()
+                && (highest_set_bit.is_none()
+                    || algebra::get_max_set_bit(&mat.rows[j]).unwrap() > highest_set_bit.unwrap())
+            {
+                highest_set_bit = algebra::get_max_set_bit(&mat.rows[j]);
+                max_row = j;
+            }
+        }
+        if let Some(highest_set_bit) = highest_set_bit {
+            if max_row < i {
+                mat.rows.swap(i, max_row);
+                id.rows.swap(i, max_row);
+            }
+            for j in (0..i).rev() {
+                if algebra::get_max_set_bit(&mat.rows[j]).is_some()
+                    && algebra::get_max_set_bit(&mat.rows[j]).unwrap() == highest_set_bit
+                {
+                    let to_add = mat.rows[i].clone();
+                    mat.rows[j].xor(&to_add);
+                    let to_add = id.rows[i].clone();
+                    id.rows[j].xor(&to_add);
+                }
+            }
+        } else {
+            break;
+        }
+        loop_id = i;
+    }
+    id.rows.drain(loop_id..id.row_size());
+    for i in (0..id.row_size()).rev() {
+        let mut highest_set_bit = algebra::get_max_set_bit(&id.rows[i]);
+        let mut max_row = i;
+        for j in (0..i).rev() {
+            if algebra::get_max_set_bit(&id.rows[j]).is_some()
+                && (highest_set_bit.is_none()
+                    || algebra::get_max_set_bit(&id.rows[j]).unwrap() > highest_set_bit.unwrap())
+            {
+                highest_set_bit = algebra::get_max_set_bit(&id.rows[j]);
+                max_row = j;
+            }
+        }
+        if let Some(highest_set_bit) = highest_set_bit {
+            if max_row < i {
+                id.rows.swap(i, max_row);
+            }
+            for j in (0..i).rev() {
+                if algebra::get_max_set_bit(&id.rows[j]).is_some()
+                    && algebra::get_max_set_bit(&id.rows[j]).unwrap() == highest_set_bit
+                {
+                    let to_add = id.rows[i].clone();
+                    id.rows[j].xor(&to_add);
+                }
+            }
+        } else {
+            break;
+        }
+    }
+    for i in 0..id.row_size() {
+        let highest_set_bit = algebra::get_max_set_bit(&id.rows[i]);
+        for j in i + 1..id.row_size() {
+            if id.rows[j][highest_set_bit.unwrap()] {
+                let to_add = id.rows[i].clone();
+                id.rows[j].xor(&to_add);
+            }
+        }
+    }
+    id
+}
+
+/// A xorshift generator, enough to build reproducible random matrices.
+fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[test]
+fn extract_linear_dependencies_matches_reference_test() {
+    let mut state = 0x2545_f491_4f6c_dd1d;
+    let columns = 160;
+    let mut rows: Vec<Vob> = (0..200)
+        .map(|_| {
+            (0..columns)
+                .map(|_| xorshift(&mut state) & 1 == 1)
+                .collect()
+        })
+        .collect();
+    // add duplicates and sums of rows so that there are more dependencies than the ones
+    // forced by the number of rows
+    for _ in 0..100 {
+        let mut row = rows[xorshift(&mut state) as usize % rows.len()].clone();
+        if xorshift(&mut state) & 1 == 1 {
+            row.xor(&rows[xorshift(&mut state) as usize % rows.len()]);
+        }
+        rows.push(row);
+    }
+    rows.push(Vob::from_elem(columns, false));
+    for i in (1..rows.len()).rev() {
+        rows.swap(i, xorshift(&mut state) as usize % (i + 1));
+    }
+    let mat = Matrix::from_rows(rows);
+    let dependencies = algebra::extract_linear_dependencies(mat.clone());
+    let expected = extract_linear_dependencies_reference(mat);
+    assert!(dependencies.row_size() > 301 - columns);
+    assert!(dependencies.equal_as_row_set(&expected));
+}
@@ -0,0 +1,138 @@
+//! Error type returned by the apis of `soc::System` and by the solvers of `solver`.
+
+use crate::soc::Id;
+
+use std::error;
+use std::fmt;
+use std::io;
+
+/// The ways an operation on a `System` can fail.
+///
+/// Operations failing with a `CrushError` leave the `System` as it was before the call, unless
+/// stated otherwise in their documentation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CrushError {
+    /// A `Bdd` doesn't have the same `nvar` as the `System`.
+    NvarMismatch { expected: usize, found: usize },
+    /// A `Bdd` with this id is already in the `System`.
+    DuplicateBdd(Id),
+    /// No `Bdd` with this id is in the `System`.
+    BddNotFound(Id),
+    /// An operation on two `Bdd`s was given twice the same id.
+    SameBdd(Id),
+    /// The level `above` is not (directly, for a swap) above the level `below`.
+    NotAbove { above: usize, below: usize },
+    /// The level is not above the sink of the `Bdd`.
+    LevelOutOfRange { level: usize, sink: usize },
+    /// The variable is out of the range of the `System`.
+    VarOutOfRange(usize),
+    /// The variable doesn't appear in any `Bdd` of the `System`.
+    VarNotFound(usize),
+    /// There is no `Bdd` to build a `System` from, or no `Bdd` left in the `System`.
+    EmptySystem,
+    /// A linear equation is not linearly independant from the `LinBank`.
+    LinearlyDependent,
+    /// A linear equation contradicts the `LinBank` (it reduces to `0 = 1`).
+    Contradiction,
+    /// The constraint of this index contradicts the `LinBank` and the previous constraints.
+    Inconsistent(usize),
+    /// A solution doesn't have as many variables as the `System`.
+    SolutionLength { expected: usize, found: usize },
+    /// No path of the `Bdd` recorded by a drop is consistent with a solution.
+    NoConsistentPath(Id),
+    /// A `Bdd` would exceed the node budget of the `System`.
+    NodeBudget,
+    /// A `Bdd` has more paths than the most allowed to enumerate them.
+    TooManyPaths { max: usize },
+    /// An absorption left the `Bdd` of this id without any path, the `System` has no solution.
+    ///
+    /// The `System` may be left partially updated, there is no solution to look for anyway.
+    NoSolution(Id),
+    /// The equations given to build a `System` are malformed.
+    Parse(String),
+}
+
+impl fmt::Display for CrushError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrushError::NvarMismatch { expected, found } => write!(
+                f,
+                "Bdd have nvar size {}, system has {}",
+                found, expected
+            ),
+            CrushError::DuplicateBdd(id) => {
+                write!(f, "A Bdd with id {} is already in the system", id)
+            }
+            CrushError::BddNotFound(id) => write!(f, "id {} not present in system", id),
+            CrushError::SameBdd(id) => write!(f, "both bdd ids are equal to {}", id),
+            CrushError::NotAbove { above, below } => {
+                write!(f, "Level {} is not above Level {}", above, below)
+            }
+            CrushError::LevelOutOfRange { level, sink } => write!(
+                f,
+                "Out of range of levels : level {}, sink level is {}",
+                level, sink
+            ),
+            CrushError::VarOutOfRange(var) => {
+                write!(f, "variable {} out of the range of the system", var)
+            }
+            CrushError::VarNotFound(var) => {
+                write!(f, "variable {} not found in the bdds of the system", var)
+            }
+            CrushError::EmptySystem => write!(f, "no Bdd in the system"),
+            CrushError::LinearlyDependent => write!(
+                f,
+                "linear equation non linearly independant from current LinBank"
+            ),
+            CrushError::Contradiction => {
+                write!(f, "linear equation contradicts the current LinBank")
+            }
+            CrushError::Inconsistent(i) => {
+                write!(f, "constraint {}: contradicts the current LinBank", i)
+            }
+            CrushError::SolutionLength { expected, found } => write!(
+                f,
+                "solution has {} variables, system has {}",
+                found, expected
+            ),
+            CrushError::NoConsistentPath(id) => write!(
+                f,
+                "no path consistent with the solution in dropped bdd {}",
+                id
+            ),
+            CrushError::NodeBudget => write!(f, "node budget exceeded"),
+            CrushError::TooManyPaths { max } => {
+                write!(f, "too many paths to enumerate, more than {}", max)
+            }
+            CrushError::NoSolution(id) => {
+                write!(f, "bdd {} has no path left, the system has no solution", id)
+            }
+            CrushError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for CrushError {}
+
+/// Kept so the callers still working with `io::Error` can use `?` on the apis of `System`.
+///
+/// `NodeBudget` and `TooManyPaths` map to `ErrorKind::Other`, the errors caused by the arguments of a call to
+/// `ErrorKind::InvalidInput` and the ones caused by the content of the `System` to
+/// `ErrorKind::InvalidData`.
+impl From<CrushError> for io::Error {
+    fn from(e: CrushError) -> io::Error {
+        let kind = match e {
+            CrushError::NodeBudget | CrushError::TooManyPaths { .. } => io::ErrorKind::Other,
+            CrushError::SameBdd(_)
+            | CrushError::NotAbove { .. }
+            | CrushError::LevelOutOfRange { .. }
+            | CrushError::VarOutOfRange(_)
+            | CrushError::VarNotFound(_)
+            | CrushError::EmptySystem
+            | CrushError::SolutionLength { .. }
+            | CrushError::Parse(_) => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, e)
+    }
+}
@@ -6,6 +6,7 @@ extern crate vob;
 
 #[macro_use]
 pub mod algebra;
+pub mod error;
 pub mod soc;
 pub mod solver;
 
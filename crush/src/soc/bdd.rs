@@ -16,6 +16,10 @@
 //! - You will create less than ~2**53 nodes in your BDD (would overflow a 64 bits usize otherwise)
 //! - Generally you are running on a 64 bit system
 //!
+//! Since `next_id` only grows, `swap` and `add` relabel the nodes with the ids `1..=size` (see
+//! `compact_ids`) once it goes past `NEXT_ID_COMPACTION_THRESHOLD`, so only the number of nodes
+//! alive at once has to stay below this bound.
+//!
 //! Out of the array of `levels` 2 are specific : the first and the last.
 //! While they are stored as any level, the first level will only be one node
 //! and the last will also be one node with both outgoing edges set to None
@@ -25,15 +29,20 @@
 //! - removing the dead end nodes (skip the last level)
 //! - removing the orphan nodes (skip the first level)
 
+use crate::error::CrushError;
 use crate::soc::node::Node;
 use crate::soc::{level::Level, Id};
 use crate::{AHashMap, AHashSet};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::hash::BuildHasherDefault;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
 use vob::Vob;
 use num_bigint::ToBigUint;
 
+/// The `next_id` past which `swap` and `add` compact the ids of the nodes of a `Bdd`.
+pub const NEXT_ID_COMPACTION_THRESHOLD: usize = 1 << 40;
+
 /// A `LinEq` is a linear equation found in the BDD.
 /// A level which has only outgoing 1-edges or 0-edges
 /// can be absorbed and its equation and value extracted as a `LinEq`.
@@ -83,8 +92,13 @@ impl LinEq {
     }
 }
 
+/// Hash of the topology of a `Bdd`, ignoring the ids of its nodes and the lhs of its levels
+/// (see `Bdd::structural_hash`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StructuralHash(u64);
+
 /// A Binary Decision Diagram (see module documentation for more details)
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Bdd {
     levels: Vec<Level>,
     id: Id,
@@ -115,6 +129,50 @@ impl Bdd {
         self.next_id = next_id;
     }
 
+    /// Return the last id given to a node inserted by an operation of the `Bdd`
+    #[inline]
+    pub fn get_next_id(&self) -> usize {
+        self.next_id
+    }
+
+    /// Relabel the nodes with the ids `1..=size` (encoded with the id of the `Bdd`), level by
+    /// level and by increasing old id, and reset `next_id` to the size of the `Bdd`.
+    ///
+    /// The ids are never reused otherwise, this keeps `next_id` from growing without bound on a
+    /// long-lived `Bdd`. The `Bdd` stays equal to itself (`PartialEq` ignores the ids).
+    pub fn compact_ids(&mut self) {
+        let bdd_id = *self.id;
+        let mut new_ids: AHashMap<Id, Id> =
+            AHashMap::with_capacity_and_hasher(self.get_size(), Default::default());
+        for level in self.levels.iter() {
+            let mut ids: Vec<Id> = level.iter_nodes().map(|(id, _)| *id).collect();
+            ids.sort();
+            for id in ids {
+                let new_id = Id::new((new_ids.len() + 1) * 10000 + bdd_id);
+                new_ids.insert(id, new_id);
+            }
+        }
+        for level in self.levels.iter_mut() {
+            let nodes: AHashMap<Id, Node> = level
+                .iter_nodes()
+                .map(|(id, node)| {
+                    let e0 = node.get_e0().map(|e0| new_ids[&e0]);
+                    let e1 = node.get_e1().map(|e1| new_ids[&e1]);
+                    (new_ids[id], Node::with_edges(e0, e1))
+                })
+                .collect();
+            level.replace_nodes(nodes);
+        }
+        self.next_id = new_ids.len();
+    }
+
+    /// Call `compact_ids` if `next_id` went past `NEXT_ID_COMPACTION_THRESHOLD`.
+    fn compact_ids_past_threshold(&mut self) {
+        if self.next_id > NEXT_ID_COMPACTION_THRESHOLD {
+            self.compact_ids();
+        }
+    }
+
     /// Return the index of the last level
     #[inline]
     pub fn get_sink_level_index(&self) -> usize {
@@ -145,6 +203,19 @@ impl Bdd {
         self.levels.drain(..)
     }
 
+    /// Return the id, the 0-edge and the 1-edge of each node of the level `level`, sorted by id.
+    ///
+    /// Together with `get_lhs` this is enough to walk the graph of the `Bdd` (ex: to draw it)
+    /// without giving access to its internals. Panics if `level` is not a level of the `Bdd`.
+    pub fn level_nodes(&self, level: usize) -> Vec<(Id, Option<Id>, Option<Id>)> {
+        let mut nodes: Vec<(Id, Option<Id>, Option<Id>)> = self.levels[level]
+            .iter_nodes()
+            .map(|(id, node)| (*id, node.get_e0(), node.get_e1()))
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
     /// Return the number of levels of the `Bdd`
     #[inline]
     pub fn get_levels_size(&self) -> usize {
@@ -329,6 +400,43 @@ impl Bdd {
         let max_level_size = self.levels[level_index_below].get_nodes_len() * 2;
         let mut known_functions: AHashMap<(Option<Id>, Option<Id>), Id> =
             AHashMap::with_capacity_and_hasher(max_level_size, Default::default());
+        self.swap_with_known_functions(level_index_above, level_index_below, &mut known_functions);
+    }
+
+    /// Move the level at index `from` to the index `to`, shifting the levels in between by one.
+    ///
+    /// This is the same as swapping the level with its neighbour repeatedly until it reaches
+    /// `to`, but the map of the known functions is allocated once for all the swaps.
+    pub fn move_level(&mut self, from: usize, to: usize) {
+        let (first, last) = if from < to { (from, to) } else { (to, from) };
+        let max_level_size = self.levels[first..=last]
+            .iter()
+            .map(|level| level.get_nodes_len() * 2)
+            .max()
+            .unwrap();
+        let mut known_functions: AHashMap<(Option<Id>, Option<Id>), Id> =
+            AHashMap::with_capacity_and_hasher(max_level_size, Default::default());
+        if from < to {
+            for i in from..to {
+                known_functions.clear();
+                self.swap_with_known_functions(i, i + 1, &mut known_functions);
+            }
+        } else {
+            for i in (to..from).rev() {
+                known_functions.clear();
+                self.swap_with_known_functions(i, i + 1, &mut known_functions);
+            }
+        }
+    }
+
+    /// The swap operation (see `swap`), using the empty `known_functions` as scratch space.
+    fn swap_with_known_functions(
+        &mut self,
+        level_index_above: usize,
+        level_index_below: usize,
+        known_functions: &mut AHashMap<(Option<Id>, Option<Id>), Id>,
+    ) {
+        let max_level_size = self.levels[level_index_below].get_nodes_len() * 2;
         let mut nodes: AHashMap<Id, Node> =
             AHashMap::with_capacity_and_hasher(max_level_size, Default::default());
         let (above, below) = self.levels.split_at_mut(level_index_above + 1);
@@ -398,6 +506,7 @@ impl Bdd {
             });
         self.next_id = next_id;
         self.levels[level_index_below].replace_nodes(nodes);
+        self.compact_ids_past_threshold();
         let lhs_1 = self.levels[level_index_above].get_lhs();
         let lhs_2 = self.levels[level_index_below].get_lhs();
         self.levels[level_index_above].replace_lhs(lhs_2);
@@ -432,9 +541,9 @@ impl Bdd {
     /// Finally add the `lhs` of `level_1` to `level_2`
     pub fn add(&mut self, mut level_index_above: usize, level_index_below: usize) {
         assert!(level_index_above < level_index_below);
-        while level_index_below > level_index_above + 1 {
-            self.swap(level_index_above, level_index_above + 1);
-            level_index_above += 1;
+        if level_index_below > level_index_above + 1 {
+            self.move_level(level_index_above, level_index_below - 1);
+            level_index_above = level_index_below - 1;
         }
         let max_level_size = self.levels[level_index_below].get_nodes_len() * 2;
         let mut nodes: AHashMap<Id, Node> =
@@ -487,6 +596,7 @@ impl Bdd {
         }
         self.next_id = next_id;
         self.levels[level_index_below].replace_nodes(nodes);
+        self.compact_ids_past_threshold();
         let lhs_1 = self.levels[level_index_above].get_lhs();
         self.levels[level_index_below].add_lhs(&lhs_1);
     }
@@ -496,28 +606,45 @@ impl Bdd {
     /// Connect each edge of the `level` above to the sink if they were connected to the `level` to drop,
     /// remove the level to drop,
     /// finally merge the equal nodes in the bdd.
+    ///
+    /// When the dropped level is the last one above the sink, only the sink is left (all its
+    /// paths were valid for one value of the dropped lhs). When a single level is left above the
+    /// sink, it is the source and its node has nothing to be merged with.
+    ///
+    /// Panics if `level_index` is not above the sink.
     pub fn drop(&mut self, mut level_index: usize) {
-        while level_index != self.get_levels_size() - 2 {
-            self.swap(level_index, level_index + 1);
-            level_index += 1;
+        let sink_level_index = self.get_sink_level_index();
+        assert!(
+            level_index < sink_level_index,
+            "level {} is not above the sink level {}",
+            level_index,
+            sink_level_index
+        );
+        if level_index != sink_level_index - 1 {
+            self.move_level(level_index, sink_level_index - 1);
+            level_index = sink_level_index - 1;
         }
-        let len = self.get_levels_size() - 1;
-        let (above, sink) = self.levels.split_at_mut(len);
-        if level_index != 0 {
-            if let Some((sink_id, _)) = sink[0].iter_nodes().next() {
-                above[len - 2].iter_mut_nodes().for_each(|(_, node)| {
-                    if node.get_e0().is_some() {
-                        node.connect_e0(*sink_id);
-                    }
-                    if node.get_e1().is_some() {
-                        node.connect_e1(*sink_id);
-                    }
-                });
-            }
+        if level_index == 0 {
+            self.levels.remove(level_index);
+            debug_assert_eq!(self.get_levels_size(), 1);
+            return;
+        }
+        let (above, sink) = self.levels.split_at_mut(sink_level_index);
+        if let Some((sink_id, _)) = sink[0].iter_nodes().next() {
+            above[level_index - 1].iter_mut_nodes().for_each(|(_, node)| {
+                if node.get_e0().is_some() {
+                    node.connect_e0(*sink_id);
+                }
+                if node.get_e1().is_some() {
+                    node.connect_e1(*sink_id);
+                }
+            });
         }
         self.levels.remove(level_index);
         if level_index > 1 {
             self.merge_equals_node_start(level_index - 1);
+        } else {
+            debug_assert_eq!(self.levels[0].get_nodes_len(), 1);
         }
     }
 
@@ -525,7 +652,10 @@ impl Bdd {
     /// connect each parent of the nodes located at `level_index` to its child 0/1edge (depending of the valeur of `edge`).
     /// The opposite edges are now non-valid (if the lhs is equal to zero, cannot be equal to one and viceversa).
     /// The level is then remove and reducing is perform on the bdd (removing orphans and dead ends).
-    pub fn absorb(&mut self, level_index: usize, edge: bool) {
+    ///
+    /// Return an `Error` without changing the bdd if no node of the level has an edge of the
+    /// value `edge`, the bdd then having no solution.
+    pub fn absorb(&mut self, level_index: usize, edge: bool) -> Result<(), CrushError> {
         let mut new_level = AHashMap::with_capacity_and_hasher(
             self.levels[level_index].get_nodes_len(),
             Default::default(),
@@ -533,8 +663,7 @@ impl Bdd {
 
         // If the level to absorb is the source of the bdd, different strategy
         if level_index == 0 {
-            self.absorb_source(edge);
-            return;
+            return self.absorb_source(edge);
         }
 
         if !edge {
@@ -557,19 +686,26 @@ impl Bdd {
         // the level had only outgoing edges of the other type.
         // This would be a 0 = 1
         if new_level.is_empty() {
-            panic!("System has no solutions")
+            return Err(CrushError::NoSolution(self.id));
         }
         self.point_all_parents_to_new_level_map(&new_level, level_index - 1, level_index);
         self.levels.remove(level_index);
         self.remove_all_dead_ends_start(level_index - 1);
         self.remove_orphans_start(level_index);
         self.merge_equals_node_start(level_index - 1);
+        Ok(())
     }
 
     /// Absorb the source of the bdd along the edge precised.
     /// To absorb it we remove the opposing edge of the next level.
     /// The level 0 is then removed and then the orphans removed starting at new level 1
-    fn absorb_source(&mut self, edge: bool) {
+    fn absorb_source(&mut self, edge: bool) -> Result<(), CrushError> {
+        // If there is not valid outgoing edge then there is no solution
+        let (_, source) = self.levels[0].iter_nodes().next().unwrap();
+        let kept = if edge { source.get_e1() } else { source.get_e0() };
+        if kept.is_none() {
+            return Err(CrushError::NoSolution(self.id));
+        }
         let node = &self.levels[0].pop_source();
         // if the top node has both edges pointing to same node, we don't need to remove the wrong edge
         if node.get_e0() != node.get_e1() {
@@ -582,11 +718,8 @@ impl Bdd {
             }
         }
         self.levels.remove(0);
-        // If there is not valid outgoing edge then there is no solution
-        if self.levels[0].get_nodes_len() == 0 {
-            panic!("System has no solutions")
-        }
         self.remove_orphans_start(1);
+        Ok(())
     }
 
     /// Iterate through the bdd to find linear equations
@@ -594,7 +727,10 @@ impl Bdd {
     /// or outoing 1edges
     /// The equation is then extracted as a LinEq and the level absorbed
     /// Loop until no equation are left to absorb
-    pub fn scan_absorb_lin_eq(&mut self) -> Vec<LinEq> {
+    ///
+    /// Return an `Error` if a level with no outgoing edge is found, the bdd then having no
+    /// solution (see `absorb`).
+    pub fn scan_absorb_lin_eq(&mut self) -> Result<Vec<LinEq>, CrushError> {
         let mut lin_eqs_absorbed = Vec::new();
         loop {
             let mut absorbed = false;
@@ -603,7 +739,7 @@ impl Bdd {
                 // in the unlikely event that there is a 0 level remaining in the BDD
                 // we absorb it but the equation is 0 = 0 so we don't grab it
                 if level.iter_set_lhs().count() == 0 {
-                    self.absorb(i, false);
+                    self.absorb(i, false)?;
                     absorbed = true;
                     break;
                 }
@@ -611,13 +747,13 @@ impl Bdd {
                 if !has_0edge {
                     let lin_eq = LinEq::new(level.get_lhs(), true);
                     lin_eqs_absorbed.push(lin_eq);
-                    self.absorb(i, true);
+                    self.absorb(i, true)?;
                     absorbed = true;
                     break;
                 } else if !has_1edge {
                     let lin_eq = LinEq::new(level.get_lhs(), false);
                     lin_eqs_absorbed.push(lin_eq);
-                    self.absorb(i, false);
+                    self.absorb(i, false)?;
                     absorbed = true;
                     break;
                 }
@@ -626,7 +762,25 @@ impl Bdd {
                 break;
             }
         }
-        lin_eqs_absorbed
+        Ok(lin_eqs_absorbed)
+    }
+
+    /// Count the levels which `scan_absorb_lin_eq` would absorb as a linear equation right away,
+    /// the levels (except the sink) with a non empty lhs and only outgoing 0edges or only
+    /// outgoing 1edges.
+    ///
+    /// The `Bdd` is left as it is, and as absorbing a level can make other levels linear this is
+    /// a lower bound on the number of equations `scan_absorb_lin_eq` finds.
+    pub fn count_linear_levels(&self) -> usize {
+        self.levels
+            .iter()
+            .take(self.levels.len() - 1)
+            .filter(|level| level.iter_set_lhs().next().is_some())
+            .filter(|level| {
+                let (has_0edge, has_1edge) = level.check_outgoing_edges();
+                has_0edge != has_1edge
+            })
+            .count()
     }
 
     /// Used to remove any jumping edges in a bdd, ensuring that if a node has a parent
@@ -673,12 +827,92 @@ impl Bdd {
         }
     }
 
+    /// Hash the topology of the `Bdd`: two `Bdd`s with the same number of levels and with nodes
+    /// connected the same way have the same `StructuralHash`, whatever their lhs.
+    ///
+    /// The nodes are numbered in the order they are reached from the source, following the edge 0
+    /// before the edge 1, so the hash doesn't depend on their ids. Assumes the `Bdd` has no
+    /// jumping edges.
+    pub fn structural_hash(&self) -> StructuralHash {
+        let mut hasher = DefaultHasher::new();
+        self.levels.len().hash(&mut hasher);
+        let mut order: Vec<Id> = self.levels[0].iter_nodes().map(|(id, _)| *id).collect();
+        order.sort();
+        for level in self.levels.iter() {
+            order.len().hash(&mut hasher);
+            let mut next_order = Vec::new();
+            let mut next_index: AHashMap<Id, usize> =
+                AHashMap::with_capacity_and_hasher(order.len() * 2, Default::default());
+            for id in order.iter() {
+                let node = level.get_nodes().get(id).unwrap();
+                for edge in [node.get_e0(), node.get_e1()].iter() {
+                    let index = edge.map(|child| {
+                        *next_index.entry(child).or_insert_with(|| {
+                            next_order.push(child);
+                            next_order.len()
+                        })
+                    });
+                    index.hash(&mut hasher);
+                }
+            }
+            order = next_order;
+        }
+        StructuralHash(hasher.finish())
+    }
+
+    /// Return the first edge found going from a node to a node which is not in the level just
+    /// below, as a pair (parent, child) of node ids, or `None` if the `Bdd` has no jumping edges.
+    pub fn has_jumping_edges(&self) -> Option<(Id, Id)> {
+        for (level, level_below) in self.levels.iter().zip(self.levels.iter().skip(1)) {
+            for (id, node) in level.iter_nodes() {
+                for child in node.get_e0().iter().chain(node.get_e1().iter()) {
+                    if !level_below.get_nodes().contains_key(child) {
+                        return Some((*id, *child));
+                    }
+                }
+            }
+        }
+        None
+    }
+
     /// Merge nodes which represent the same function in a level.
     /// Start with the level_index and goes upwards.
     ///
     /// Short circuited -> will stop when no change were found in the previous level.
     pub fn merge_equals_node_start(&mut self, mut level_index: usize) {
         let mut changed = true;
+        while changed && level_index > 1 {
+            changed = self.merge_equals_node_at_level(level_index);
+            level_index -= 1;
+        }
+    }
+
+    /// Merge nodes which represent the same function in every level, from the
+    /// bottom of the `Bdd` up to the level just below the source.
+    ///
+    /// Unlike `merge_equals_node_start` this never short circuits, so merges
+    /// uncovered in a level are always propagated to all the levels above it.
+    pub fn fully_reduce(&mut self) {
+        for level_index in (1..self.get_sink_level_index()).rev() {
+            self.merge_equals_node_at_level(level_index);
+        }
+        debug_assert!(self.is_reduced());
+    }
+
+    /// Returns true if no two nodes of a same level share the same outgoing edges.
+    pub fn is_reduced(&self) -> bool {
+        self.levels.iter().all(|level| {
+            let mut known_functions: AHashSet<(Option<Id>, Option<Id>)> =
+                AHashSet::with_capacity_and_hasher(level.get_nodes_len(), Default::default());
+            level
+                .iter_nodes()
+                .all(|(_, node)| known_functions.insert((node.get_e0(), node.get_e1())))
+        })
+    }
+
+    /// Merge the nodes of the level at `level_index` which represent the same function
+    /// and point their parents to the remaining node. Returns true if any node was merged.
+    fn merge_equals_node_at_level(&mut self, level_index: usize) -> bool {
         let max_size_map = self.levels[level_index].get_nodes_len();
         let mut known_functions: HashMap<
             (Option<Id>, Option<Id>),
@@ -687,25 +921,22 @@ impl Bdd {
         > = AHashMap::with_capacity_and_hasher(max_size_map, Default::default());
         let mut map: HashMap<Id, Id, BuildHasherDefault<ahash::AHasher>> =
             AHashMap::with_capacity_and_hasher(max_size_map, Default::default());
-        while changed && level_index > 1 {
-            changed = false;
-            for (id, node) in self.levels[level_index].iter_nodes() {
-                match known_functions.get(&(node.get_e0(), node.get_e1())) {
-                    Some(existing_node) => {
-                        changed = true;
-                        map.insert(*id, *existing_node);
-                    }
-                    None => {
-                        known_functions.insert((node.get_e0(), node.get_e1()), *id);
-                    }
-                };
-            }
-            self.point_all_parents_to_new_level_map(&map, level_index - 1, level_index);
-            self.levels[level_index].remove_nodes_from_map(&map);
-            known_functions.clear();
-            map.clear();
-            level_index -= 1;
+        for (id, node) in self.levels[level_index].iter_nodes() {
+            match known_functions.get(&(node.get_e0(), node.get_e1())) {
+                Some(existing_node) => {
+                    map.insert(*id, *existing_node);
+                }
+                None => {
+                    known_functions.insert((node.get_e0(), node.get_e1()), *id);
+                }
+            };
+        }
+        if map.is_empty() {
+            return false;
         }
+        self.point_all_parents_to_new_level_map(&map, level_index - 1, level_index);
+        self.levels[level_index].remove_nodes_from_map(&map);
+        true
     }
 
     /// For all `nodes` located on the range `level_start..level_max` (level_max not included) :
@@ -773,8 +1004,38 @@ impl Bdd {
     /// When we reach the sink we go back to the stack to find the next path
     /// up until the stack is exhausted.
     /// If a BDD contain more that 20 paths we only return the first 20 to avoid
-    /// exploding in memory size.
+    /// exploding in memory size, see `get_all_valid_path_bounded` to get all of them.
     pub fn get_all_valid_path(&self) -> Vec<Vec<LinEq>> {
+        self.enumerate_valid_paths(Some(20))
+    }
+
+    /// Returns a `Vec` of all valid paths of a `Bdd` (see `get_all_valid_path`), without
+    /// stopping after the first 20.
+    ///
+    /// The paths are counted first (see `count_paths`): if there are more than `max_paths` of
+    /// them an `Error` is returned straight away instead of starting the enumeration.
+    pub fn get_all_valid_path_bounded(
+        &self,
+        max_paths: usize,
+    ) -> Result<Vec<Vec<LinEq>>, CrushError> {
+        if self.count_paths() > max_paths.into() {
+            return Err(CrushError::TooManyPaths { max: max_paths });
+        }
+        Ok(self.enumerate_valid_paths(None))
+    }
+
+    /// Returns the first `n` valid paths of a `Bdd` (see `get_all_valid_path`), or all of them
+    /// if there are fewer.
+    pub fn get_first_valid_paths(&self, n: usize) -> Vec<Vec<LinEq>> {
+        if n == 0 {
+            return Vec::new();
+        }
+        self.enumerate_valid_paths(Some(n - 1))
+    }
+
+    /// Enumerate the valid paths from the top to the bottom, stopping once there are more than
+    /// `cap` of them if a `cap` is given.
+    fn enumerate_valid_paths(&self, cap: Option<usize>) -> Vec<Vec<LinEq>> {
         if self.get_sink_level_index() == 0 {
             return vec![vec![]];
         }
@@ -846,7 +1107,7 @@ impl Bdd {
             }
             paths.push(path);
             //just checking to avoid exploding in memory if called on a really large bdd
-            if paths.len() > 20 {
+            if cap.is_some_and(|cap| paths.len() > cap) {
                 return paths;
             }
         }
@@ -910,7 +1171,9 @@ impl Bdd {
     /// Replace a variable in all the lhs of the bdd by a linear combination.
     /// If the linear combination is equal to true:flip all the edges of the level.
     /// If when replacing the lhs a zero level is created -> absorb it along its zero edges.
-    pub fn replace_var_in_bdd(&mut self, var: usize, eq: &LinEq) {
+    ///
+    /// Return an `Error` if a zero level has no zero edge, the bdd then having no solution.
+    pub fn replace_var_in_bdd(&mut self, var: usize, eq: &LinEq) -> Result<(), CrushError> {
         let mut to_absorbe: Vec<usize> = Vec::with_capacity(self.levels.len());
         // We should be skipping the last level, but since we are explicitly checking that
         // the level has the var bit set and the last level has an all-zero lhs
@@ -928,8 +1191,51 @@ impl Bdd {
             }
         });
         for _ in 0..to_absorbe.len() {
-            self.absorb(to_absorbe.pop().unwrap(), false);
+            self.absorb(to_absorbe.pop().unwrap(), false)?;
+        }
+        Ok(())
+    }
+    /// Replace several variables in all the lhs of the bdd by linear combinations in a single
+    /// pass over the levels (see `replace_var_in_bdd`).
+    ///
+    /// The variable of each pair should not appear in the linear combination of any other
+    /// pair, so that the order of the replacements doesn't matter.
+    pub fn replace_vars_in_bdd(&mut self, eqs: &[(usize, LinEq)]) -> Result<(), CrushError> {
+        self.apply_linear_map(eqs)
+    }
+
+    /// Substitute simultaneously every variable of `map` by its linear combination in all the
+    /// lhs of the bdd, in a single pass over the levels.
+    ///
+    /// As in `replace_var_in_bdd`, the `LinEq` paired with a variable contains this variable and
+    /// its rhs flips the edges. The substitution is simultaneous: a variable appearing in the
+    /// combination of another one is not substituted itself, so `map` can describe any linear
+    /// layer (ex: swapping two variables).
+    ///
+    /// Return an `Error` if a zero level has no zero edge, the bdd then having no solution.
+    pub fn apply_linear_map(&mut self, map: &[(usize, LinEq)]) -> Result<(), CrushError> {
+        let mut to_absorbe: Vec<usize> = Vec::with_capacity(self.levels.len());
+        self.levels.iter_mut().enumerate().for_each(|(i, level)| {
+            let lhs = level.get_lhs();
+            let mut replaced = false;
+            for (var, eq) in map.iter() {
+                if lhs.get(*var) == Some(true) {
+                    level.add_lhs(&eq.get_lhs());
+                    if eq.get_rhs() {
+                        level.flip_edges();
+                    }
+                    replaced = true;
+                }
+            }
+            if replaced && level.iter_set_lhs().next().is_none() {
+                //No bits are set -> zero level
+                to_absorbe.push(i);
+            }
+        });
+        for _ in 0..to_absorbe.len() {
+            self.absorb(to_absorbe.pop().unwrap(), false)?;
         }
+        Ok(())
     }
 }
 
@@ -949,7 +1255,8 @@ impl fmt::Debug for Bdd {
 
 /// Mainly implemented for testing
 ///
-/// Both `Bdd` should be reduced before comparing.
+/// Both `Bdd` should be reduced before comparing (see `is_reduced`), this is checked in debug
+/// builds.
 ///
 /// Since we cannot rely on the order or the id of the nodes,
 /// we compare 2 bdd by mapping nodes to each other following their edges.
@@ -957,6 +1264,10 @@ impl fmt::Debug for Bdd {
 /// on the other bdd or else the bdds are not equal.
 impl PartialEq for Bdd {
     fn eq(&self, other: &Bdd) -> bool {
+        debug_assert!(
+            self.is_reduced() && other.is_reduced(),
+            "comparing a Bdd which is not reduced"
+        );
         if self.get_levels_size() != other.get_levels_size() {
             return false;
         }
@@ -1036,3 +1347,16 @@ impl PartialEq for Bdd {
 }
 
 impl Eq for Bdd {}
+
+/// Consistent with `PartialEq`: hashes the lhs of the levels and the topology of the nodes (see
+/// `structural_hash`), but not the ids of the nodes or of the `Bdd`.
+///
+/// Like `eq`, this walks through every node of the `Bdd` (and allocates a map of the nodes of each
+/// level), so it is linear in the size of the `Bdd` and should not be called on large `Bdd`s in a
+/// hot loop. The `Bdd` should be reduced and have no jumping edges.
+impl Hash for Bdd {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_lhs().hash(state);
+        self.structural_hash().hash(state);
+    }
+}
@@ -18,7 +18,7 @@ use std::collections::hash_map::{Iter, IterMut};
 use vob::{IterSetBits, Vob};
 
 /// A level inside a Binary Decision Diagram
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Level {
     nodes: AHashMap<Id, Node>,
     lhs: Vob,
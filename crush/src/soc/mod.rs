@@ -40,7 +40,7 @@ macro_rules! bdd {
         [$($crate::soc::utils::LevelSpec::new($crate::soc::utils::vars(nom::types::CompleteStr(&$lhs)).expect("wrong format for lhs").1, [
             $($crate::soc::utils::NodeSpec::new(Id::new($id_node), Id::new($e0), Id::new($e1)))
             ,*].to_vec()))
-        ,*].to_vec()),$nvar);
+        ,*].to_vec()),$nvar)
     }
 }
 
@@ -75,6 +75,32 @@ impl Id {
     pub fn new(val: usize) -> Id {
         Id { val }
     }
+
+    /// Return a new `Id` shifted by `delta` from the current one.
+    ///
+    /// Will panic if the resulting value overflows the storage type.
+    #[inline]
+    pub fn offset(self, delta: usize) -> Id {
+        Id::new(
+            self.val
+                .checked_add(delta)
+                .expect("overflow when computing the offset of an Id"),
+        )
+    }
+}
+
+impl From<usize> for Id {
+    #[inline]
+    fn from(val: usize) -> Id {
+        Id::new(val)
+    }
+}
+
+impl From<Id> for usize {
+    #[inline]
+    fn from(id: Id) -> usize {
+        id.val
+    }
 }
 
 impl Deref for Id {
@@ -7,24 +7,120 @@
 //! the solutions to the system of equations it represents can be extracted.
 
 use crate::algebra;
+use crate::error::CrushError;
+use crate::solver::Dependency;
 use crate::soc::{
-    bdd::{Bdd, LinEq},
-    Id,
+    bdd::{Bdd, LinEq, StructuralHash},
+    utils, Id,
 };
-use crate::AHashMap;
+use crate::{AHashMap, AHashSet};
 
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
-use std::io::{self, Error, ErrorKind};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
 use std::result::Result;
 use vob::Vob;
 
+/// The solutions of a `System` along with whether some solutions were left out, see
+/// `System::get_solutions_bounded`.
+pub type BoundedSolutions = (Vec<Vec<Option<bool>>>, bool);
+
 /// A system of Bdds providing a number of methods to interact safely with the Bdds it contains
-#[derive(Default)]
+///
+/// A `System` is `Send`, so independent systems can be moved to their own thread and solved
+/// concurrently, but it is not `Sync` since its `Bdd`s are kept in `RefCell`s.
+/// Cloning a `System` gives an independent copy, useful to solve the same instance in
+/// different ways.
+#[derive(Default, Clone)]
 pub struct System {
     bdds: AHashMap<Id, RefCell<Bdd>>,
     nvar: usize,
     lin_bank: LinBank,
+    record_drops: bool,
+    drop_records: Vec<DropRecord>,
+    max_bdd_nodes: Option<usize>,
+    replace_passes: usize,
+    record_ops: bool,
+    op_log: Vec<SystemOp>,
+    // the solutions found by the last `get_solutions`, forgotten by any change of the `System`
+    solutions: RefCell<Option<Vec<Vec<Option<bool>>>>>,
+}
+
+/// A `DropRecord` keeps what is needed to recover the value of the variables
+/// lost by a `drop` operation once the `System` is solved.
+///
+/// It holds a copy of the `Bdd` taken right before the drop together with the
+/// index of the dropped level. Since a solution of the `System` after the drop
+/// is still following a path in the upper levels of this copy, walking it
+/// tells us which edge(s) of the dropped level are valid for the solution.
+#[derive(Clone)]
+pub struct DropRecord {
+    bdd: Bdd,
+    level_index: usize,
+}
+
+/// An operation done on a `System`, as kept by its operation log (see `set_op_recording`).
+///
+/// Each variant holds the arguments of the method of the same name, so that `System::replay`
+/// can apply the operations again in the same order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SystemOp {
+    Swap { bdd_id: Id, above: usize, below: usize },
+    MoveLevel { bdd_id: Id, from: usize, to: usize },
+    Add { bdd_id: Id, above: usize, below: usize },
+    Absorb { bdd_id: Id, level_index: usize, edge: bool },
+    Drop { bdd_id: Id, level_index: usize },
+    JoinBdds { bdd_1_id: Id, bdd_2_id: Id },
+    Fix { lhs: Vec<usize>, rhs: bool },
+    FixMany { eqs: Vec<(Vec<usize>, bool)> },
+    ScanAbsorbLinEqs { bdd_id: Id },
+    PopBdd { bdd_id: Id },
+    PushBdd { bdd: Bdd },
+}
+
+/// What a call to `System::reduce_once` did to the `System`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StepReport {
+    /// The ids of the `Bdd`s joined to resolve the dependency, the first one is the `Bdd` the
+    /// others were joined into.
+    pub joined: Vec<Id>,
+    /// The number of times a level was added to the level below it.
+    pub levels_added: usize,
+    /// The number of times a level was moved or swapped to bring the levels of the dependency
+    /// next to each other.
+    pub levels_moved: usize,
+    /// The number of linear equations absorbed and pushed to the `LinBank`.
+    pub lin_eqs_found: usize,
+}
+
+/// The differences between two `System`s found by `System::diff`, the ids are sorted and the
+/// equations are in the order they were pushed to the `LinBank`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemDiff {
+    /// The ids of the `Bdd`s which are only in the first `System`.
+    pub only_in_self: Vec<Id>,
+    /// The ids of the `Bdd`s which are only in the second `System`.
+    pub only_in_other: Vec<Id>,
+    /// The ids of the `Bdd`s of both `System`s with a different number of nodes, different lhs
+    /// or a different topology.
+    pub changed: Vec<Id>,
+    /// The equations (lhs and rhs) of the `LinBank` of the first `System` which are not in the
+    /// `LinBank` of the second.
+    pub lin_eqs_only_in_self: Vec<(Vob, bool)>,
+    /// The equations (lhs and rhs) of the `LinBank` of the second `System` which are not in the
+    /// `LinBank` of the first.
+    pub lin_eqs_only_in_other: Vec<(Vob, bool)>,
+}
+
+impl SystemDiff {
+    /// Return true if no difference was found.
+    pub fn is_empty(&self) -> bool {
+        *self == SystemDiff::default()
+    }
 }
 
 /// `LinBank` is the structure holding the valid linear equations
@@ -82,15 +178,26 @@ impl System {
         Default::default()
     }
 
+    /// Build a `System` from GF(2) polynomial equations given in ANF, with monomials up to
+    /// degree 2 (see `utils::parse_system_spec_from_anf` for the format).
+    ///
+    /// The variables `x0..xn` of the equations keep their index, the auxiliary variables
+    /// standing for the quadratic monomials come after them.
+    pub fn from_anf(input: &str) -> Result<System, CrushError> {
+        let (spec, _) = utils::parse_system_spec_from_anf(input)
+            .map_err(|e| CrushError::Parse(e.to_string()))?;
+        Ok(utils::build_system_from_spec(spec))
+    }
+
     /// Construct a `System` from a `Vec` of `Bdd` using the `nvar` of the first `Bdd`
     /// as its `nvar`.
     ///
     /// Will return an `Error` if all Bdds don't have the same `nvar`.
-    pub fn from_elem(bdds: Vec<Bdd>) -> Result<System, Error> {
+    pub fn from_elem(bdds: Vec<Bdd>) -> Result<System, CrushError> {
         let mut bdds = bdds;
         let mut sys = System::new();
         if bdds.is_empty() {
-            return Err(Error::new(ErrorKind::InvalidInput, "Empty vec"));
+            return Err(CrushError::EmptySystem);
         }
         sys.nvar = bdds[0].get_nvar_size();
         for bdd in bdds.drain(..) {
@@ -101,6 +208,7 @@ impl System {
 
     /// Set `nvar` of the `System`
     pub fn set_nvar(&mut self, nvar: usize) {
+        self.forget_solutions();
         self.nvar = nvar;
     }
 
@@ -109,22 +217,87 @@ impl System {
         self.nvar
     }
 
-    /// Push a `Bdd` in the system.
+    /// Grow `nvar` of the `System` to `nvar`, resizing the lhs of the levels of all the `Bdd`s
+    /// (including the ones kept by the drop records) and of the LinBank equations.
+    ///
+    /// The new variables don't appear anywhere, so they are free. Nothing is done if `nvar`
+    /// is not above the current `nvar`.
+    pub fn grow_nvar(&mut self, nvar: usize) {
+        self.forget_solutions();
+        if nvar <= self.nvar {
+            return;
+        }
+        let bdds = self
+            .bdds
+            .values_mut()
+            .map(|bdd| bdd.get_mut())
+            .chain(self.drop_records.iter_mut().map(|record| &mut record.bdd));
+        for bdd in bdds {
+            for level_index in 0..bdd.get_levels_size() {
+                bdd.set_lhs_level(level_index, vec![], nvar);
+            }
+        }
+        for lin_eq in self.lin_bank.lin_eqs.iter_mut() {
+            let mut lhs = lin_eq.get_lhs();
+            lhs.resize(nvar, false);
+            *lin_eq = LinEq::new(lhs, lin_eq.get_rhs());
+        }
+        self.nvar = nvar;
+    }
+
+    /// Set the maximum number of nodes a single `Bdd` of the `System` may reach.
+    ///
+    /// When set, `join_bdds`, `swap` and `add` will refuse to produce a `Bdd` bigger than
+    /// `max_bdd_nodes` and return `CrushError::NodeBudget` instead, leaving
+    /// the `System` as it was before the operation. `None` (the default) means no limit.
+    ///
+    /// The size of a joined `Bdd` is known beforehand so `join_bdds` fails before doing anything,
+    /// but for `swap` and `add` the check is made once the operation is done and then reverted.
+    /// The memory needed by a single operation (and by reverting it) is therefore not bounded,
+    /// the budget only stops the solving before the next one.
+    pub fn set_max_bdd_nodes(&mut self, max_bdd_nodes: Option<usize>) {
+        self.max_bdd_nodes = max_bdd_nodes;
+    }
+
+    /// Get the number of times a `Bdd` was gone through to replace fixed variables by
+    /// linear combinations, which is what makes fixing expensive.
+    pub fn get_replace_passes(&self) -> usize {
+        self.replace_passes
+    }
+
+    /// Get the maximum number of nodes a single `Bdd` of the `System` may reach.
+    pub fn get_max_bdd_nodes(&self) -> Option<usize> {
+        self.max_bdd_nodes
+    }
+
+    /// Return an `Error` if `size` is above the node budget of the `System`.
+    fn check_node_budget(&self, size: usize) -> Result<(), CrushError> {
+        match self.max_bdd_nodes {
+            Some(max_bdd_nodes) if size > max_bdd_nodes => {
+                Err(CrushError::NodeBudget)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Push a `Bdd` in the system, a copy of it being kept in the operation log if recording is
+    /// enabled.
     ///
     /// Return an `Error` if the `nvar` of the `Bdd` is different from the `nvar` of the `System`, or
     /// if a `Bdd` with the same `id` was already present in the system
-    pub fn push_bdd(&mut self, bdd: Bdd) -> Result<(), Error> {
+    pub fn push_bdd(&mut self, bdd: Bdd) -> Result<(), CrushError> {
+        self.forget_solutions();
         if bdd.get_nvar_size() != self.nvar {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "Bdd have different nvar size from system",
-            ));
+            return Err(CrushError::NvarMismatch {
+                expected: self.nvar,
+                found: bdd.get_nvar_size(),
+            });
         }
         if self.get_bdd(bdd.get_id()).is_ok() {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "A Bdd with the same id is already in the system",
-            ));
+            return Err(CrushError::DuplicateBdd(bdd.get_id()));
+        }
+        if self.record_ops {
+            self.record(SystemOp::PushBdd { bdd: bdd.clone() });
         }
         self.bdds.insert(bdd.get_id(), RefCell::new(bdd));
         Ok(())
@@ -132,14 +305,13 @@ impl System {
 
     /// Return a reference to the `Bdd` which `id` is equal to `bdd_id`.
     ///
-    /// Will return an `Error` if there is no `Bdd` matching this condition.
-    pub fn get_bdd(&self, bdd_id: Id) -> Result<&RefCell<Bdd>, Error> {
+    /// Will return an `Error` if there is no `Bdd` matching this condition. The `Bdd` should
+    /// only be changed through the operations of the `System`, which forget the solutions cached
+    /// by `get_solutions`.
+    pub fn get_bdd(&self, bdd_id: Id) -> Result<&RefCell<Bdd>, CrushError> {
         match self.bdds.get(&bdd_id) {
             Some(bdd) => Ok(bdd),
-            None => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("id {} not present in system", bdd_id),
-            )),
+            None => Err(CrushError::BddNotFound(bdd_id)),
         }
     }
 
@@ -147,21 +319,108 @@ impl System {
     /// are contains in `ids` and returning a new `System` made of those `Bdd`.
     ///
     /// Will return an `Error` if one `Id` in `ids` doesn't match any `Bdd` in the `system`.
-    pub fn split(&mut self, ids: &[Id]) -> Result<System, Error> {
+    /// The removal of each `Bdd` is logged as a `pop_bdd`.
+    pub fn split(&mut self, ids: &[Id]) -> Result<System, CrushError> {
+        self.forget_solutions();
         let mut bdds = Vec::with_capacity(ids.len());
         for id in ids {
             bdds.push(self.pop_bdd(*id)?);
         }
         let mut sys = System::from_elem(bdds)?;
         sys.lin_bank = self.lin_bank.clone();
+        sys.record_drops = self.record_drops;
+        sys.max_bdd_nodes = self.max_bdd_nodes;
         Ok(sys)
     }
 
+    /// Group the ids of the `Bdd`s of the `System` in its connected components: two `Bdd`s are in
+    /// the same component if they share a variable, directly or through other `Bdd`s of the
+    /// component.
+    ///
+    /// The components don't share any variable, so each of them can be `split` and solved on its
+    /// own (see `combine_solutions`). The ids of a component are sorted, and the components are
+    /// sorted by their smallest id.
+    pub fn connected_components(&self) -> Vec<Vec<Id>> {
+        let mut ids: Vec<Id> = self.bdds.keys().cloned().collect();
+        ids.sort();
+        // the component of each bdd, as an index in ids of a bdd of the same component
+        let mut parent: Vec<usize> = (0..ids.len()).collect();
+        fn root(parent: &[usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                i = parent[i];
+            }
+            i
+        }
+        let mut owner: Vec<Option<usize>> = vec![None; self.nvar];
+        for (i, id) in ids.iter().enumerate() {
+            for lhs in self.bdds[id].borrow().get_lhs().iter() {
+                for var in lhs.iter_set_bits(..) {
+                    match owner[var] {
+                        Some(j) => {
+                            let (root_i, root_j) = (root(&parent, i), root(&parent, j));
+                            // keep the smallest index as root, so components stay sorted
+                            parent[root_i.max(root_j)] = root_i.min(root_j);
+                        }
+                        None => owner[var] = Some(i),
+                    }
+                }
+            }
+        }
+        let mut components: Vec<Vec<Id>> = Vec::new();
+        let mut component_of_root: Vec<Option<usize>> = vec![None; ids.len()];
+        for (i, id) in ids.iter().enumerate() {
+            let r = root(&parent, i);
+            match component_of_root[r] {
+                Some(c) => components[c].push(*id),
+                None => {
+                    component_of_root[r] = Some(components.len());
+                    components.push(vec![*id]);
+                }
+            }
+        }
+        components
+    }
+
+    /// Combine the solutions of the `System`s split from `self` along its connected components
+    /// (see `connected_components`) into the solutions of `self`.
+    ///
+    /// There is one solution for each choice of a solution in every element of `parts`, made of
+    /// the values set by the chosen solutions and completed with the `LinBank` of `self`.
+    pub fn combine_solutions(&self, parts: &[Vec<Vec<Option<bool>>>]) -> Vec<Vec<Option<bool>>> {
+        let mut lin_banks = vec![self.lin_bank.clone()];
+        for part in parts.iter() {
+            let mut next = Vec::with_capacity(lin_banks.len() * part.len());
+            for lin_bank in lin_banks.iter() {
+                for solution in part.iter() {
+                    let mut lin_bank = lin_bank.clone();
+                    for (var, value) in solution.iter().enumerate() {
+                        if let Some(value) = value {
+                            let mut lhs = Vob::from_elem(self.nvar, false);
+                            lhs.set(var, true);
+                            lin_bank.push_lin_eq(LinEq::new(lhs, *value));
+                        }
+                    }
+                    next.push(lin_bank);
+                }
+            }
+            lin_banks = next;
+        }
+        lin_banks
+            .into_iter()
+            .map(|lin_bank| {
+                algebra::solve_linear_system(matrix![lin_bank.get_lhs()], lin_bank.get_rhs())
+            })
+            .collect()
+    }
+
     /// Merge a `System` into the current one by pushing all the non-empty `Bdd`
     /// in the `System` and all the `LinEq` of the `LinBank`.
     ///
     /// Will return an error if one of the `Bdd` has a different `nvar` from the `System`.
-    pub fn merge(&mut self, system: &mut System) -> Result<(()), Error> {
+    /// The merge is logged as a `push_bdd` for each `Bdd` and a `fix` for each `LinEq` added.
+    pub fn merge(&mut self, system: &mut System) -> Result<(()), CrushError> {
+        self.forget_solutions();
+        system.forget_solutions();
         for bdd in system.drain_bdds() {
             // TODO -> error handling should take into account middle crash and rollback system to its initial state
             // to avoid half merging if one bdd have a different nvar
@@ -169,9 +428,16 @@ impl System {
                 self.push_bdd(bdd.1.into_inner())?;
             }
         }
-        for lin_eq in system.lin_bank.lin_eqs.drain(..) {
-            self.push_lin_eq_to_lin_bank(lin_eq);
+        for lin_eq in std::mem::take(&mut system.lin_bank.lin_eqs) {
+            if let Some(eq) = self.push_lin_eq_to_lin_bank(lin_eq)? {
+                let lhs = eq.get_lhs().iter_set_bits(..).collect();
+                self.record(SystemOp::Fix {
+                    lhs,
+                    rhs: eq.get_rhs(),
+                });
+            }
         }
+        self.drop_records.append(&mut system.drop_records);
         Ok(())
     }
 
@@ -179,79 +445,162 @@ impl System {
     ///
     /// The `bdd_1_id` will be the `id` of the resulting `Bdd`
     ///
+    /// A `Bdd` reduced to its sink holds no equation: joining with it leaves the levels of the
+    /// other `Bdd` untouched, and joining two of them leaves a single sink.
+    ///
     /// Returns the `bdd_1_id` if successfull, or an `Error` if
-    /// `bdd_id_1` and `bdd_id_2` are equals or one is not found in the
-    /// `System`.
-    pub fn join_bdds(&mut self, bdd_1_id: Id, bdd_2_id: Id) -> Result<Id, Error> {
+    /// `bdd_id_1` and `bdd_id_2` are equals, one is not found in the
+    /// `System` or the joined `Bdd` would exceed the node budget.
+    pub fn join_bdds(&mut self, bdd_1_id: Id, bdd_2_id: Id) -> Result<Id, CrushError> {
+        self.forget_solutions();
         if bdd_1_id == bdd_2_id {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "bdd_1_id is equal to bdd_2_id",
-            ));
+            return Err(CrushError::SameBdd(bdd_1_id));
         }
         let bdd_1 = self.get_bdd(bdd_1_id)?;
         let bdd_2 = self.get_bdd(bdd_2_id)?;
+        // a sink-only bdd holds no equation, joining with it only removes it
+        if bdd_2.borrow().get_sink_level_index() == 0 {
+            self.bdds.remove(&bdd_2_id);
+            self.record(SystemOp::JoinBdds { bdd_1_id, bdd_2_id });
+            return Ok(bdd_1_id);
+        }
+        if bdd_1.borrow().get_sink_level_index() == 0 {
+            bdd_1.borrow_mut().drain_levels();
+            for level in bdd_2.borrow_mut().drain_levels() {
+                bdd_1.borrow_mut().add_existing_level(level)
+            }
+            self.bdds.remove(&bdd_2_id);
+            self.record(SystemOp::JoinBdds { bdd_1_id, bdd_2_id });
+            return Ok(bdd_1_id);
+        }
+        // the sink of bdd_1 and the source of bdd_2 are merged into a single level
+        self.check_node_budget(
+            (bdd_1.borrow().get_size() + bdd_2.borrow().get_size()).saturating_sub(1),
+        )?;
         let sink_level_id = bdd_1.borrow().get_sink_level_index();
         for level in bdd_2.borrow_mut().drain_levels() {
             bdd_1.borrow_mut().add_existing_level(level)
         }
         bdd_1.borrow_mut().merge_sink_source(sink_level_id);
         self.bdds.remove(&bdd_2_id);
+        self.record(SystemOp::JoinBdds { bdd_1_id, bdd_2_id });
         Ok(bdd_1_id)
     }
 
     /// Performs a `swap` operation on the `Bdd` with the `id` specified between the 2 level indexes given.
     ///
     /// Returns an `Error` if `level_index_above` is not directly above `level_index_below`, if
-    /// `level_index_below` is out of the range of the levels the `Bdd`, or if `bdd_id` is not found in the `System`..
+    /// `level_index_below` is out of the range of the levels the `Bdd`, if `bdd_id` is not found in the `System`,
+    /// or if the `Bdd` exceeds the node budget once the swap is done (the swap is then reverted).
     pub fn swap(
         &mut self,
         bdd_id: Id,
         level_index_above: usize,
         level_index_below: usize,
-    ) -> Result<(), Error> {
+    ) -> Result<(), CrushError> {
+        self.forget_solutions();
         if level_index_below != level_index_above + 1 {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "Level 1 is not directly above Level 2",
-            ));
+            return Err(CrushError::NotAbove {
+                above: level_index_above,
+                below: level_index_below,
+            });
         }
         let bdd = self.get_bdd(bdd_id)?;
-        if level_index_below >= bdd.borrow().get_sink_level_index() {
-            return Err(Error::new(ErrorKind::InvalidData, "Out of range of levels"));
+        let sink_level_index = bdd.borrow().get_sink_level_index();
+        if level_index_below >= sink_level_index {
+            return Err(CrushError::LevelOutOfRange {
+                level: level_index_below,
+                sink: sink_level_index,
+            });
         }
         bdd.borrow_mut().swap(level_index_above, level_index_below);
+        let size = bdd.borrow().get_size();
+        if let Err(e) = self.check_node_budget(size) {
+            // swapping twice the same levels gives back the original bdd
+            bdd.borrow_mut().swap(level_index_above, level_index_below);
+            return Err(e);
+        }
+        self.record(SystemOp::Swap {
+            bdd_id,
+            above: level_index_above,
+            below: level_index_below,
+        });
+        Ok(())
+    }
+
+    /// Performs a `move_level` operation on the `Bdd` with the `id` specified, moving the level at
+    /// index `from` to the index `to`.
+    ///
+    /// Returns an `Error` if `from` or `to` is out of the range of the levels of the `Bdd`, if `bdd_id`
+    /// is not found in the `System`, or if the `Bdd` exceeds the node budget once the move is
+    /// done (the move is then reverted).
+    pub fn move_level(&mut self, bdd_id: Id, from: usize, to: usize) -> Result<(), CrushError> {
+        self.forget_solutions();
+        let bdd = self.get_bdd(bdd_id)?;
+        let sink_level_index = bdd.borrow().get_sink_level_index();
+        if from >= sink_level_index || to >= sink_level_index {
+            return Err(CrushError::LevelOutOfRange {
+                level: from.max(to),
+                sink: sink_level_index,
+            });
+        }
+        if from == to {
+            return Ok(());
+        }
+        bdd.borrow_mut().move_level(from, to);
+        let size = bdd.borrow().get_size();
+        if let Err(e) = self.check_node_budget(size) {
+            // moving the level back undoes the swaps in reverse order
+            bdd.borrow_mut().move_level(to, from);
+            return Err(e);
+        }
+        self.record(SystemOp::MoveLevel { bdd_id, from, to });
         Ok(())
     }
 
     /// Performs a `add` operation on the `Bdd` with the `id` specified between the 2 level indexes given.
     ///
     /// Returns an `Error` if `level_index_above` is not directly above `level_index_below`, if
-    /// `level_index_below` is out of the range of the levels the `Bdd`, or if `bdd_id` is not found in the `System`.
+    /// `level_index_below` is out of the range of the levels the `Bdd`, if `bdd_id` is not found in the `System`,
+    /// or if the `Bdd` exceeds the node budget once the add is done (the add is then reverted).
     pub fn add(
         &mut self,
         bdd_id: Id,
         level_index_above: usize,
         level_index_below: usize,
-    ) -> Result<(), Error> {
+    ) -> Result<(), CrushError> {
+        self.forget_solutions();
         if level_index_above >= level_index_below {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "Level above is not above Level below",
-            ));
+            return Err(CrushError::NotAbove {
+                above: level_index_above,
+                below: level_index_below,
+            });
         }
         let bdd = self.get_bdd(bdd_id)?;
-        if level_index_below >= bdd.borrow().get_sink_level_index() {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "Out of range of levels : trying to add on level {}, sink level is {}",
-                    level_index_below,
-                    bdd.borrow().get_sink_level_index()
-                ),
-            ));
+        let sink_level_index = bdd.borrow().get_sink_level_index();
+        if level_index_below >= sink_level_index {
+            return Err(CrushError::LevelOutOfRange {
+                level: level_index_below,
+                sink: sink_level_index,
+            });
         }
         bdd.borrow_mut().add(level_index_above, level_index_below);
+        let size = bdd.borrow().get_size();
+        if let Err(e) = self.check_node_budget(size) {
+            // the level above was swapped down to be right above the level below before adding,
+            // adding it again and swapping it back up gives back the original bdd
+            let mut bdd = bdd.borrow_mut();
+            bdd.add(level_index_below - 1, level_index_below);
+            if level_index_below - 1 != level_index_above {
+                bdd.move_level(level_index_below - 1, level_index_above);
+            }
+            return Err(e);
+        }
+        self.record(SystemOp::Add {
+            bdd_id,
+            above: level_index_above,
+            below: level_index_below,
+        });
         Ok(())
     }
 
@@ -259,39 +608,214 @@ impl System {
     ///
     /// Returns an `Error` if `level_index` is out of the range of the levels the `Bdd`, or
     /// if `bdd_id` is not found in the `System`.
-    pub fn absorb(&mut self, bdd_id: Id, level_index: usize, edge: bool) -> Result<(), Error> {
+    pub fn absorb(&mut self, bdd_id: Id, level_index: usize, edge: bool) -> Result<(), CrushError> {
+        self.forget_solutions();
         let bdd = self.get_bdd(bdd_id)?;
-        if level_index >= bdd.borrow().get_sink_level_index() {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "Out of range of levels : trying to absorb {}, sink level is {}",
-                    level_index,
-                    bdd.borrow().get_sink_level_index()
-                ),
-            ));
+        let sink_level_index = bdd.borrow().get_sink_level_index();
+        if level_index >= sink_level_index {
+            return Err(CrushError::LevelOutOfRange {
+                level: level_index,
+                sink: sink_level_index,
+            });
         }
-        bdd.borrow_mut().absorb(level_index, edge);
+        bdd.borrow_mut().absorb(level_index, edge)?;
+        self.record(SystemOp::Absorb {
+            bdd_id,
+            level_index,
+            edge,
+        });
         Ok(())
     }
 
     /// Performs a `drop` operation on the `Bdd` with the `id` specified on `level_index`.
     ///
+    /// If drop recording is enabled (see `set_drop_recording`) a `DropRecord` is kept
+    /// so the dropped variables can be recovered with `recover_dropped`.
+    ///
     /// Returns an `Error` if `level_index` is out of the range of the levels the `Bdd`, or
     /// if `bdd_id` is not found in the `System`.
-    pub fn drop(&mut self, bdd_id: Id, level_index: usize) -> Result<(), Error> {
+    pub fn drop(&mut self, bdd_id: Id, level_index: usize) -> Result<(), CrushError> {
+        self.forget_solutions();
         let bdd = self.get_bdd(bdd_id)?;
-        if level_index >= bdd.borrow().get_sink_level_index() {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "Out of range of levels : trying to drop {}, sink level is {}",
+        let sink_level_index = bdd.borrow().get_sink_level_index();
+        if level_index >= sink_level_index {
+            return Err(CrushError::LevelOutOfRange {
+                level: level_index,
+                sink: sink_level_index,
+            });
+        }
+        if self.record_drops {
+            let record = DropRecord {
+                bdd: bdd.borrow().clone(),
+                level_index,
+            };
+            self.drop_records.push(record);
+        }
+        self.get_bdd(bdd_id)?.borrow_mut().drop(level_index);
+        self.record(SystemOp::Drop {
+            bdd_id,
+            level_index,
+        });
+        Ok(())
+    }
+
+    /// Drop the variable `var` from the `System`, the same way an `Independency` is resolved by a
+    /// `DroppingSolver`: the `Bdd`s containing `var` are joined (in the order of their ids), the
+    /// first level containing `var` is added to every other one until a single level holds it,
+    /// and this level is dropped.
+    ///
+    /// Returns an `Error` if `var` is not in the range of the `System` or doesn't appear in any
+    /// `Bdd`, or if one of the operations fails (the `System` is then left as it was at the time
+    /// of the failure).
+    pub fn drop_variable(&mut self, var: usize) -> Result<(), CrushError> {
+        if var >= self.nvar {
+            return Err(CrushError::VarOutOfRange(var));
+        }
+        let mut ids: Vec<Id> = self
+            .bdds
+            .iter()
+            .filter(|(_, bdd)| bdd.borrow().iter_levels().any(|level| level.is_var_set(var)))
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort();
+        let bdd_root_id = match ids.first() {
+            Some(id) => *id,
+            None => return Err(CrushError::VarNotFound(var)),
+        };
+        for id in ids.iter().skip(1) {
+            self.join_bdds(bdd_root_id, *id)?;
+        }
+        let levels: Vec<usize> = self
+            .get_bdd(bdd_root_id)?
+            .borrow()
+            .iter_levels()
+            .enumerate()
+            .filter(|(_, level)| level.is_var_set(var))
+            .map(|(i, _)| i)
+            .collect();
+        for i in 0..levels.len() - 1 {
+            self.add(bdd_root_id, levels[i], levels[i + 1])?;
+            self.swap(bdd_root_id, levels[i + 1] - 1, levels[i + 1])?;
+        }
+        self.drop(bdd_root_id, *levels.last().unwrap())
+    }
+
+    /// Enable or disable the recording of the `drop` operations.
+    ///
+    /// Recording is disabled by default as each record holds a copy of the dropped `Bdd`.
+    pub fn set_drop_recording(&mut self, record_drops: bool) {
+        self.record_drops = record_drops;
+    }
+
+    /// Return the `DropRecord`s kept since recording was enabled, in the order of the drops.
+    pub fn get_drop_records(&self) -> &[DropRecord] {
+        &self.drop_records
+    }
+
+    /// Enable or disable the recording of the operations done on the `System` (`swap`,
+    /// `move_level`, `add`, `absorb`, `drop`, `join_bdds`, `fix`, `fix_many`,
+    /// `scan_absorb_lin_eqs`, `pop_bdd` and `push_bdd`) in its operation log, only the successful
+    /// ones being kept. The methods built on them (ex: `drop_variable`, `get_solutions`, `split`,
+    /// `merge` or `drain_bdds`) are logged as the operations they are made of.
+    ///
+    /// Recording is disabled by default, the log is meant to replay a solving (see `replay`).
+    pub fn set_op_recording(&mut self, record_ops: bool) {
+        self.record_ops = record_ops;
+    }
+
+    /// Return the operations recorded since recording was enabled, in the order they were done.
+    pub fn op_log(&self) -> &[SystemOp] {
+        &self.op_log
+    }
+
+    /// Apply the operations of `ops` (ex: the operation log of another `System`) in order to
+    /// `initial` and return the resulting `System`.
+    ///
+    /// Replaying the log of a `System` on a copy of the state it had when recording was
+    /// enabled gives back its current state. Returns the `Error` of the first operation which
+    /// fails.
+    pub fn replay(ops: &[SystemOp], mut initial: System) -> Result<System, CrushError> {
+        for op in ops.iter() {
+            match op {
+                SystemOp::Swap {
+                    bdd_id,
+                    above,
+                    below,
+                } => initial.swap(*bdd_id, *above, *below)?,
+                SystemOp::MoveLevel { bdd_id, from, to } => {
+                    initial.move_level(*bdd_id, *from, *to)?
+                }
+                SystemOp::Add {
+                    bdd_id,
+                    above,
+                    below,
+                } => initial.add(*bdd_id, *above, *below)?,
+                SystemOp::Absorb {
+                    bdd_id,
                     level_index,
-                    bdd.borrow().get_sink_level_index()
-                ),
-            ));
+                    edge,
+                } => initial.absorb(*bdd_id, *level_index, *edge)?,
+                SystemOp::Drop {
+                    bdd_id,
+                    level_index,
+                } => initial.drop(*bdd_id, *level_index)?,
+                SystemOp::JoinBdds { bdd_1_id, bdd_2_id } => {
+                    initial.join_bdds(*bdd_1_id, *bdd_2_id)?;
+                }
+                SystemOp::Fix { lhs, rhs } => initial.fix(lhs.clone(), *rhs)?,
+                SystemOp::FixMany { eqs } => {
+                    initial.fix_many(eqs)?;
+                }
+                SystemOp::ScanAbsorbLinEqs { bdd_id } => {
+                    initial.scan_absorb_lin_eqs(*bdd_id)?;
+                }
+                SystemOp::PopBdd { bdd_id } => {
+                    initial.pop_bdd(*bdd_id)?;
+                }
+                SystemOp::PushBdd { bdd } => initial.push_bdd(bdd.clone())?,
+            }
+        }
+        Ok(initial)
+    }
+
+    /// Forget the solutions cached by `get_solutions`, called by every operation which may
+    /// change them.
+    fn forget_solutions(&self) {
+        self.solutions.replace(None);
+    }
+
+    /// Push `op` to the operation log if recording is enabled (see `set_op_recording`).
+    fn record(&mut self, op: SystemOp) {
+        if self.record_ops {
+            self.op_log.push(op);
+        }
+    }
+
+    /// Fill in `solution` the variables lost by the recorded `drop` operations.
+    ///
+    /// The records are replayed from the last drop to the first. For each one we look
+    /// for a path in the recorded `Bdd` consistent with the known values of `solution`,
+    /// the unknown variables appearing on this path are then set accordingly.
+    ///
+    /// This means every unknown variable appearing in the lhs of a level of a recorded `Bdd`
+    /// gets a value, not only the dropped ones: the value of a dropped variable may depend on
+    /// them. Those left free by the path are set to false. Unknown variables which don't appear
+    /// in any recorded `Bdd` are left untouched.
+    ///
+    /// Returns an `Error` if `solution` is not of length `nvar` or if no such path exists for
+    /// one of the records, meaning `solution` is not a solution of the `System`.
+    pub fn recover_dropped(&self, solution: &mut [Option<bool>]) -> Result<(), CrushError> {
+        if solution.len() != self.nvar {
+            return Err(CrushError::SolutionLength {
+                expected: self.nvar,
+                found: solution.len(),
+            });
+        }
+        for record in self.drop_records.iter().rev() {
+            if !record.complete(solution) {
+                return Err(CrushError::NoConsistentPath(record.bdd.get_id()));
+            }
         }
-        bdd.borrow_mut().drop(level_index);
         Ok(())
     }
 
@@ -304,54 +828,253 @@ impl System {
     /// fix(vec![1,2,3], true) -> x1 + x2 + x3 = 1;
     /// ```
     ///
-    /// Return an `Error` if the fix was not linearly independant from the LinBank.
-    pub fn fix(&mut self, lhs: Vec<usize>, rhs: bool) -> Result<(), io::Error> {
+    /// A fix already implied by the LinBank (it reduces to `0 = 0`) is redundant and nothing is
+    /// added. Return an `Error` if the fix contradicts the LinBank (it reduces to `0 = 1`).
+    ///
+    /// A variable beyond `nvar` (ex: an auxiliary variable added after the `System` was built)
+    /// first grows the `System` to include it (see `grow_nvar`).
+    pub fn fix(&mut self, lhs: Vec<usize>, rhs: bool) -> Result<(), CrushError> {
+        if let Some(max_var) = lhs.iter().max() {
+            self.grow_nvar(max_var + 1);
+        }
         let mut lhs_as_vob = Vob::new();
         lhs_as_vob.resize(self.nvar, false);
         for var in lhs.iter() {
             lhs_as_vob.set(*var, true);
         }
-        let lin_eq = LinEq::new(lhs_as_vob, rhs);
-        match self.push_lin_eq_to_lin_bank(lin_eq) {
-            Some(_) => Ok(()),
-            None => Err(Error::new(
-                ErrorKind::InvalidData,
-                "linear equation non linearly independant from current LinBank",
-            )),
+        let lin_eq = self.lin_bank.reduce(LinEq::new(lhs_as_vob, rhs));
+        if lin_eq.get_lhs_max_set_bit().is_none() {
+            if lin_eq.get_rhs() {
+                return Err(CrushError::Contradiction);
+            }
+        } else {
+            self.push_lin_eq_to_lin_bank(lin_eq)?;
         }
+        self.record(SystemOp::Fix { lhs, rhs });
+        Ok(())
+    }
+
+    /// Fix the value of several linear combinations of variables at once (see `fix`).
+    ///
+    /// All the equations are pushed to the LinBank (in order, exactly as a sequence of `fix`)
+    /// before the `Bdd`s are updated, then each `Bdd` is gone through a single time to
+    /// replace all the newly resolved variables.
+    ///
+    /// Return the number of equations added, the redundant ones (reducing to `0 = 0`) being
+    /// skipped. A variable beyond `nvar` grows the `System` as in `fix`.
+    ///
+    /// Return an `Error` without changing the `System` if an equation contradicts the LinBank
+    /// or the equations before it (it reduces to `0 = 1`).
+    pub fn fix_many(&mut self, eqs: &[(Vec<usize>, bool)]) -> Result<usize, CrushError> {
+        let nvar = match eqs.iter().flat_map(|(lhs, _)| lhs).max() {
+            Some(max_var) => self.nvar.max(max_var + 1),
+            None => self.nvar,
+        };
+        let mut lin_bank = self.lin_bank.clone();
+        for lin_eq in lin_bank.lin_eqs.iter_mut() {
+            let mut lhs = lin_eq.get_lhs();
+            lhs.resize(nvar, false);
+            *lin_eq = LinEq::new(lhs, lin_eq.get_rhs());
+        }
+        let mut pushed: Vec<(usize, LinEq)> = Vec::with_capacity(eqs.len());
+        for (lhs, rhs) in eqs.iter() {
+            let mut lhs_as_vob = Vob::from_elem(nvar, false);
+            for var in lhs.iter() {
+                lhs_as_vob.set(*var, true);
+            }
+            let eq = lin_bank.reduce(LinEq::new(lhs_as_vob, *rhs));
+            match eq.get_lhs_max_set_bit() {
+                Some(var) => {
+                    lin_bank.lin_eqs.push(eq.clone());
+                    pushed.push((var, eq));
+                }
+                None if eq.get_rhs() => return Err(CrushError::Contradiction),
+                None => (),
+            }
+        }
+        self.grow_nvar(nvar);
+        self.forget_solutions();
+        self.lin_bank = lin_bank;
+        // an equation only contains the variables resolved after it, remove them
+        // starting from the last one so that no resolved variable is left
+        for j in (0..pushed.len()).rev() {
+            let (var, eq) = pushed[j].clone();
+            for (_, earlier) in pushed.iter_mut().take(j) {
+                if earlier.get_lhs()[var] {
+                    earlier.add_lin_eq(&eq);
+                }
+            }
+        }
+        if !pushed.is_empty() {
+            for bdd in self.bdds.iter_mut() {
+                bdd.1.borrow_mut().replace_vars_in_bdd(&pushed)?;
+                self.replace_passes += 1;
+            }
+        }
+        self.record(SystemOp::FixMany { eqs: eqs.to_vec() });
+        Ok(pushed.len())
+    }
+
+    /// Fix the variables `a` and `b` to be equal by adding `xa + xb = 0` to the LinBank,
+    /// which removes the higher of the two from all the `Bdd`s of the `System`.
+    ///
+    /// An equality already implied by the LinBank is redundant and nothing is added (see `fix`).
+    /// Return an `Error` if one of the variables is not in the `System` or if `a == b`.
+    pub fn fix_equal(&mut self, a: usize, b: usize) -> Result<(), CrushError> {
+        if a >= self.nvar || b >= self.nvar {
+            return Err(CrushError::VarOutOfRange(a.max(b)));
+        }
+        if a == b {
+            return Err(CrushError::LinearlyDependent);
+        }
+        self.fix(vec![a, b], false)
+    }
+
+    /// Add externally derived linear constraints to the `System`, each constraint being the
+    /// variables of its left hand side and its right hand side (see `fix`).
+    ///
+    /// Return for each constraint whether it was added (`true`) or ignored because it was
+    /// linearly dependent from the LinBank and the previous constraints (`false`).
+    /// Return an `Error` without adding anything if a variable is not in the `System` or if a
+    /// constraint contradicts the LinBank and the previous constraints.
+    pub fn add_linear_constraints(
+        &mut self,
+        constraints: &[(Vec<usize>, bool)],
+    ) -> Result<Vec<bool>, CrushError> {
+        let mut lin_bank = self.lin_bank.clone();
+        for (i, (lhs, rhs)) in constraints.iter().enumerate() {
+            if let Some(var) = lhs.iter().find(|var| **var >= self.nvar) {
+                return Err(CrushError::VarOutOfRange(*var));
+            }
+            let mut lhs_as_vob = Vob::from_elem(self.nvar, false);
+            for var in lhs.iter() {
+                lhs_as_vob.set(*var, !lhs_as_vob[*var]);
+            }
+            if !lin_bank.push_consistent_lin_eq(LinEq::new(lhs_as_vob, *rhs)) {
+                return Err(CrushError::Inconsistent(i));
+            }
+        }
+        Ok(constraints
+            .iter()
+            .map(|(lhs, rhs)| {
+                let size = self.get_lin_bank_size();
+                self.fix(lhs.clone(), *rhs).is_ok() && self.get_lin_bank_size() > size
+            })
+            .collect())
     }
 
     /// Scan the `Bdd` of `bdd_id` for `LinEq` and push the `LinEq`s found to the `LinBank`
     ///
     /// Returns the number of `LinEq` correctly absorbed or an `Error` if `bdd_id` is not in the
-    /// `System`.
-    pub fn scan_absorb_lin_eqs(&mut self, bdd_id: Id) -> Result<usize, io::Error> {
+    /// `System`, or if the absorption leaves a `Bdd` without solution (`CrushError::NoSolution`).
+    pub fn scan_absorb_lin_eqs(&mut self, bdd_id: Id) -> Result<usize, CrushError> {
+        self.forget_solutions();
         let mut absorbed = 0;
         let bdd = self.get_bdd(bdd_id)?;
-        let mut lin_eqs = bdd.borrow_mut().scan_absorb_lin_eq();
+        let mut lin_eqs = bdd.borrow_mut().scan_absorb_lin_eq()?;
         for lin_eq in lin_eqs.drain(..) {
-            if self.push_lin_eq_to_lin_bank(lin_eq).is_some() {
+            if self.push_lin_eq_to_lin_bank(lin_eq)?.is_some() {
                 absorbed += 1;
             }
         }
+        self.record(SystemOp::ScanAbsorbLinEqs { bdd_id });
         Ok(absorbed)
     }
 
+    /// Return true if no linear dependency is left between the levels of the `Bdd`s, ie once a
+    /// solver would stop (the `Dependency`s of a `System` are built from these linear
+    /// dependencies). Meant for the callers stepping through the resolution themselves (see
+    /// `reduce_once`).
+    ///
+    /// The solutions of a solved `System` are the solutions of its `LinBank` extended by the
+    /// paths of the remaining `Bdd`s (see `get_solutions`).
+    pub fn is_solved(&self) -> bool {
+        let lhs: Vec<Vob> = self
+            .get_system_lhs()
+            .into_iter()
+            .flat_map(|(_, lhs)| lhs)
+            .collect();
+        algebra::extract_linear_dependencies(matrix![lhs]).row_size() == 0
+    }
+
+    /// Run one iteration of a solver on the `System`: absorb the linear equations, extract the
+    /// dependencies, resolve the best one (the one with the lowest `minimize_distance`) and
+    /// absorb the linear equations it produced.
+    ///
+    /// Returns `None` once no dependency is left, in which case `get_solutions` gives the
+    /// solutions of the `System`. Meant to follow the resolution step by step, the solvers of
+    /// `solver` should be used to solve a `System`.
+    pub fn reduce_once<D: Dependency>(&mut self) -> Result<Option<StepReport>, CrushError> {
+        let mut report = StepReport::default();
+        report.lin_eqs_found += self.absorb_all_lin_eqs()?;
+        let deps = D::extract(self);
+        let best = match deps.iter().min_by_key(|dep| dep.minimize_distance()) {
+            Some(dep) => dep,
+            None => return Ok(None),
+        };
+        let (ids, levels) = best.best_join_order();
+        let root = ids[0];
+        for id in ids.iter().skip(1) {
+            self.join_bdds(root, *id)?;
+        }
+        for i in (0..levels.len() - 1).rev() {
+            self.move_level(root, levels[i + 1], levels[i] + 1)?;
+            self.add(root, levels[i], levels[i] + 1)?;
+            report.levels_moved += 1;
+            report.levels_added += 1;
+            if i != 0 {
+                self.swap(root, levels[i], levels[i] + 1)?;
+                report.levels_moved += 1;
+            }
+        }
+        self.absorb(root, levels[0] + 1, false)?;
+        report.joined = ids;
+        report.lin_eqs_found += self.absorb_all_lin_eqs()?;
+        Ok(Some(report))
+    }
+
+    /// Scan all the `Bdd`s for linear equations until none is left, removing the `Bdd`s reduced
+    /// to their sink. Returns the number of `LinEq` pushed to the `LinBank`.
+    fn absorb_all_lin_eqs(&mut self) -> Result<usize, CrushError> {
+        let mut total = 0;
+        let mut absorbed = true;
+        while absorbed {
+            absorbed = false;
+            let ids: Vec<Id> = self.bdds.keys().cloned().collect();
+            for id in ids.iter() {
+                let found = self.scan_absorb_lin_eqs(*id)?;
+                if found > 0 {
+                    absorbed = true;
+                    total += found;
+                }
+            }
+            for id in ids.iter() {
+                if self.get_bdd(*id)?.borrow().get_sink_level_index() == 0 {
+                    self.pop_bdd(*id)?;
+                }
+            }
+        }
+        Ok(total)
+    }
+
     /// Attempt to push the `LinEq` to the `LinBank` and if successfull remove the higher
     /// variable of the  modified `LinEq` from the whole `System`.
     ///
     /// Return `Some(modified lin_eq)` if successfull or `None` if `lin_eq` was not linearly
-    /// independant from the `LinBank`.
-    fn push_lin_eq_to_lin_bank(&mut self, lin_eq: LinEq) -> Option<LinEq> {
+    /// independant from the `LinBank`, or an `Error` if replacing the variable leaves a `Bdd`
+    /// without solution (see `Bdd::replace_var_in_bdd`).
+    fn push_lin_eq_to_lin_bank(&mut self, lin_eq: LinEq) -> Result<Option<LinEq>, CrushError> {
+        self.forget_solutions();
         match self.lin_bank.push_lin_eq(lin_eq) {
             Some(eq) => {
                 let var = eq.get_lhs_max_set_bit().unwrap();
                 for bdd in self.bdds.iter_mut() {
-                    bdd.1.borrow_mut().replace_var_in_bdd(var, &eq);
+                    bdd.1.borrow_mut().replace_var_in_bdd(var, &eq)?;
+                    self.replace_passes += 1;
                 }
-                Some(eq)
+                Ok(Some(eq))
             }
-            None => None,
+            None => Ok(None),
         }
     }
 
@@ -362,26 +1085,147 @@ impl System {
             .fold(0, |acc, bdd| acc + bdd.1.borrow().get_size())
     }
 
+    /// Return the id and the number of nodes of each `Bdd` of the `System`, sorted by id.
+    pub fn bdd_sizes(&self) -> Vec<(Id, usize)> {
+        let mut sizes: Vec<(Id, usize)> = self
+            .bdds
+            .iter()
+            .map(|(id, bdd)| (*id, bdd.borrow().get_size()))
+            .collect();
+        sizes.sort();
+        sizes
+    }
+
+    /// Return the id of the `Bdd` with the fewest nodes, the lowest id among the `Bdd`s of the
+    /// same size, or `None` if the `System` is empty.
+    pub fn smallest_bdd_id(&self) -> Option<Id> {
+        self.bdd_sizes()
+            .into_iter()
+            .min_by_key(|(id, size)| (*size, *id))
+            .map(|(id, _)| id)
+    }
+
+    /// Return the id of the `Bdd` with the most nodes, the lowest id among the `Bdd`s of the
+    /// same size, or `None` if the `System` is empty.
+    pub fn largest_bdd_id(&self) -> Option<Id> {
+        self.bdd_sizes()
+            .into_iter()
+            .min_by_key(|(id, size)| (std::cmp::Reverse(*size), *id))
+            .map(|(id, _)| id)
+    }
+
+    /// Count the `Bdd`s of the `System` sharing the same topology (see `Bdd::structural_hash`).
+    ///
+    /// This is a diagnostic of how much structure is repeated in the `System`, ex: the `Bdd`s
+    /// of the instances of a same S-Box.
+    pub fn count_structurally_identical(&self) -> HashMap<StructuralHash, usize> {
+        let mut counts = HashMap::new();
+        for (_, bdd) in self.bdds.iter() {
+            *counts.entry(bdd.borrow().structural_hash()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Count the levels of the `Bdd`s of the `System` which are already linear equations (see
+    /// `Bdd::count_linear_levels`), without absorbing them.
+    pub fn count_linear_levels(&self) -> usize {
+        self.bdds
+            .iter()
+            .fold(0, |acc, bdd| acc + bdd.1.borrow().count_linear_levels())
+    }
+
+    /// Compare the `System` with `other`, to see how a change of the solving modified it.
+    ///
+    /// The `Bdd`s are matched by id and compared on their number of nodes, the lhs of their
+    /// levels and their topology (see `Bdd::structural_hash`). The equations of the `LinBank`s
+    /// are compared as they are stored (reduced when pushed), so two `LinBank`s with the same
+    /// solutions pushed in a different order can differ.
+    pub fn diff(&self, other: &System) -> SystemDiff {
+        let mut diff = SystemDiff::default();
+        let other_lhs: AHashMap<Id, Vec<Vob>> = other.get_system_lhs().into_iter().collect();
+        for (id, lhs) in self.get_system_lhs() {
+            match other_lhs.get(&id) {
+                Some(other_lhs) => {
+                    let bdd = self.bdds[&id].borrow();
+                    let other_bdd = other.bdds[&id].borrow();
+                    if lhs != *other_lhs
+                        || bdd.get_size() != other_bdd.get_size()
+                        || bdd.structural_hash() != other_bdd.structural_hash()
+                    {
+                        diff.changed.push(id);
+                    }
+                }
+                None => diff.only_in_self.push(id),
+            }
+        }
+        diff.only_in_other = other
+            .bdds
+            .keys()
+            .filter(|id| !self.bdds.contains_key(id))
+            .cloned()
+            .collect();
+        let (key, other_key) = (self.lin_bank.key(), other.lin_bank.key());
+        diff.lin_eqs_only_in_self = key
+            .iter()
+            .filter(|lin_eq| !other_key.contains(lin_eq))
+            .cloned()
+            .collect();
+        diff.lin_eqs_only_in_other = other_key
+            .iter()
+            .filter(|lin_eq| !key.contains(lin_eq))
+            .cloned()
+            .collect();
+        diff.only_in_self.sort();
+        diff.only_in_other.sort();
+        diff.changed.sort();
+        diff
+    }
+
+    /// Count, for each variable of the `System`, the number of levels of its `Bdd`s whose lhs
+    /// contains the variable (the weights of the columns of the lhs of all the levels).
+    ///
+    /// The variables appearing in many levels are the hard ones to eliminate, this can be used to
+    /// guide the choice of the levels to drop.
+    pub fn variable_occurrences(&self) -> Vec<usize> {
+        let mut occurrences = vec![0; self.nvar];
+        for (_, bdd) in self.bdds.iter() {
+            for lhs in bdd.borrow().get_lhs().iter() {
+                lhs.iter_set_bits(..).for_each(|var| occurrences[var] += 1);
+            }
+        }
+        occurrences
+    }
+
     /// Iterate over the `bdds` of the `System`.
     pub fn iter_bdds(&self) -> std::collections::hash_map::Iter<Id, RefCell<Bdd>> {
         self.bdds.iter()
     }
 
-    /// Drain over the `bdds` of the `System`.
+    /// Drain over the `bdds` of the `System`, the removal of each `Bdd` being logged as a
+    /// `pop_bdd`.
     pub fn drain_bdds(&mut self) -> std::collections::hash_map::Drain<Id, RefCell<Bdd>> {
+        self.forget_solutions();
+        if self.record_ops {
+            let mut ids: Vec<Id> = self.bdds.keys().copied().collect();
+            ids.sort();
+            for bdd_id in ids {
+                self.record(SystemOp::PopBdd { bdd_id });
+            }
+        }
         self.bdds.drain()
     }
 
     /// Remove the `Bdd` of given index `bdd_id` from the `System` and returns it.
     ///
     /// Return an Error if `bdd_id` is not in the `System`.
-    pub fn pop_bdd(&mut self, bdd_id: Id) -> Result<Bdd, io::Error> {
+    pub fn pop_bdd(&mut self, bdd_id: Id) -> Result<Bdd, CrushError> {
+        self.forget_solutions();
         match self.bdds.remove(&bdd_id) {
-            Some(bdd_ref) => Ok(bdd_ref.into_inner()),
-            None => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("id {} not present in system", *bdd_id),
-            )),
+            Some(bdd_ref) => {
+                self.record(SystemOp::PopBdd { bdd_id });
+                Ok(bdd_ref.into_inner())
+            }
+            None => Err(CrushError::BddNotFound(bdd_id)),
         }
     }
 
@@ -395,34 +1239,67 @@ impl System {
     }
 
     /// Return the solutions to the `System` using the `LinBank` and the paths in the
-    /// remaining BDDs. If multiple BDDs are still in the system it will join all of them to
-    /// find the solutions.
+    /// remaining BDDs. If multiple BDDs are still in the system it will join all of them, in the
+    /// order of their ids, to find the solutions.
     ///
     /// Will use the `algebra::solve_linear_system` to find the different solutions.
+    ///
+    /// The solutions are cached until the `System` changes, so calling it again (ex: after a
+    /// solver already returned them) neither joins nor enumerates anything.
     pub fn get_solutions(&mut self) -> Vec<Vec<Option<bool>>> {
-        let keys: Vec<Id> = self.bdds.keys().cloned().collect();
-        let remaining_id = match keys.len() {
+        if let Some(solutions) = self.solutions.get_mut() {
+            return solutions.clone();
+        }
+        let solutions = self.find_solutions();
+        *self.solutions.get_mut() = Some(solutions.clone());
+        solutions
+    }
+
+    /// The uncached `get_solutions`.
+    fn find_solutions(&mut self) -> Vec<Vec<Option<bool>>> {
+        let remaining_id = match self.join_all().unwrap() {
             // everything in linbank
-            0 => {
+            None => {
                 let lhs = self.lin_bank.get_lhs();
                 let rhs = self.lin_bank.get_rhs();
                 return vec![algebra::solve_linear_system(matrix![lhs], rhs)];
             }
-            // only one BDD left
-            1 => keys[0],
-            // multiple BDD, join everything first
-            _ => {
-                for key in 1..keys.len() {
-                    self.join_bdds(keys[0], keys[key]).unwrap();
-                }
-                keys[0]
+            Some(id) => id,
+        };
+        let paths = self.bdds[&remaining_id].borrow().get_all_valid_path();
+        self.paths_to_solutions(paths)
+    }
+
+    /// Same as `get_solutions`, but at most `max` solutions are returned, along with whether
+    /// some solutions were left out, and the paths are not capped to the first 21.
+    ///
+    /// The paths of the joined `Bdd` are counted first (see `Bdd::get_all_valid_path_bounded`),
+    /// so only the first `max` of them are ever enumerated. The result is not cached.
+    pub fn get_solutions_bounded(
+        &mut self,
+        max: usize,
+    ) -> Result<BoundedSolutions, CrushError> {
+        let remaining_id = match self.join_all()? {
+            None => {
+                let lhs = self.lin_bank.get_lhs();
+                let rhs = self.lin_bank.get_rhs();
+                let mut solutions = vec![algebra::solve_linear_system(matrix![lhs], rhs)];
+                solutions.truncate(max);
+                return Ok((solutions, max == 0));
             }
+            Some(id) => id,
         };
-        let paths = self
-            .get_bdd(remaining_id)
-            .unwrap()
-            .borrow()
-            .get_all_valid_path();
+        let bdd = self.bdds[&remaining_id].borrow();
+        let (paths, truncated) = match bdd.get_all_valid_path_bounded(max) {
+            Ok(paths) => (paths, false),
+            Err(CrushError::TooManyPaths { .. }) => (bdd.get_first_valid_paths(max), true),
+            Err(e) => return Err(e),
+        };
+        Ok((self.paths_to_solutions(paths), truncated))
+    }
+
+    /// Solve the `LinBank` along with the equations of each path.
+    fn paths_to_solutions(&self, paths: Vec<Vec<LinEq>>) -> Vec<Vec<Option<bool>>> {
         let mut solutions = Vec::new();
         for path in paths {
             let mut lin_bank = self.lin_bank.clone();
@@ -437,10 +1314,134 @@ impl System {
         solutions
     }
 
+    /// Return the number of solutions of the `System` without enumerating them.
+    ///
+    /// The remaining `Bdd`s are joined, then the count is the number of paths in the
+    /// resulting `Bdd` times `2^free` where `free` is the number of variables not fixed by the
+    /// `LinBank` and the lhs of the levels (which are the same for every path).
+    ///
+    /// This assumes the `System` has no linear dependencies left (see `solver`), otherwise the
+    /// paths whose equations contradict each other are counted as well. Saturates to
+    /// `u128::MAX` on overflow. Fails if the `Bdd`s can't be joined (ex: the node budget is
+    /// exceeded).
+    pub fn count_solutions(&mut self) -> Result<u128, CrushError> {
+        let remaining_id = self.join_all()?;
+        let mut lin_bank = self.lin_bank.clone();
+        let paths = match remaining_id {
+            None => 1,
+            Some(id) => {
+                let bdd = self.get_bdd(id).unwrap().borrow();
+                for level in bdd.iter_levels().take(bdd.get_sink_level_index()) {
+                    lin_bank.push_lin_eq(LinEq::new(level.get_lhs(), false));
+                }
+                if bdd.get_sink_level_index() == 0 {
+                    1
+                } else {
+                    u128::try_from(&bdd.count_paths()).unwrap_or(u128::MAX)
+                }
+            }
+        };
+        let free = self.nvar - lin_bank.lin_eqs.len();
+        Ok(if paths == 0 {
+            0
+        } else if free >= 128 {
+            u128::MAX
+        } else {
+            paths.saturating_mul(1 << free)
+        })
+    }
+
+    /// Join all the `Bdd`s of the `System` into a single one and return it, consuming the `System`.
+    ///
+    /// The `Bdd`s are joined in the order of their ids, as in `get_solutions`. The variables
+    /// resolved by the `LinBank` are already replaced in the `Bdd`s each time an equation is pushed,
+    /// so the solutions of the `System` are the paths of the returned `Bdd` combined with the
+    /// equations of `get_lin_eqs` (taken before consuming the `System`).
+    ///
+    /// Returns an `Error` if the `System` has no `Bdd` left or if the joined `Bdd` would exceed
+    /// the node budget.
+    pub fn into_single_bdd(mut self) -> Result<Bdd, CrushError> {
+        match self.join_all()? {
+            Some(id) => self.pop_bdd(id),
+            None => Err(CrushError::EmptySystem),
+        }
+    }
+
+    /// Join all the `Bdd`s into the one of the lowest id, in the order of their ids so that the
+    /// solutions are always in the same order, and return this id or `None` if there is no `Bdd`.
+    fn join_all(&mut self) -> Result<Option<Id>, CrushError> {
+        let mut keys: Vec<Id> = self.bdds.keys().cloned().collect();
+        keys.sort();
+        for key in keys.iter().skip(1) {
+            self.join_bdds(keys[0], *key)?;
+        }
+        Ok(keys.first().cloned())
+    }
+
     /// Return the number of `LinEq` in the `LinBank`.
     pub fn get_lin_bank_size(&self) -> usize {
         self.lin_bank.lin_eqs.len()
     }
+
+    /// Return the `LinEq`s of the `LinBank`, in the order they were pushed.
+    pub fn get_lin_eqs(&self) -> &[LinEq] {
+        &self.lin_bank.lin_eqs
+    }
+
+    /// Write the `LinBank` to the file at path, one `lhs = rhs` per line where `lhs` is the sum
+    /// of the variables of the equation as in the levels of a .bdd file (ex: `13+3+35 = 1`).
+    ///
+    /// The file can be read back with `import_lin_bank`, or with
+    /// `utils::load_linear_constraints`.
+    pub fn export_lin_bank(&self, path: &PathBuf) -> Result<(), io::Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for lin_eq in self.lin_bank.lin_eqs.iter() {
+            let lhs = lin_eq
+                .get_lhs()
+                .iter_set_bits(..)
+                .map(|var| var.to_string())
+                .collect::<Vec<String>>();
+            writeln!(writer, "{} = {}", lhs.join("+"), lin_eq.get_rhs() as u8)?;
+        }
+        writer.flush()
+    }
+
+    /// Add the equations of the file at path written by `export_lin_bank` to the `System`, for
+    /// instance to warm-start the resolution of a freshly built copy of an exported `System`.
+    ///
+    /// Return the number of equations added, the ones already implied by the LinBank being
+    /// skipped, or an `Error` without adding anything if the file is malformed or an equation
+    /// doesn't fit the `System` (see `add_linear_constraints`).
+    pub fn import_lin_bank(&mut self, path: &PathBuf) -> Result<usize, io::Error> {
+        let constraints = utils::load_linear_constraints(path)?;
+        let added = self.add_linear_constraints(&constraints)?;
+        Ok(added.iter().filter(|added| **added).count())
+    }
+
+    /// Return the variables of `forbid` together with the variables which can't be dropped
+    /// without losing the value of one of them, sorted.
+    ///
+    /// A variable of `forbid` which is the highest set bit of an equation of the `LinBank` has
+    /// been replaced in the `Bdd`s by the other variables of the equation, so dropping one of
+    /// those would leave it undetermined. The pivot of an equation of the `LinBank` is always its
+    /// highest set bit, so instead of choosing another pivot those variables are protected too.
+    pub fn protected_variables(&self, forbid: &[usize]) -> Vec<usize> {
+        let mut protected: AHashSet<usize> = forbid.iter().cloned().collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for lin_eq in self.lin_bank.lin_eqs.iter() {
+                if protected.contains(&lin_eq.get_lhs_max_set_bit().unwrap()) {
+                    for var in lin_eq.get_lhs().iter_set_bits(..) {
+                        changed |= protected.insert(var);
+                    }
+                }
+            }
+        }
+        let mut protected: Vec<usize> = protected.into_iter().collect();
+        protected.sort();
+        protected
+    }
 }
 
 impl fmt::Debug for System {
@@ -457,15 +1458,102 @@ impl fmt::Debug for System {
     }
 }
 
-impl LinBank {
-    /// Push the `LinEq` in the `LinBank` if `lin_eq` is linearly independent
-    /// from the equations already in the `LinBank`.
+/// Mainly implemented for testing
+///
+/// Two `System`s are equal if they have the same `nvar`, the same ids with equal `Bdd`s (see
+/// the `PartialEq` of `Bdd`) and the same equations in their LinBank, whatever their order.
+/// The settings (drop and operation recording, node budget), the drop records and the
+/// operation log are not compared.
+impl PartialEq for System {
+    fn eq(&self, other: &System) -> bool {
+        if self.nvar != other.nvar || self.bdds.len() != other.bdds.len() {
+            return false;
+        }
+        let same_bdds = self.bdds.iter().all(|(id, bdd)| match other.bdds.get(id) {
+            Some(other_bdd) => *bdd.borrow() == *other_bdd.borrow(),
+            None => false,
+        });
+        if !same_bdds {
+            return false;
+        }
+        let key: AHashSet<(Vob, bool)> = self.lin_bank.key().into_iter().collect();
+        let other_key: AHashSet<(Vob, bool)> = other.lin_bank.key().into_iter().collect();
+        key == other_key
+    }
+}
+
+impl DropRecord {
+    /// Return the index the dropped level had in the recorded `Bdd`.
+    pub fn get_level_index(&self) -> usize {
+        self.level_index
+    }
+
+    /// Return the lhs of the dropped level.
+    pub fn get_lhs(&self) -> Vob {
+        self.bdd.iter_levels().nth(self.level_index).unwrap().get_lhs()
+    }
+
+    /// Look for a path from the source to the sink of the recorded `Bdd` consistent with
+    /// `solution`, and set the unknown variables of `solution` according to that path.
     ///
-    /// Perform the verification by adding (see `LinBank` doc).
+    /// Each level whose lhs involves unknown variables adds an equation on those
+    /// variables to a local `LinBank`, an edge is followed only if its equation is
+    /// consistent with the ones already on the path.
     ///
-    /// Return `Some(modified lin_eq)` if the lin_eq was pushed
-    /// and `None` if it wasn't.
-    pub fn push_lin_eq(&mut self, mut lin_eq: LinEq) -> Option<LinEq> {
+    /// The walk is done level by level and two paths reaching the same node with the
+    /// same local `LinBank` are only followed once, so the number of states kept is
+    /// bounded by the number of nodes as long as the levels only involve known variables.
+    ///
+    /// Returns false if no consistent path exists.
+    fn complete(&self, solution: &mut [Option<bool>]) -> bool {
+        let levels: Vec<_> = self.bdd.iter_levels().collect();
+        let sink_level_index = levels.len() - 1;
+        let source = match levels[0].iter_nodes().next() {
+            Some((id, _)) => *id,
+            None => return false,
+        };
+        let mut current = vec![(source, LinBank::default())];
+        for level in levels.iter().take(sink_level_index) {
+            let lhs = level.get_lhs();
+            let mut unknown = Vob::from_elem(lhs.len(), false);
+            let mut known_sum = false;
+            for var in lhs.iter_set_bits(..) {
+                match solution[var] {
+                    Some(value) => known_sum ^= value,
+                    None => {
+                        unknown.set(var, true);
+                    }
+                }
+            }
+            let mut seen = AHashSet::default();
+            let mut next = Vec::new();
+            for (id, bank) in current.drain(..) {
+                let node = &level.get_nodes()[&id];
+                for (edge, child) in [(false, node.get_e0()), (true, node.get_e1())].iter() {
+                    if let Some(child) = child {
+                        let mut bank = bank.clone();
+                        let lin_eq = LinEq::new(unknown.clone(), edge ^ known_sum);
+                        if bank.push_consistent_lin_eq(lin_eq) && seen.insert((*child, bank.key()))
+                        {
+                            next.push((*child, bank));
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                return false;
+            }
+            current = next;
+        }
+        current[0].1.assign_into(solution);
+        true
+    }
+}
+
+impl LinBank {
+    /// Reduce `lin_eq` by adding the equations of the `LinBank` whose highest
+    /// set bit is also set in `lin_eq` (see `LinBank` doc).
+    fn reduce(&self, mut lin_eq: LinEq) -> LinEq {
         for lin_bank_eq in self.lin_eqs.iter() {
             if lin_eq
                 .get_lhs()
@@ -475,6 +1563,18 @@ impl LinBank {
                 lin_eq.add_lin_eq(&lin_bank_eq)
             }
         }
+        lin_eq
+    }
+
+    /// Push the `LinEq` in the `LinBank` if `lin_eq` is linearly independent
+    /// from the equations already in the `LinBank`.
+    ///
+    /// Perform the verification by adding (see `LinBank` doc).
+    ///
+    /// Return `Some(modified lin_eq)` if the lin_eq was pushed
+    /// and `None` if it wasn't.
+    pub fn push_lin_eq(&mut self, lin_eq: LinEq) -> Option<LinEq> {
+        let lin_eq = self.reduce(lin_eq);
         match lin_eq.get_lhs_max_set_bit() {
             Some(_) => {
                 self.lin_eqs.push(lin_eq.clone());
@@ -484,6 +1584,56 @@ impl LinBank {
         }
     }
 
+    /// Push `lin_eq` in the `LinBank` if it is linearly independent from it.
+    ///
+    /// Return false if `lin_eq` reduces to `0 = 1`, meaning it contradicts the `LinBank`.
+    fn push_consistent_lin_eq(&mut self, lin_eq: LinEq) -> bool {
+        let lin_eq = self.reduce(lin_eq);
+        match lin_eq.get_lhs_max_set_bit() {
+            Some(_) => {
+                self.lin_eqs.push(lin_eq);
+                true
+            }
+            None => !lin_eq.get_rhs(),
+        }
+    }
+
+    /// Return the equations of the `LinBank` in a form that can be hashed.
+    fn key(&self) -> Vec<(Vob, bool)> {
+        self.lin_eqs
+            .iter()
+            .map(|lin_eq| (lin_eq.get_lhs(), lin_eq.get_rhs()))
+            .collect()
+    }
+
+    /// Set in `solution` the variables of the `LinBank` which are still `None`.
+    ///
+    /// Variables which are not the highest set bit of an equation are free and set to false,
+    /// the others are then found by substitution starting from the last equation pushed.
+    fn assign_into(&self, solution: &mut [Option<bool>]) {
+        let pivots: AHashSet<usize> = self
+            .lin_eqs
+            .iter()
+            .map(|lin_eq| lin_eq.get_lhs_max_set_bit().unwrap())
+            .collect();
+        for lin_eq in self.lin_eqs.iter() {
+            for var in lin_eq.get_lhs().iter_set_bits(..) {
+                if solution[var].is_none() && !pivots.contains(&var) {
+                    solution[var] = Some(false);
+                }
+            }
+        }
+        for lin_eq in self.lin_eqs.iter().rev() {
+            let pivot = lin_eq.get_lhs_max_set_bit().unwrap();
+            let value = lin_eq
+                .get_lhs()
+                .iter_set_bits(..)
+                .filter(|var| *var != pivot)
+                .fold(lin_eq.get_rhs(), |acc, var| acc ^ solution[var].unwrap());
+            solution[pivot] = Some(value);
+        }
+    }
+
     /// Return a copy of all the left hand side of the equations inside the `LinBank`
     pub fn get_lhs(&self) -> Vec<Vob> {
         self.lin_eqs.iter().map(|lin_eq| lin_eq.get_lhs()).collect()
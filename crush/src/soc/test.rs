@@ -1,5 +1,12 @@
-use crate::soc::{utils, Id};
+use crate::error::CrushError;
+use crate::soc::{
+    bdd::{Bdd, LinEq},
+    system::{System, SystemOp},
+    utils, Id,
+};
+use num_bigint::ToBigUint;
 use std::io::Error;
+use vob::Vob;
 
 #[test]
 fn swap_test() {
@@ -12,6 +19,35 @@ fn swap_test() {
     assert_eq!(bdd, save);
 }
 
+#[test]
+fn compact_ids_test() {
+    let mut bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    bdd.set_next_id(6);
+    for _ in 0..100 {
+        bdd.swap(1, 2);
+        bdd.swap(0, 1);
+    }
+    let uncompacted = bdd.clone();
+    let grown_next_id = bdd.get_next_id();
+    assert!(grown_next_id > 100);
+    bdd.compact_ids();
+    assert_eq!(bdd, uncompacted);
+    assert_eq!(bdd.get_next_id(), bdd.get_size());
+    assert!(bdd.get_next_id() < grown_next_id);
+    let mut ids: Vec<usize> = (0..bdd.get_levels_size())
+        .flat_map(|level| bdd.level_nodes(level))
+        .map(|(id, _, _)| *id)
+        .collect();
+    ids.sort();
+    let expected: Vec<usize> = (1..=bdd.get_size()).map(|id| id * 10000).collect();
+    assert_eq!(ids, expected);
+    // the operations go on from the compacted ids
+    bdd.swap(1, 2);
+    let mut expected = uncompacted;
+    expected.swap(1, 2);
+    assert_eq!(bdd, expected);
+}
+
 #[test]
 fn add_test() {
     let mut bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
@@ -24,21 +60,29 @@ fn add_test() {
 }
 
 #[test]
-fn absorb_test() {
+fn absorb_test() -> Result<(), CrushError> {
     let mut bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
-    bdd.absorb(2, false);
+    bdd.absorb(2, false)?;
     let expected_result = bdd!(5;0;[("1+2",[(1;2,0)]);("3+2",[(2;0,6)]);("",[(6;0,0)])]);
     assert_eq!(bdd, expected_result);
 
     let mut bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
-    bdd.absorb(1, true);
+    bdd.absorb(1, true)?;
     let expected_result = bdd!(5;0;[("1+2",[(1;5,0)]);("0+4",[(5;6,0)]);("",[(6;0,0)])]);
     assert_eq!(bdd, expected_result);
 
     let mut bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
-    bdd.absorb(0, false);
+    bdd.absorb(0, false)?;
     let expected_result = bdd!(5;0;[("3+2",[(2;4,5)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
     assert_eq!(bdd, expected_result);
+
+    // no node of the level has a 0edge (or the source a 1edge), the bdd has no solution left
+    let mut bdd = bdd!(5;0;[("1+2",[(1;2,0)]);("3+2",[(2;0,3)]);("",[(3;0,0)])]);
+    let save = bdd.clone();
+    assert_eq!(bdd.absorb(1, false), Err(CrushError::NoSolution(Id::new(0))));
+    assert_eq!(bdd.absorb(0, true), Err(CrushError::NoSolution(Id::new(0))));
+    assert_eq!(bdd, save);
+    Ok(())
 }
 
 #[test]
@@ -59,16 +103,114 @@ fn drop_test() {
     assert_eq!(bdd, expected_result);
 }
 
+#[test]
+fn drop_two_levels_test() {
+    // x1 + x2 = 0 forces x0 + x4 = 1 and x1 + x2 = 1 forces x0 + x4 = 0
+    let build = || bdd!(5;0;[("1+2",[(1;2,3)]);("0+4",[(2;0,4);(3;4,0)]);("",[(4;0,0)])]);
+    let mut bdd = build();
+    bdd.drop(1);
+    let expected_result = bdd!(5;0;[("1+2",[(1;4,4)]);("",[(4;0,0)])]);
+    assert_eq!(bdd, expected_result);
+    assert_eq!(bdd.count_paths(), 2u32.into());
+
+    // the source is swapped down before being dropped
+    let mut bdd = build();
+    bdd.drop(0);
+    let expected_result = bdd!(5;0;[("0+4",[(1;4,4)]);("",[(4;0,0)])]);
+    assert_eq!(bdd, expected_result);
+
+    // dropping the last level left above the sink only leaves the sink
+    bdd.drop(0);
+    assert_eq!(bdd.get_levels_size(), 1);
+    assert!(bdd.get_lhs().is_empty());
+}
+
+#[test]
+fn drop_middle_of_three_levels_test() {
+    let mut bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    bdd.drop(1);
+    // x1 + x2 = 0 leaves x0 + x4 free, x1 + x2 = 1 still forces x0 + x4 = 1
+    let expected_result = bdd!(5;0;[("1+2",[(1;2,3)]);("0+4",[(2;6,6);(3;0,6)]);("",[(6;0,0)])]);
+    assert_eq!(bdd, expected_result);
+    assert_eq!(bdd.count_paths(), 3u32.into());
+}
+
+#[test]
+#[should_panic]
+fn drop_sink_test() {
+    let mut bdd = bdd!(5;0;[("0+4",[(5;6,0)]);("",[(6;0,0)])]);
+    bdd.drop(1);
+}
+
 #[test]
 fn count_path_test() {
     let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
-    assert_eq!(bdd.count_paths(), 3);
+    assert_eq!(bdd.count_paths(), 3.to_biguint().unwrap());
 
     let bdd = bdd!(5;0;[("0+4",[(4;6,6)]);("",[(6;0,0)])]);
-    assert_eq!(bdd.count_paths(), 2);
+    assert_eq!(bdd.count_paths(), 2.to_biguint().unwrap());
 
     let bdd = bdd!(5;0;[("",[(6;0,0)])]);
-    assert_eq!(bdd.count_paths(), 0);
+    assert_eq!(bdd.count_paths(), 0.to_biguint().unwrap());
+}
+
+#[test]
+fn get_all_valid_path_bounded_test() {
+    // every level can take both values, 2^6 paths
+    let bdd = bdd!(6;0;[("0",[(1;2,2)]);("1",[(2;3,3)]);("2",[(3;4,4)]);("3",[(4;5,5)]);("4",[(5;6,6)]);("5",[(6;7,7)]);("",[(7;0,0)])]);
+    assert_eq!(bdd.get_all_valid_path().len(), 21);
+    assert_eq!(
+        bdd.get_all_valid_path_bounded(63).unwrap_err(),
+        CrushError::TooManyPaths { max: 63 }
+    );
+    let paths = bdd.get_all_valid_path_bounded(64).unwrap();
+    assert_eq!(paths.len(), 64);
+    let distinct: std::collections::HashSet<Vec<bool>> = paths
+        .iter()
+        .map(|path| path.iter().map(|eq| eq.get_rhs()).collect())
+        .collect();
+    assert_eq!(distinct.len(), 64);
+    // 2^100 paths, far too many to enumerate
+    let mut spec = wide_bdd_spec(100);
+    let bdd = utils::build_bdd_from_spec(&mut spec, 100);
+    assert_eq!(
+        bdd.get_all_valid_path_bounded(1_000_000).unwrap_err(),
+        CrushError::TooManyPaths { max: 1_000_000 }
+    );
+    assert_eq!(bdd.get_first_valid_paths(5).len(), 5);
+}
+
+/// The spec of a `Bdd` of `n` levels whose lhs are the variables 0 to `n - 1`, each node going
+/// to the next one by both edges, so it has 2^`n` paths.
+fn wide_bdd_spec(n: usize) -> utils::BddSpec {
+    let mut levels: Vec<utils::LevelSpec> = (0..n)
+        .map(|var| {
+            let node = utils::NodeSpec::new(Id::new(var + 1), Id::new(var + 2), Id::new(var + 2));
+            utils::LevelSpec::new(vec![var as i64], vec![node])
+        })
+        .collect();
+    let sink = utils::NodeSpec::new(Id::new(n + 1), Id::new(0), Id::new(0));
+    levels.push(utils::LevelSpec::new(vec![], vec![sink]));
+    utils::BddSpec::new(Id::new(0), levels)
+}
+
+#[test]
+fn get_solutions_bounded_test() -> Result<(), Error> {
+    let spec = utils::SystemSpec::new(100, vec![wide_bdd_spec(100)]);
+    let mut system = utils::build_system_from_spec(spec);
+    let (solutions, truncated) = system.get_solutions_bounded(30)?;
+    assert!(truncated);
+    assert_eq!(solutions.len(), 30);
+    let distinct: std::collections::HashSet<&Vec<Option<bool>>> = solutions.iter().collect();
+    assert_eq!(distinct.len(), 30);
+    assert!(solutions.iter().all(|solution| solution.iter().all(|bit| bit.is_some())));
+
+    let spec = utils::SystemSpec::new(6, vec![wide_bdd_spec(6)]);
+    let mut system = utils::build_system_from_spec(spec);
+    let (solutions, truncated) = system.get_solutions_bounded(64)?;
+    assert!(!truncated);
+    assert_eq!(solutions.len(), 64);
+    Ok(())
 }
 
 #[test]
@@ -110,6 +252,35 @@ fn join_empty_bdd() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn join_absorbed_bdd() -> Result<(), Error> {
+    // absorbing the single level of bdd_2 and bdd_3 leaves them reduced to their sink
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let bdd_2 = bdd!(5;1;[("2+4",[(1;2,0)]);("",[(2;0,0)])]);
+    let bdd_3 = bdd!(5;2;[("3",[(1;0,2)]);("",[(2;0,0)])]);
+    let expected_result = bdd.clone();
+    let mut system = system![bdd, bdd_2, bdd_3]?;
+    system.absorb(Id::new(1), 0, false)?;
+    system.absorb(Id::new(2), 0, true)?;
+    assert_eq!(system.get_bdd(Id::new(1))?.borrow().get_sink_level_index(), 0);
+
+    assert_eq!(system.join_bdds(Id::new(0), Id::new(1))?, Id::new(0));
+    assert_eq!(*system.get_bdd(Id::new(0))?.borrow(), expected_result);
+    assert_eq!(system.join_bdds(Id::new(2), Id::new(0))?, Id::new(2));
+    assert_eq!(*system.get_bdd(Id::new(2))?.borrow(), expected_result);
+    assert_eq!(system.iter_bdds().len(), 1);
+
+    // joining two sinks leaves a single sink
+    let sink = bdd!(5;0;[("",[(1;0,0)])]);
+    let sink_2 = bdd!(5;1;[("",[(1;0,0)])]);
+    let mut system = system![sink, sink_2]?;
+    system.join_bdds(Id::new(0), Id::new(1))?;
+    assert_eq!(system.iter_bdds().len(), 1);
+    assert_eq!(system.get_bdd(Id::new(0))?.borrow().get_levels_size(), 1);
+    assert_eq!(system.get_size(), 1);
+    Ok(())
+}
+
 #[test]
 fn fix_test() -> Result<(), Error> {
     let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
@@ -135,6 +306,26 @@ fn fix_test() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn fix_redundant_test() -> Result<(), Error> {
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let mut system = system![bdd]?;
+    system.fix(vec![1, 2], true)?;
+    system.fix(vec![3], true)?;
+    let bdd = system.get_bdd(Id::new(0))?.borrow().clone();
+    // x1 + x2 + x3 = 0 follows from the two equations
+    system.fix(vec![1, 2], true)?;
+    system.fix(vec![1, 2, 3], false)?;
+    assert_eq!(system.get_lin_bank_size(), 2);
+    assert_eq!(
+        system.fix(vec![1, 2, 3], true),
+        Err(CrushError::Contradiction)
+    );
+    assert_eq!(system.get_lin_bank_size(), 2);
+    assert_eq!(*system.get_bdd(Id::new(0))?.borrow(), bdd);
+    Ok(())
+}
+
 #[test]
 fn test_equality() {
     let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
@@ -142,3 +333,856 @@ fn test_equality() {
     ("0+4",[(40000;0,60000);(50000;60000,0)]);("",[(60000;0,0)])]);
     assert_eq!(bdd, same_bdd)
 }
+
+#[test]
+fn id_conversion_test() {
+    let id: Id = 42.into();
+    assert_eq!(id, Id::new(42));
+    assert_eq!(Id::from(7), Id::new(7));
+    let val: usize = id.into();
+    assert_eq!(val, 42);
+    assert_eq!(usize::from(Id::new(0)), 0);
+    // Deref still gives access to the inner value
+    assert_eq!(*id, 42);
+}
+
+#[test]
+fn id_offset_test() {
+    let id = Id::new(10);
+    assert_eq!(id.offset(0), id);
+    assert_eq!(id.offset(5), Id::new(15));
+    assert_eq!(id.offset(2).offset(3), id.offset(5));
+    assert_eq!(*Id::new(3).offset(4), 7);
+}
+
+#[test]
+#[should_panic]
+fn id_offset_overflow_test() {
+    Id::new(usize::MAX).offset(1);
+}
+
+#[test]
+fn recover_dropped_test() -> Result<(), Error> {
+    // x3 doesn't appear in any level and stays free
+    let bdd = bdd!(4;0;[("0",[(1;2,3)]);("1",[(2;4,0);(3;0,5)]);("1+2",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let mut system = system![bdd]?;
+    let mut expected = system.get_solutions();
+    expected.sort();
+
+    let bdd = bdd!(4;0;[("0",[(1;2,3)]);("1",[(2;4,0);(3;0,5)]);("1+2",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let mut system = system![bdd]?;
+    system.set_drop_recording(true);
+    system.drop(Id::new(0), 2)?;
+    assert_eq!(system.get_drop_records().len(), 1);
+    let mut solutions = system.get_solutions();
+    assert!(solutions.iter().all(|solution| solution[2].is_none()));
+    for solution in solutions.iter_mut() {
+        system.recover_dropped(solution)?;
+        assert_eq!(solution[3], None);
+    }
+    solutions.sort();
+    assert_eq!(solutions, expected);
+
+    // a solution not following any path of the dropped bdd cannot be completed
+    assert_eq!(
+        system.recover_dropped(&mut [Some(true), Some(false), None, None]),
+        Err(CrushError::NoConsistentPath(Id::new(0)))
+    );
+    // neither can a solution of the wrong size
+    let err = system.recover_dropped(&mut [Some(true), Some(true)]).unwrap_err();
+    assert_eq!(
+        err,
+        CrushError::SolutionLength {
+            expected: 4,
+            found: 2
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn system_errors_test() -> Result<(), Error> {
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let bdd_2 = bdd!(5;1;[("2+4",[(1;2,0)]);("",[(2;0,0)])]);
+    let mut system = system![bdd, bdd_2]?;
+    assert_eq!(System::from_elem(Vec::new()).err(), Some(CrushError::EmptySystem));
+
+    let other_nvar = bdd!(6;2;[("2+4",[(1;2,0)]);("",[(2;0,0)])]);
+    assert_eq!(
+        system.push_bdd(other_nvar),
+        Err(CrushError::NvarMismatch {
+            expected: 5,
+            found: 6
+        })
+    );
+    let same_id = bdd!(5;1;[("2+4",[(1;2,0)]);("",[(2;0,0)])]);
+    assert_eq!(
+        system.push_bdd(same_id),
+        Err(CrushError::DuplicateBdd(Id::new(1)))
+    );
+    assert_eq!(
+        system.get_bdd(Id::new(2)).err(),
+        Some(CrushError::BddNotFound(Id::new(2)))
+    );
+    assert_eq!(
+        system.join_bdds(Id::new(0), Id::new(0)),
+        Err(CrushError::SameBdd(Id::new(0)))
+    );
+    assert_eq!(
+        system.swap(Id::new(0), 0, 2),
+        Err(CrushError::NotAbove { above: 0, below: 2 })
+    );
+    assert_eq!(
+        system.absorb(Id::new(1), 1, false),
+        Err(CrushError::LevelOutOfRange { level: 1, sink: 1 })
+    );
+    assert!(matches!(System::from_anf("x0 + y1"), Err(CrushError::Parse(_))));
+
+    // the io::Error keeps the message of the CrushError
+    let err: Error = CrushError::BddNotFound(Id::new(2)).into();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "id 2 not present in system");
+    let err: Error = CrushError::VarOutOfRange(5).into();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    Ok(())
+}
+
+#[test]
+fn node_budget_test() -> Result<(), Error> {
+    let bdd = bdd!(5;0;[("0",[(1;2,3)]);("1",[(2;4,5);(3;5,0)]);("2",[(4;6,7);(5;7,0)]);("3",[(6;8,0);(7;0,8)]);("",[(8;0,0)])]);
+    let bdd_2 = bdd!(5;1;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let save = bdd!(5;0;[("0",[(1;2,3)]);("1",[(2;4,5);(3;5,0)]);("2",[(4;6,7);(5;7,0)]);("3",[(6;8,0);(7;0,8)]);("",[(8;0,0)])]);
+    let mut system = system![bdd, bdd_2]?;
+    system.set_max_bdd_nodes(Some(8));
+
+    let err = system.join_bdds(Id::new(0), Id::new(1)).unwrap_err();
+    assert_eq!(err, CrushError::NodeBudget);
+    assert_eq!(err.to_string(), "node budget exceeded");
+    assert_eq!(system.iter_bdds().len(), 2);
+
+    // adding the source to the third level grows the bdd to 9 nodes, the add has to be reverted
+    let err = system.add(Id::new(0), 0, 2).unwrap_err();
+    assert_eq!(err, CrushError::NodeBudget);
+    assert_eq!(*system.get_bdd(Id::new(0))?.borrow(), save);
+
+    system.set_max_bdd_nodes(None);
+    system.add(Id::new(0), 0, 2)?;
+    system.join_bdds(Id::new(0), Id::new(1))?;
+    assert_eq!(system.iter_bdds().len(), 1);
+
+    // swapping the two middle levels grows the bdd to 10 nodes, the swap has to be reverted
+    let bdd = bdd!(5;0;[("0",[(1;2,3)]);("1",[(2;4,0);(3;0,5)]);("2",[(4;6,7);(5;7,6)]);("3",[(6;8,0);(7;0,8)]);("",[(8;0,0)])]);
+    let save = bdd!(5;0;[("0",[(1;2,3)]);("1",[(2;4,0);(3;0,5)]);("2",[(4;6,7);(5;7,6)]);("3",[(6;8,0);(7;0,8)]);("",[(8;0,0)])]);
+    let mut system = system![bdd]?;
+    system.set_max_bdd_nodes(Some(9));
+    let err = system.swap(Id::new(0), 1, 2).unwrap_err();
+    assert_eq!(err, CrushError::NodeBudget);
+    assert_eq!(*system.get_bdd(Id::new(0))?.borrow(), save);
+    Ok(())
+}
+
+#[test]
+fn count_solutions_test() -> Result<(), Error> {
+    // 3 paths over 3 independent levels in a 5 variables system -> 2 free variables
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let mut system = system![bdd]?;
+    assert_eq!(system.count_solutions()?, 12);
+
+    system.fix(vec![0], true)?;
+    assert_eq!(system.count_solutions()?, 6);
+
+    // two bdds are joined before counting
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let bdd_2 = bdd!(5;1;[("1",[(1;2,0)]);("",[(2;0,0)])]);
+    let mut system = system![bdd, bdd_2]?;
+    let mut budgeted = system.clone();
+    assert_eq!(system.count_solutions()?, 6);
+
+    // the join needed to count fails with the node budget
+    budgeted.set_max_bdd_nodes(Some(1));
+    assert_eq!(budgeted.count_solutions(), Err(CrushError::NodeBudget));
+    Ok(())
+}
+
+#[test]
+fn from_anf_test() -> Result<(), Error> {
+    let (spec, nvar) = utils::parse_system_spec_from_anf(
+        "# comments and blank lines are skipped
+
+        x0*x1 + x2 + 1
+        x1*x0 + x3*x3 + x2*x1*1 + x1*x2 + x0*0",
+    )?;
+    assert_eq!(nvar, 4);
+    let system = utils::build_system_from_spec(spec);
+    // x0*x1 is shared by the two equations, x2*x1 cancels out and x3*x3 is x3
+    assert_eq!(system.get_nvar(), 4 + 1);
+    assert_eq!(system.iter_bdds().len(), 1 + 2);
+
+    assert!(System::from_anf("x0*x1*x2 + 1").is_err());
+    assert!(System::from_anf("x0 + y1").is_err());
+    assert!(System::from_anf("x0 + x0 + 1").is_err());
+    Ok(())
+}
+
+#[test]
+fn fix_equal_test() -> Result<(), Error> {
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let mut system = system![bdd]?;
+    system.fix_equal(4, 0)?;
+    assert_eq!(system.get_lin_bank_size(), 1);
+    // x4 is replaced by x0 everywhere
+    let lhs: Vec<Vob> = system
+        .iter_bdds()
+        .flat_map(|bdd| {
+            bdd.1
+                .borrow()
+                .iter_levels()
+                .map(|level| level.get_lhs())
+                .collect::<Vec<Vob>>()
+        })
+        .collect();
+    assert!(lhs.iter().all(|lhs| !lhs[4]));
+
+    // already implied, nothing is added
+    assert_eq!(system.fix_equal(0, 4), Ok(()));
+    assert_eq!(system.fix_equal(2, 2), Err(CrushError::LinearlyDependent));
+    assert_eq!(system.fix_equal(2, 5), Err(CrushError::VarOutOfRange(5)));
+    assert_eq!(system.get_lin_bank_size(), 1);
+    Ok(())
+}
+
+#[test]
+fn add_linear_constraints_test() -> Result<(), Error> {
+    // x5 and x6 don't appear in the bdd
+    let bdd = bdd!(7;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let mut system = system![bdd]?;
+    assert_eq!(system.count_solutions()?, 48);
+    let constraints = utils::parse_linear_constraints(
+        "# the last constraint follows from the first two
+        5 = 1
+        5+6 = 0
+        6 = 1",
+    )?;
+    assert_eq!(
+        constraints,
+        vec![(vec![5], true), (vec![5, 6], false), (vec![6], true)]
+    );
+    assert_eq!(
+        system.add_linear_constraints(&constraints)?,
+        vec![true, true, false]
+    );
+    assert_eq!(system.get_lin_bank_size(), 2);
+    assert_eq!(system.count_solutions()?, 12);
+
+    // nothing is added when a constraint is out of range or contradicts the LinBank
+    assert_eq!(
+        system.add_linear_constraints(&[(vec![4], true), (vec![7], true)]),
+        Err(CrushError::VarOutOfRange(7))
+    );
+    assert_eq!(
+        system.add_linear_constraints(&[(vec![4], true), (vec![6], false)]),
+        Err(CrushError::Inconsistent(1))
+    );
+    assert_eq!(system.get_lin_bank_size(), 2);
+    assert!(utils::parse_linear_constraints("1+x2 = 1").is_err());
+    assert!(utils::parse_linear_constraints("1+2 = 2").is_err());
+    Ok(())
+}
+
+#[test]
+fn move_level_test() -> Result<(), Error> {
+    let bdd = bdd!(6;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("5",[(6;0,7)]);("",[(7;0,0)])]);
+    let mut moved = bdd.clone();
+    let mut swapped = bdd.clone();
+    moved.move_level(0, 3);
+    for i in 0..3 {
+        swapped.swap(i, i + 1);
+    }
+    assert_eq!(moved, swapped);
+    moved.move_level(3, 1);
+    for i in (1..3).rev() {
+        swapped.swap(i, i + 1);
+    }
+    assert_eq!(moved, swapped);
+
+    let mut system = system![bdd]?;
+    let id = *system.iter_bdds().next().unwrap().0;
+    assert_eq!(
+        system.move_level(id, 0, 4),
+        Err(CrushError::LevelOutOfRange { level: 4, sink: 4 })
+    );
+    assert!(system.move_level(id, 4, 0).is_err());
+    system.move_level(id, 2, 0)?;
+    system.move_level(id, 0, 0)?;
+    Ok(())
+}
+
+#[test]
+fn fix_many_test() -> Result<(), Error> {
+    let make_system = || {
+        let bdd = bdd!(6;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+        let bdd_2 = bdd!(6;1;[("5+3",[(1;2,3)]);("4",[(2;4,0);(3;0,4)]);("",[(4;0,0)])]);
+        system![bdd, bdd_2]
+    };
+    // the second equation is pushed as x0 + x2 = 1 and contains the variable
+    // resolved by the third one
+    let eqs = vec![
+        (vec![4], true),
+        (vec![0, 4], false),
+        (vec![2], false),
+        (vec![0, 2], true),
+        (vec![5, 3, 1], true),
+    ];
+    let mut expected = make_system()?;
+    for (lhs, rhs) in eqs.iter() {
+        expected.fix(lhs.clone(), *rhs)?;
+    }
+    let added = expected.get_lin_bank_size();
+    let mut system = make_system()?;
+    assert_eq!(system.fix_many(&eqs)?, added);
+    assert_eq!(system, expected);
+    assert_eq!(system.get_replace_passes(), 2);
+    assert!(system.get_replace_passes() < expected.get_replace_passes());
+
+    // x6 is beyond nvar, the system grows to include it
+    assert_eq!(system.fix_many(&[(vec![6], true)])?, 1);
+    assert_eq!(system.get_nvar(), 7);
+    assert_eq!(system.get_lin_bank_size(), added + 1);
+
+    // a contradiction is rejected as by `fix`, even inside a single call, and nothing changes
+    let mut system = make_system()?;
+    let save = make_system()?;
+    match system.fix_many(&[(vec![0], false), (vec![0], true)]) {
+        Err(CrushError::Contradiction) => (),
+        result => panic!("expected a contradiction, got {:?}", result),
+    }
+    assert_eq!(system, save);
+    assert_eq!(system.get_lin_bank_size(), 0);
+    assert_eq!(system.fix_many(&[(vec![0], false)])?, 1);
+    match system.fix_many(&[(vec![0], true)]) {
+        Err(CrushError::Contradiction) => (),
+        result => panic!("expected a contradiction, got {:?}", result),
+    }
+    assert_eq!(system.get_lin_bank_size(), 1);
+    // a redundant equation is skipped
+    assert_eq!(system.fix_many(&[(vec![0], false)])?, 0);
+    Ok(())
+}
+
+#[test]
+fn fix_grows_nvar_test() -> Result<(), Error> {
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let mut system = system![bdd]?;
+    system.fix(vec![3], true)?;
+    // x6 is an auxiliary variable equal to x1 + 1, x5 is left free
+    system.fix(vec![1, 6], true)?;
+    assert_eq!(system.get_nvar(), 7);
+    let bdd = system.get_bdd(Id::new(0))?.borrow().clone();
+    assert_eq!(bdd.get_nvar_size(), 7);
+    assert!(bdd.get_lhs().iter().all(|lhs| lhs.len() == 7));
+    assert_eq!(system.count_solutions()?, 12);
+    let solutions = system.get_solutions();
+    assert!(!solutions.is_empty());
+    for solution in solutions.iter() {
+        assert_eq!(solution.len(), 7);
+        assert_eq!(solution[3], Some(true));
+        assert_eq!(solution[5], None);
+        assert_eq!(solution[6], solution[1].map(|x1| !x1));
+    }
+    Ok(())
+}
+
+#[test]
+fn system_is_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<System>();
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let mut system = system![bdd].unwrap();
+    let count = std::thread::spawn(move || system.count_solutions().unwrap())
+        .join()
+        .unwrap();
+    assert_eq!(count, 12);
+}
+
+#[test]
+fn fully_reduce_test() {
+    let mut bdd = bdd!(5;0;[("0",[(1;2,3)]);("1",[(2;4,5);(3;5,4)]);("2",[(4;6,7);(5;6,7)]);("3",[(6;8,0);(7;0,8)]);("",[(8;0,0)])]);
+    let expected_result = bdd!(5;0;[("0",[(1;2,2)]);("1",[(2;4,4)]);("2",[(4;6,7)]);("3",[(6;8,0);(7;0,8)]);("",[(8;0,0)])]);
+    assert!(!bdd.is_reduced());
+    bdd.merge_equals_node_start(3);
+    assert!(!bdd.is_reduced());
+    bdd.fully_reduce();
+    assert!(bdd.is_reduced());
+    assert_eq!(bdd, expected_result);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "comparing a Bdd which is not reduced")]
+fn compare_not_reduced_test() {
+    let bdd = bdd!(5;0;[("0",[(1;2,3)]);("1",[(2;4,4);(3;4,4)]);("",[(4;0,0)])]);
+    let _ = bdd == bdd.clone();
+}
+
+#[test]
+fn has_jumping_edges_test() {
+    let mut bdd = Bdd::new();
+    for (level_index, (var, node)) in [(Some(1), 1), (Some(2), 2), (None, 3)].iter().enumerate() {
+        bdd.add_level();
+        bdd.set_lhs_level(level_index, var.iter().cloned().collect(), 5);
+        bdd.add_nodes_to_level(level_index, vec![Id::new(*node)]);
+    }
+    bdd.set_next_id(4);
+    bdd.connect_nodes_from_spec(Id::new(1), Id::new(2), 0);
+    bdd.connect_nodes_from_spec(Id::new(1), Id::new(3), 1);
+    bdd.connect_nodes_from_spec(Id::new(2), Id::new(3), 0);
+    assert_eq!(
+        bdd.has_jumping_edges(),
+        Some((Id::new(10000), Id::new(30000)))
+    );
+    bdd.add_same_edges_node_at_level(1);
+    assert_eq!(bdd.has_jumping_edges(), None);
+    // the macro removes the jumping edges, including the ones going to the sink
+    let bdd = bdd!(5;0;[("1",[(1;2,3)]);("2",[(2;3,0)]);("",[(3;0,0)])]);
+    assert_eq!(bdd.has_jumping_edges(), None);
+}
+
+#[test]
+fn count_linear_levels_test() -> Result<(), Error> {
+    // only the levels "1+2" (0edges) and "0+4" (1edges) are linear
+    let mut bdd = bdd!(5;0;[("1+2",[(1;2,0)]);("3+2",[(2;4,5)]);("0+4",[(4;0,6);(5;0,6)]);("",[(6;0,0)])]);
+    let bdd_2 = bdd!(5;1;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    assert_eq!(bdd.count_linear_levels(), 2);
+    assert_eq!(bdd_2.count_linear_levels(), 0);
+    let system = system![bdd.clone(), bdd_2]?;
+    assert_eq!(system.count_linear_levels(), 2);
+    assert!(bdd.scan_absorb_lin_eq()?.len() >= 2);
+    assert_eq!(bdd.count_linear_levels(), 0);
+    Ok(())
+}
+
+#[test]
+fn count_structurally_identical_test() -> Result<(), Error> {
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let relabeled = bdd!(5;1;[("0",[(1;3,2)]);("4",[(3;5,4);(2;5,0)]);("1+3",[(5;0,6);(4;6,0)]);("",[(6;0,0)])]);
+    let other = bdd!(5;2;[("2+4",[(1;2,0)]);("",[(2;0,0)])]);
+    assert_eq!(bdd.structural_hash(), relabeled.structural_hash());
+    assert_ne!(bdd.structural_hash(), other.structural_hash());
+    let system = system![bdd, relabeled, other]?;
+    let counts = system.count_structurally_identical();
+    let mut multiplicities: Vec<usize> = counts.values().cloned().collect();
+    multiplicities.sort();
+    assert_eq!(multiplicities, vec![1, 2]);
+    Ok(())
+}
+
+#[test]
+fn diff_test() -> Result<(), Error> {
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let bdd_2 = bdd!(5;1;[("2+4",[(1;2,0)]);("",[(2;0,0)])]);
+    let system = system![bdd, bdd_2]?;
+    assert!(system.diff(&system.clone()).is_empty());
+
+    // x3 only appears in the Bdd 0
+    let mut fixed = system.clone();
+    fixed.fix(vec![3], true)?;
+    let diff = system.diff(&fixed);
+    assert_eq!(diff.changed, vec![Id::new(0)]);
+    assert!(diff.only_in_self.is_empty() && diff.only_in_other.is_empty());
+    assert!(diff.lin_eqs_only_in_self.is_empty());
+    let mut lhs = Vob::from_elem(5, false);
+    lhs.set(3, true);
+    assert_eq!(diff.lin_eqs_only_in_other, vec![(lhs.clone(), true)]);
+    assert_eq!(fixed.diff(&system).lin_eqs_only_in_self, vec![(lhs, true)]);
+
+    let mut popped = system.clone();
+    popped.pop_bdd(Id::new(1))?;
+    let diff = system.diff(&popped);
+    assert_eq!(diff.only_in_self, vec![Id::new(1)]);
+    assert_eq!(popped.diff(&system).only_in_other, vec![Id::new(1)]);
+    assert!(diff.changed.is_empty());
+    Ok(())
+}
+
+#[test]
+fn variable_occurrences_test() -> Result<(), Error> {
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let bdd_2 = bdd!(5;1;[("2+4",[(1;2,0)]);("",[(2;0,0)])]);
+    let system = system![bdd, bdd_2]?;
+    assert_eq!(system.variable_occurrences(), vec![1, 1, 3, 1, 2]);
+    assert_eq!(System::new().variable_occurrences(), Vec::<usize>::new());
+    Ok(())
+}
+
+#[test]
+fn protected_variables_test() -> Result<(), Error> {
+    let bdd = bdd!(8;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let mut system = system![bdd]?;
+    // x6 is replaced by x5, which is itself replaced by x3
+    system.add_linear_constraints(&[(vec![5, 6], false), (vec![3, 5], true), (vec![1, 4], true)])?;
+    assert_eq!(system.protected_variables(&[6]), vec![3, 5, 6]);
+    assert_eq!(system.protected_variables(&[4, 7]), vec![1, 4, 7]);
+    assert_eq!(system.protected_variables(&[1]), vec![1]);
+    assert_eq!(system.protected_variables(&[]), Vec::<usize>::new());
+    Ok(())
+}
+
+#[test]
+fn connected_components_test() -> Result<(), Error> {
+    let build = || {
+        system![
+            bdd!(8;0;[("0+1",[(1;2,3)]);("1",[(2;4,0);(3;0,4)]);("",[(4;0,0)])]),
+            bdd!(8;1;[("4+5",[(1;2,3)]);("5",[(2;4,0);(3;0,4)]);("",[(4;0,0)])]),
+            bdd!(8;2;[("1+2",[(1;2,0)]);("",[(2;0,0)])]),
+            bdd!(8;3;[("6",[(1;2,2)]);("",[(2;0,0)])])
+        ]
+    };
+    let mut system = build()?;
+    let components = system.connected_components();
+    assert_eq!(
+        components,
+        vec![vec![Id::new(0), Id::new(2)], vec![Id::new(1)], vec![Id::new(3)]]
+    );
+    let mut parts = Vec::new();
+    for ids in components.iter() {
+        parts.push(system.split(ids)?.get_solutions());
+    }
+    assert_eq!(system.iter_bdds().len(), 0);
+    let mut combined = system.combine_solutions(&parts);
+    combined.sort();
+    let mut expected = build()?.get_solutions();
+    expected.sort();
+    assert_eq!(combined.len(), 8);
+    assert_eq!(combined, expected);
+    Ok(())
+}
+
+#[test]
+fn bdd_hash_test() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+    use std::hash::{Hash, Hasher};
+    let hash = |bdd: &Bdd| {
+        let mut hasher = DefaultHasher::new();
+        bdd.hash(&mut hasher);
+        hasher.finish()
+    };
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    // same Bdd with other ids for the nodes and the Bdd
+    let renumbered = bdd!(5;1;[("1+2",[(7;9,8)]);("3+2",[(9;11,10);(8;11,0)]);("0+4",[(11;0,12);(10;12,0)]);("",[(12;0,0)])]);
+    let other_lhs = bdd!(5;2;[("1+3",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    assert_eq!(bdd, renumbered);
+    assert_eq!(hash(&bdd), hash(&renumbered));
+    assert_ne!(hash(&bdd), hash(&other_lhs));
+
+    let mut cache = HashMap::new();
+    cache.insert(bdd, "first");
+    assert_eq!(cache.get(&renumbered), Some(&"first"));
+    assert_eq!(cache.get(&other_lhs), None);
+    cache.insert(renumbered, "second");
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn apply_linear_map_test() {
+    let build = || bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    // x1 = x0 + x3 and x4 = x0 + 1
+    let x1 = LinEq::new(vob![true, true, false, true, false], false);
+    let x4 = LinEq::new(vob![true, false, false, false, true], true);
+    let mut sequential = build();
+    sequential.replace_var_in_bdd(1, &x1).unwrap();
+    sequential.replace_var_in_bdd(4, &x4).unwrap();
+    let mut batch = build();
+    batch.apply_linear_map(&[(1, x1), (4, x4)]).unwrap();
+    // the zero level absorbed leaves equal nodes
+    batch.fully_reduce();
+    sequential.fully_reduce();
+    assert_eq!(batch, sequential);
+    // swapping x2 and x3 needs the substitution to be simultaneous
+    let mut swapped = build();
+    swapped
+        .apply_linear_map(&[
+            (2, LinEq::new(vob![false, false, true, true, false], false)),
+            (3, LinEq::new(vob![false, false, true, true, false], false)),
+        ])
+        .unwrap();
+    let expected_result = bdd!(5;0;[("1+3",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    assert_eq!(swapped, expected_result);
+}
+
+#[test]
+fn drop_variable_test() -> Result<(), Error> {
+    let bdd = bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    let bdd_2 = bdd!(5;1;[("2+4",[(1;2,0)]);("",[(2;0,0)])]);
+    let mut system = system![bdd, bdd_2]?;
+    assert_eq!(system.drop_variable(5), Err(CrushError::VarOutOfRange(5)));
+    system.drop_variable(2)?;
+    assert_eq!(system.iter_bdds().len(), 1);
+    assert!(system
+        .iter_bdds()
+        .all(|(_, bdd)| bdd.borrow().iter_levels().all(|level| !level.is_var_set(2))));
+    assert_eq!(system.drop_variable(2), Err(CrushError::VarNotFound(2)));
+    Ok(())
+}
+
+#[test]
+fn get_solutions_order_test() -> Result<(), Error> {
+    let build = |reversed: bool| {
+        let mut bdds = vec![
+            bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]),
+            bdd!(5;1;[("2+4",[(1;2,3)]);("0",[(2;4,0);(3;0,4)]);("",[(4;0,0)])]),
+            bdd!(5;2;[("3",[(1;2,2)]);("",[(2;0,0)])]),
+        ];
+        if reversed {
+            bdds.reverse();
+        }
+        System::from_elem(bdds)
+    };
+    let solutions = build(false)?.get_solutions();
+    assert!(!solutions.is_empty());
+    assert_eq!(solutions, build(false)?.get_solutions());
+    assert_eq!(solutions, build(true)?.get_solutions());
+    Ok(())
+}
+
+#[test]
+fn get_solutions_is_cached_test() -> Result<(), Error> {
+    let build = || {
+        system![
+            bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]),
+            bdd!(5;1;[("2+4",[(1;2,3)]);("0",[(2;4,0);(3;0,4)]);("",[(4;0,0)])])
+        ]
+    };
+    let joins = |system: &System| {
+        system
+            .op_log()
+            .iter()
+            .filter(|op| matches!(op, SystemOp::JoinBdds { .. }))
+            .count()
+    };
+    let mut system = build()?;
+    system.set_op_recording(true);
+    let solutions = system.get_solutions();
+    assert_eq!(joins(&system), 1);
+    assert_eq!(system.get_solutions(), solutions);
+    assert_eq!(joins(&system), 1);
+    // reading the System keeps them, get_solutions joined its Bdds into a single one
+    let ids: Vec<Id> = system.iter_bdds().map(|(id, _)| *id).collect();
+    assert_eq!(ids.len(), 1);
+    system.get_bdd(ids[0])?;
+    assert_eq!(system.get_solutions(), solutions);
+    assert_eq!(joins(&system), 1);
+
+    // a change of the System forgets the cached solutions
+    system.fix(vec![3], true)?;
+    let fixed = system.get_solutions();
+    let mut expected = build()?;
+    expected.fix(vec![3], true)?;
+    assert_eq!(fixed, expected.get_solutions());
+    assert_ne!(fixed, solutions);
+    Ok(())
+}
+
+#[test]
+fn into_single_bdd_test() -> Result<(), Error> {
+    let mut system = system![
+        bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]),
+        bdd!(5;1;[("2+4",[(1;2,3)]);("0",[(2;4,0);(3;0,4)]);("",[(4;0,0)])])
+    ]?;
+    system.fix(vec![3], true)?;
+    let mut expected = system.clone().get_solutions();
+    expected.sort();
+
+    let lin_eqs = system.get_lin_eqs().to_vec();
+    let bdd = system.into_single_bdd()?;
+    assert!(bdd.get_lhs().iter().all(|lhs| !lhs[3]));
+    // the single bdd and the equations of the LinBank give back the whole system
+    let mut rebuilt = system![bdd]?;
+    for eq in lin_eqs.iter() {
+        rebuilt.fix(eq.get_lhs().iter_set_bits(..).collect(), eq.get_rhs())?;
+    }
+    let mut solutions = rebuilt.get_solutions();
+    solutions.sort();
+    assert!(!solutions.is_empty());
+    assert_eq!(solutions, expected);
+
+    assert_eq!(System::new().into_single_bdd().err(), Some(CrushError::EmptySystem));
+    Ok(())
+}
+
+#[test]
+fn export_import_lin_bank_test() -> Result<(), Error> {
+    let build = || {
+        system![
+            bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]),
+            bdd!(5;1;[("2+4",[(1;2,3)]);("0",[(2;4,0);(3;0,4)]);("",[(4;0,0)])]),
+            bdd!(5;2;[("3",[(1;0,2)]);("",[(2;0,0)])])
+        ]
+    };
+    let mut solved = build()?;
+    // solve partway: absorb the equation of the third bdd and join the first two
+    solved.scan_absorb_lin_eqs(Id::new(2))?;
+    solved.join_bdds(Id::new(0), Id::new(1))?;
+    assert!(solved.get_lin_bank_size() > 0);
+    let path = std::env::temp_dir().join(format!("crush_lin_bank_{}.txt", std::process::id()));
+    solved.export_lin_bank(&path)?;
+
+    let mut fresh = build()?;
+    let size = fresh.get_size();
+    assert_eq!(fresh.import_lin_bank(&path)?, solved.get_lin_bank_size());
+    std::fs::remove_file(&path)?;
+    assert_eq!(fresh.get_lin_bank_size(), solved.get_lin_bank_size());
+    // x3 is removed from the bdds as it was in the solved system
+    assert!(fresh.get_size() < size);
+    let mut expected = build()?.get_solutions();
+    expected.sort();
+    let mut solutions = fresh.get_solutions();
+    solutions.sort();
+    assert_eq!(solutions, expected);
+    Ok(())
+}
+
+#[test]
+fn bdd_size_selectors_test() -> Result<(), Error> {
+    let mut system = system![
+        bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]),
+        bdd!(5;1;[("3",[(1;0,2)]);("",[(2;0,0)])]),
+        bdd!(5;2;[("2+4",[(1;2,3)]);("0",[(2;4,0);(3;0,4)]);("",[(4;0,0)])]),
+        bdd!(5;3;[("4",[(1;2,0)]);("",[(2;0,0)])])
+    ]?;
+    assert_eq!(
+        system.bdd_sizes(),
+        vec![(Id::new(0), 6), (Id::new(1), 2), (Id::new(2), 4), (Id::new(3), 2)]
+    );
+    assert_eq!(system.largest_bdd_id(), Some(Id::new(0)));
+    // ties go to the lowest id
+    assert_eq!(system.smallest_bdd_id(), Some(Id::new(1)));
+    system.pop_bdd(Id::new(1))?;
+    assert_eq!(system.smallest_bdd_id(), Some(Id::new(3)));
+    assert_eq!(System::new().smallest_bdd_id(), None);
+    assert_eq!(System::new().largest_bdd_id(), None);
+    Ok(())
+}
+
+#[test]
+fn level_nodes_test() {
+    let bdd = bdd!(5;3;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]);
+    // the ids of the spec are shifted by the id of the bdd
+    let node = |id: usize| Id::new(id * 10000 + 3);
+    let edge = |id: usize| Some(node(id));
+    assert_eq!(bdd.level_nodes(0), vec![(node(1), edge(2), edge(3))]);
+    assert_eq!(
+        bdd.level_nodes(1),
+        vec![(node(2), edge(4), edge(5)), (node(3), edge(4), None)]
+    );
+    assert_eq!(
+        bdd.level_nodes(2),
+        vec![(node(4), None, edge(6)), (node(5), edge(6), None)]
+    );
+    assert_eq!(bdd.level_nodes(3), vec![(node(6), None, None)]);
+}
+
+#[test]
+fn system_equality_test() -> Result<(), Error> {
+    let build = || {
+        system![
+            bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]),
+            bdd!(5;1;[("2+4",[(1;2,3)]);("0",[(2;4,0);(3;0,4)]);("",[(4;0,0)])])
+        ]
+    };
+    let mut system = build()?;
+    system.fix(vec![3], true)?;
+    system.fix(vec![1, 2], false)?;
+    assert_eq!(system, system.clone());
+
+    // the LinBank is compared whatever the order of its equations
+    let mut reordered = build()?;
+    reordered.fix(vec![1, 2], false)?;
+    reordered.fix(vec![3], true)?;
+    assert_eq!(system, reordered);
+
+    let mut fixed = system.clone();
+    fixed.fix(vec![0], true)?;
+    assert_ne!(system, fixed);
+    let mut flipped = build()?;
+    flipped.fix(vec![3], true)?;
+    flipped.fix(vec![1, 2], true)?;
+    assert_ne!(system, flipped);
+    let mut grown = system.clone();
+    grown.grow_nvar(6);
+    assert_ne!(system, grown);
+    Ok(())
+}
+
+#[test]
+fn replay_op_log_test() -> Result<(), Error> {
+    let mut system = system![
+        bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])]),
+        bdd!(5;1;[("2+4",[(1;2,3)]);("0",[(2;4,0);(3;0,4)]);("",[(4;0,0)])])
+    ]?;
+    system.set_op_recording(true);
+    let initial = system.clone();
+    system.fix(vec![3], true)?;
+    system.join_bdds(Id::new(0), Id::new(1))?;
+    system.swap(Id::new(0), 0, 1)?;
+    system.add(Id::new(0), 1, 2)?;
+    system.drop(Id::new(0), 0)?;
+    system.scan_absorb_lin_eqs(Id::new(0))?;
+    // a failed operation is not logged
+    assert!(system.swap(Id::new(0), 0, 2).is_err());
+    assert_eq!(system.op_log().len(), 6);
+    assert_eq!(
+        system.op_log()[1],
+        SystemOp::JoinBdds {
+            bdd_1_id: Id::new(0),
+            bdd_2_id: Id::new(1)
+        }
+    );
+    let replayed = System::replay(system.op_log(), initial.clone())?;
+    assert_eq!(replayed, system);
+    assert_ne!(initial, system);
+
+    // the Bdds pushed, merged, split and drained are logged as well
+    let mut system = system![bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])])]?;
+    system.set_op_recording(true);
+    let initial = system.clone();
+    system.push_bdd(bdd!(5;1;[("2+4",[(1;2,3)]);("0",[(2;4,0);(3;0,4)]);("",[(4;0,0)])]))?;
+    let mut other = system![bdd!(5;2;[("3",[(1;2,0)]);("",[(2;0,0)])])]?;
+    other.fix(vec![1, 4], true)?;
+    system.merge(&mut other)?;
+    assert_eq!(system.get_lin_bank_size(), 1);
+    system.split(&[Id::new(1)])?;
+    let replayed = System::replay(system.op_log(), initial.clone())?;
+    assert_eq!(replayed, system);
+    system.drain_bdds();
+    assert_eq!(system.iter_bdds().len(), 0);
+    assert_eq!(System::replay(system.op_log(), initial)?, system);
+    Ok(())
+}
+
+#[test]
+fn export_system_to_xorcnf_test() -> Result<(), Error> {
+    // x1 + x2 = 1, x3 = 0 and x0 + x4 = 0
+    let system = system![
+        bdd!(5;0;[("1+2",[(1;0,2)]);("3",[(2;3,0)]);("",[(3;0,0)])]),
+        bdd!(5;1;[("0+4",[(1;2,0)]);("",[(2;0,0)])])
+    ]?;
+    let path = std::env::temp_dir().join(format!("crush_xorcnf_{}.cnf", std::process::id()));
+    utils::export_system_to_xorcnf(&system, &path)?;
+    let content = std::fs::read_to_string(&path)?;
+    let mut lines = content.lines();
+    assert_eq!(lines.next(), Some("p cnf 5 3"));
+    let clauses: Vec<&str> = lines.collect();
+    assert!(clauses.iter().all(|clause| clause.starts_with('x')));
+    assert_eq!(clauses, vec!["x2 3 0", "x-4 0", "x-1 5 0"]);
+
+    // a branching bdd needs a Tseitin variable per level and a variable per node
+    let system = system![
+        bdd!(5;0;[("1+2",[(1;2,3)]);("3+2",[(2;4,5);(3;4,0)]);("0+4",[(4;0,6);(5;6,0)]);("",[(6;0,0)])])
+    ]?;
+    utils::export_system_to_xorcnf(&system, &path)?;
+    let content = std::fs::read_to_string(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(content.lines().next(), Some("p cnf 13 12"));
+    Ok(())
+}
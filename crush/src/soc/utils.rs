@@ -1,6 +1,6 @@
 //! Module providing a set of tools to create `System` of bdds from file,
 //! print a Bdd to .dot format for visualization, print systems to .bdd format
-//! and needed structures for it.
+//! or to XOR-CNF and needed structures for it.
 
 use nom::digit;
 use nom::types::CompleteStr;
@@ -8,13 +8,14 @@ use nom::types::CompleteStr;
 use crate::soc::{
     system::System,
     bdd::Bdd,
+    level::Level,
     Id};
 
 use std::str::FromStr;
 use std::fs::File;
-use std::io::{Read, BufReader,BufWriter,Write};
+use std::io::{Read, BufReader,BufWriter,Write,Error,ErrorKind};
 use std::path::PathBuf;
-use std::collections::HashSet;
+use std::collections::{BTreeMap,BTreeSet,HashMap,HashSet};
 
 /// A specification of a `Node` inside a Bdd
 #[derive(Debug,Clone)]
@@ -148,7 +149,7 @@ pub fn build_system_from_spec(mut spec: SystemSpec) -> System {
 /// Once all the level have been created we connect all the nodes to each other following the
 /// `e0` and `e1` specs. All the id of the nodes are then reset to initialize `next_id` of the
 /// `Bdd`. Finally we remove any jumping edges by calling `add_same_edge_node_at_level` on all the
-/// levels of the `Bdd`.
+/// levels of the `Bdd`, which is checked with `Bdd::has_jumping_edges` in debug builds.
 pub fn build_bdd_from_spec(spec: &mut BddSpec, nvar: usize) -> Bdd {
     let mut bdd = Bdd::new();
     bdd.set_id(spec.id);
@@ -185,10 +186,11 @@ pub fn build_bdd_from_spec(spec: &mut BddSpec, nvar: usize) -> Bdd {
         }
     }
     if spec.levels.len() > 2 {
-        for i in 1..spec.levels.len()-2 {
+        for i in 1..spec.levels.len()-1 {
             bdd.add_same_edges_node_at_level(i);
         }
     }
+    debug_assert_eq!(bdd.has_jumping_edges(), None, "jumping edge left in the bdd");
     bdd
 }
 
@@ -312,6 +314,199 @@ pub fn parse_system_spec_from_file(path: &PathBuf) -> SystemSpec {
     result.1
 }
 
+/// Return a SystemSpec from the content of a .bdd file, or an error if `input` is not fully
+/// in the correct format or doesn't hold the number of Bdds announced on its first line.
+pub fn parse_system_spec(input: &str) -> Result<SystemSpec, Error> {
+    let invalid = |reason: &str| Error::new(ErrorKind::InvalidData, reason.to_string());
+    let (_, (_, nbr_bdd)) = parameters(CompleteStr(input))
+        .map_err(|_| invalid("the first line should be the nvar and the number of Bdds"))?;
+    let (rest, spec) = full_parser(CompleteStr(input))
+        .map_err(|_| invalid("the system is not in the .bdd format"))?;
+    if !rest.trim().is_empty() {
+        return Err(invalid("the system is not in the .bdd format"));
+    }
+    if spec.bdds.len() != nbr_bdd {
+        return Err(invalid(&format!(
+            "{} Bdds announced but {} found",
+            nbr_bdd,
+            spec.bdds.len()
+        )));
+    }
+    Ok(spec)
+}
+
+/// Return a SystemSpec from a system of GF(2) polynomial equations given in ANF, along
+/// with the number of variables used by the equations.
+///
+/// Each non empty line (lines starting with `#` are comments) is a sum of monomials
+/// equal to 0, such as `x0*x1 + x2 + 1`. A monomial is either `1`, `0`, a variable `xi`
+/// or the product of two variables.
+///
+/// Each distinct quadratic monomial `xi*xj` is replaced by an auxiliary variable `y`
+/// (numbered after the last `xi`) and a Bdd for the relation `y = xi*xj` is added. Every
+/// equation is then linear and becomes a Bdd made of a single level.
+pub fn parse_system_spec_from_anf(input: &str) -> Result<(SystemSpec, usize), Error> {
+    let mut equations = Vec::new();
+    for (line_number, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let equation = parse_anf_equation(line).map_err(|e| {
+            Error::new(ErrorKind::InvalidData, format!("line {}: {}", line_number + 1, e))
+        })?;
+        if equation.is_empty() {
+            continue;
+        }
+        if equation.len() == 1 && equation.contains(&Vec::new()) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: the equation 1 = 0 has no solution", line_number + 1),
+            ));
+        }
+        equations.push(equation);
+    }
+    let nvar = equations
+        .iter()
+        .flat_map(|equation| equation.iter().flatten())
+        .max()
+        .map_or(0, |var| var + 1);
+    let mut products: BTreeMap<Vec<usize>, usize> = BTreeMap::new();
+    let mut bdds = Vec::new();
+    for equation in equations.iter() {
+        let mut lhs = Vec::new();
+        for monomial in equation.iter() {
+            match monomial.len() {
+                0 => lhs.push(-1),
+                1 => lhs.push(monomial[0] as i64),
+                _ => {
+                    let next_aux = nvar + products.len();
+                    let aux = *products.entry(monomial.clone()).or_insert_with(|| {
+                        bdds.push(product_bdd_spec(monomial[0], monomial[1], next_aux));
+                        next_aux
+                    });
+                    lhs.push(aux as i64)
+                }
+            }
+        }
+        bdds.push(BddSpec::new(
+            Id::new(0),
+            vec![
+                LevelSpec::new(lhs, vec![NodeSpec::new(Id::new(1), Id::new(2), Id::new(0))]),
+                LevelSpec::new(Vec::new(), vec![NodeSpec::new(Id::new(2), Id::new(0), Id::new(0))]),
+            ],
+        ));
+    }
+    Ok((SystemSpec::new(nvar + products.len(), bdds), nvar))
+}
+
+/// Return a SystemSpec from the parsing of a file of equations in ANF (see
+/// `parse_system_spec_from_anf`) along with the number of variables used by the equations.
+pub fn parse_system_spec_from_anf_file(path: &PathBuf) -> Result<(SystemSpec, usize), Error> {
+    let mut file_content = String::new();
+    BufReader::new(File::open(path)?).read_to_string(&mut file_content)?;
+    parse_system_spec_from_anf(&file_content)
+}
+
+/// Return the linear constraints written in `input`, one `lhs = rhs` per line where `lhs` is
+/// a sum of variables ids (ex: `13+3+35 = 1`). Lines starting with `#` are comments.
+///
+/// The constraints are in the format expected by `System::add_linear_constraints`.
+pub fn parse_linear_constraints(input: &str) -> Result<Vec<(Vec<usize>, bool)>, Error> {
+    let mut constraints = Vec::new();
+    for (line_number, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: expected a constraint such as 13+3+35 = 1", line_number + 1),
+            )
+        };
+        let mut sides = line.split('=');
+        let (lhs, rhs) = match (sides.next(), sides.next(), sides.next()) {
+            (Some(lhs), Some(rhs), None) => (lhs, rhs),
+            _ => return Err(invalid()),
+        };
+        let lhs = lhs
+            .split('+')
+            .map(|var| usize::from_str(var.trim()))
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| invalid())?;
+        let rhs = match rhs.trim() {
+            "0" => false,
+            "1" => true,
+            _ => return Err(invalid()),
+        };
+        constraints.push((lhs, rhs));
+    }
+    Ok(constraints)
+}
+
+/// Return the linear constraints of the file at path (see `parse_linear_constraints`).
+pub fn load_linear_constraints(path: &PathBuf) -> Result<Vec<(Vec<usize>, bool)>, Error> {
+    let mut file_content = String::new();
+    BufReader::new(File::open(path)?).read_to_string(&mut file_content)?;
+    parse_linear_constraints(&file_content)
+}
+
+/// Parse a sum of monomials, each monomial being the sorted list of its variables (empty
+/// for the constant 1). Monomials appearing twice cancel each other.
+fn parse_anf_equation(line: &str) -> Result<BTreeSet<Vec<usize>>, String> {
+    let mut monomials = BTreeSet::new();
+    for term in line.split('+') {
+        let mut monomial = BTreeSet::new();
+        let mut is_zero = false;
+        for factor in term.split('*').map(str::trim) {
+            match factor {
+                "1" => (),
+                "0" => is_zero = true,
+                _ => match factor.strip_prefix('x').map(usize::from_str) {
+                    Some(Ok(var)) => {
+                        monomial.insert(var);
+                    }
+                    _ => return Err(format!("invalid factor \"{}\"", factor)),
+                },
+            }
+        }
+        if monomial.len() > 2 {
+            return Err(format!("the monomial \"{}\" has a degree higher than 2", term.trim()));
+        }
+        let monomial: Vec<usize> = monomial.into_iter().collect();
+        if !is_zero && !monomials.remove(&monomial) {
+            monomials.insert(monomial);
+        }
+    }
+    Ok(monomials)
+}
+
+/// Make the spec of a Bdd for the relation `aux = a*b`.
+fn product_bdd_spec(a: usize, b: usize, aux: usize) -> BddSpec {
+    BddSpec::new(
+        Id::new(0),
+        vec![
+            LevelSpec::new(vec![a as i64], vec![NodeSpec::new(Id::new(1), Id::new(2), Id::new(3))]),
+            LevelSpec::new(
+                vec![b as i64],
+                vec![
+                    NodeSpec::new(Id::new(2), Id::new(4), Id::new(4)),
+                    NodeSpec::new(Id::new(3), Id::new(4), Id::new(5)),
+                ],
+            ),
+            LevelSpec::new(
+                vec![aux as i64],
+                vec![
+                    NodeSpec::new(Id::new(4), Id::new(6), Id::new(0)),
+                    NodeSpec::new(Id::new(5), Id::new(0), Id::new(6)),
+                ],
+            ),
+            LevelSpec::new(Vec::new(), vec![NodeSpec::new(Id::new(6), Id::new(0), Id::new(0))]),
+        ],
+    )
+}
+
 /// Write .dot langage representation of the given bdd to a file at path
 pub fn print_bdd_to_graphviz(bdd: &Bdd, path:&PathBuf) {
     let write_file = File::create(path).unwrap();
@@ -421,3 +616,112 @@ pub fn print_system_to_file(system: &System, path: &PathBuf){
         print_bdd_to_file_format(&system.get_bdd(*id).unwrap().borrow(), &mut writer);
     }
 }
+
+/// Return the XOR clause, in the format of CryptoMiniSat, saying that the sum of the DIMACS
+/// literals `vars` is `rhs`, or None if the clause always holds (no literal and `rhs` false).
+///
+/// A clause `x1 2 0` says that x1 + x2 = 1, the first literal is negated to say that the sum
+/// is 0. With no literal and `rhs` true, the empty clause `0` is returned.
+fn xor_clause(vars: &[usize], rhs: bool) -> Option<String> {
+    if vars.is_empty() {
+        return if rhs { Some("0".to_string()) } else { None };
+    }
+    let mut clause = String::from("x");
+    for (i, var) in vars.iter().enumerate() {
+        if i == 0 && !rhs {
+            clause.push('-');
+        }
+        clause.push_str(&format!("{} ", var));
+    }
+    clause.push('0');
+    Some(clause)
+}
+
+/// Write the clauses of `bdd` to `clauses`, the variables above `nvar` it needs being
+/// numbered from `next_var`, and return the next variable left unused.
+///
+/// A `Bdd` whose levels all have a single node is a conjunction of linear equations, one
+/// per level whose node has a single edge, and is written as XOR clauses only. Otherwise
+/// each level `i` gets a Tseitin variable t_i = lhs_i written as a XOR clause, and each
+/// node but the sink a variable saying it's on the path: the source is on it, and a node on
+/// it with an edge for the value of t_i has the end of that edge on it too (if t_i can't
+/// take this value from this node, it doesn't take it).
+fn bdd_to_xorcnf(bdd: &Bdd, next_var: usize, clauses: &mut Vec<String>) -> usize {
+    let sink_index = bdd.get_sink_level_index();
+    let levels = || bdd.iter_levels().take(sink_index);
+    let lhs_vars = |level: &Level| level.iter_set_lhs().map(|var| var + 1).collect::<Vec<usize>>();
+    if levels().all(|level| level.iter_nodes().count() == 1) {
+        for level in levels() {
+            let node = level.iter_nodes().next().unwrap().1;
+            let rhs = match (node.get_e0(), node.get_e1()) {
+                (Some(_), Some(_)) => continue,
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (None, None) => {
+                    clauses.push("0".to_string());
+                    continue;
+                }
+            };
+            clauses.extend(xor_clause(&lhs_vars(level), rhs));
+        }
+        return next_var;
+    }
+    let mut next_var = next_var;
+    let mut node_vars = HashMap::new();
+    for level in levels() {
+        for (id, _) in level.iter_nodes() {
+            node_vars.insert(*id, next_var);
+            next_var += 1;
+        }
+    }
+    let source = *bdd.iter_levels().next().unwrap().iter_nodes().next().unwrap().0;
+    clauses.push(format!("{} 0", node_vars[&source]));
+    for level in levels() {
+        let t = next_var;
+        next_var += 1;
+        let mut vars = lhs_vars(level);
+        vars.push(t);
+        clauses.extend(xor_clause(&vars, false));
+        for (id, node) in level.iter_nodes() {
+            let on_path = node_vars[id];
+            for (edge, t_literal) in [(node.get_e0(), t as i64), (node.get_e1(), -(t as i64))] {
+                match edge.map(|end| node_vars.get(&end)) {
+                    None => clauses.push(format!("-{} {} 0", on_path, t_literal)),
+                    Some(Some(end)) => {
+                        clauses.push(format!("-{} {} {} 0", on_path, t_literal, end))
+                    }
+                    // the sink is always on the path
+                    Some(None) => (),
+                }
+            }
+        }
+    }
+    next_var
+}
+
+/// Write the XOR-CNF representation of a system to a file at path, in the DIMACS format
+/// extended with the XOR clauses of CryptoMiniSat (see `xor_clause`).
+///
+/// The variable i of the system is the DIMACS variable i+1, the variables above `nvar` are
+/// the Tseitin and node variables encoding the branching of the `Bdd`s (see
+/// `bdd_to_xorcnf`). The equations of the LinBank are written as XOR clauses as well.
+pub fn export_system_to_xorcnf(system: &System, path: &PathBuf) -> Result<(), Error> {
+    let mut clauses = Vec::new();
+    for lin_eq in system.get_lin_eqs() {
+        let vars = lin_eq.get_lhs().iter_set_bits(..).map(|var| var + 1).collect::<Vec<usize>>();
+        clauses.extend(xor_clause(&vars, lin_eq.get_rhs()));
+    }
+    let mut ids = system.iter_bdds().map(|(id, _)| *id).collect::<Vec<Id>>();
+    ids.sort();
+    let mut next_var = system.get_nvar() + 1;
+    for id in ids {
+        let bdd = system.get_bdd(id).unwrap().borrow();
+        next_var = bdd_to_xorcnf(&bdd, next_var, &mut clauses);
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "p cnf {} {}", next_var - 1, clauses.len())?;
+    for clause in clauses {
+        writeln!(writer, "{}", clause)?;
+    }
+    writer.flush()
+}
@@ -1,8 +1,8 @@
 //! Provide the traits to create solving strategies using the apis of `soc::System`.
 
 
+use crate::error::CrushError;
 use crate::soc::{system::System, Id};
-use std::io::Error;
 use std::result::Result;
 
 /// Describe a dependency inside a `System` of `Bdd`. A `Dependency`
@@ -62,7 +62,7 @@ pub trait Solver {
     fn solve<T: Dependency>(
         &mut self,
         system: &mut System,
-    ) -> Result<Vec<Vec<Option<bool>>>, Error> {
+    ) -> Result<Vec<Vec<Option<bool>>>, CrushError> {
         Self::absorb_all_equations(system)?;
         let mut deps = T::extract(system);
         while !deps.is_empty() {
@@ -105,13 +105,12 @@ pub trait Solver {
             system.get_size(),
             system.get_lin_bank_size()
         );
-        let max_size = system.iter_bdds().fold(0, |size, bdd| {
-            if bdd.1.borrow().get_size() > size {
-                (bdd.1.borrow().get_size())
-            } else {
-                size
-            }
-        });
+        let max_size = system
+            .bdd_sizes()
+            .iter()
+            .map(|(_, size)| *size)
+            .max()
+            .unwrap_or(0);
         println!("biggest bdd has {} nodes", max_size);
     }
 
@@ -123,18 +122,14 @@ pub trait Solver {
         &self,
         system: &mut System,
         join_order: (Vec<Id>, Vec<usize>),
-    ) -> Result<(), Error> {
+    ) -> Result<(), CrushError> {
         let mut keys_iter = join_order.0.iter();
         let bdd_root_id = keys_iter.next().unwrap();
         for key in keys_iter {
-            system
-                .join_bdds(*bdd_root_id, *key)
-                .expect("should not crash when joining");
+            system.join_bdds(*bdd_root_id, *key)?;
         }
         for i in (0..join_order.1.len() - 1).rev() {
-            for j in (join_order.1[i] + 1..join_order.1[i + 1]).rev() {
-                system.swap(*bdd_root_id, j, j + 1)?;
-            }
+            system.move_level(*bdd_root_id, join_order.1[i + 1], join_order.1[i] + 1)?;
             system.add(*bdd_root_id, join_order.1[i], join_order.1[i] + 1)?;
             if i != 0 {
                 system.swap(*bdd_root_id, join_order.1[i], join_order.1[i] + 1)?;
@@ -148,7 +143,7 @@ pub trait Solver {
     /// Go through all BDDs and check for equation to absorb
     /// until there are no left. If when absorbing a BDD is reduced to
     /// its sink then we remove it from the system
-    fn absorb_all_equations(system: &mut System) -> Result<(), Error> {
+    fn absorb_all_equations(system: &mut System) -> Result<(), CrushError> {
         let mut absorbed = true;
         while absorbed {
             absorbed = false;
@@ -157,11 +152,7 @@ pub trait Solver {
                 .map(|bdd| *bdd.0)
                 .collect::<Vec<Id>>();
             for id in ids.iter() {
-                if system
-                    .scan_absorb_lin_eqs(*id)
-                    .expect("shouldn't crash when absorbing lin eq")
-                    > 0
-                {
+                if system.scan_absorb_lin_eqs(*id)? > 0 {
                     absorbed = true;
                 }
             }
@@ -202,9 +193,11 @@ pub trait Solver {
 pub trait DroppingSolver {
     /// Remove every linear dependency in a `System` using absorbtion and dropping and return the solutions.
     ///
-    /// If `forbid_dropping` is `Some` the variable it contains should not be dropped. `solve` is responsible
-    /// for choosing if an `Independency` or a `Dependency` should be resolved next, base on the
-    /// `minimize_distance` result of the best `Independency` and `Dependency`.
+    /// If `forbid_dropping` is `Some` the variable it contains should not be dropped, nor the
+    /// variables they were replaced by in the `LinBank` (see `System::protected_variables`).
+    /// `solve` is responsible for choosing if an `Independency` or a `Dependency` should be
+    /// resolved next, base on the `minimize_distance` result of the best `Independency` and
+    /// `Dependency`.
     ///
     /// Not all possible drop have to be made as the purpose of dropping is only to make absorbing the
     /// dependencies faster, so we exit and get the solutions as soon as no dependencies are left
@@ -213,10 +206,11 @@ pub trait DroppingSolver {
         &mut self,
         system: &mut System,
         forbid_dropping: Option<&[usize]>,
-    ) -> Result<Vec<Vec<Option<bool>>>, Error> {
+    ) -> Result<Vec<Vec<Option<bool>>>, CrushError> {
         Self::absorb_all_equations(system)?;
         let mut deps = D::extract(system);
-        let mut indeps = I::extract(system, forbid_dropping);
+        let protected = forbid_dropping.map(|vars| system.protected_variables(vars));
+        let mut indeps = I::extract(system, protected.as_deref());
         while !deps.is_empty() {
             let (id_dep, min_distance_dep) = Self::pick_best_dep(&deps);
             let (id_indep, min_distance_indep) = Self::pick_best_indep(&indeps);
@@ -229,7 +223,8 @@ pub trait DroppingSolver {
             Self::absorb_all_equations(system)?;
             Self::feedback(self, system);
             deps = D::extract(system);
-            indeps = I::extract(system, forbid_dropping);
+            let protected = forbid_dropping.map(|vars| system.protected_variables(vars));
+            indeps = I::extract(system, protected.as_deref());
         }
         Ok(system.get_solutions())
     }
@@ -246,13 +241,11 @@ pub trait DroppingSolver {
         &self,
         system: &mut System,
         join_order: (Vec<Id>, Vec<usize>),
-    ) -> Result<(), Error> {
+    ) -> Result<(), CrushError> {
         let mut keys_iter = join_order.0.iter();
         let bdd_root_id = keys_iter.next().unwrap();
         for key in keys_iter {
-            system
-                .join_bdds(*bdd_root_id, *key)
-                .expect("should not crash when joining");
+            system.join_bdds(*bdd_root_id, *key)?;
         }
         for i in 0..join_order.1.len() - 1 {
             system.add(*bdd_root_id, join_order.1[i], join_order.1[i + 1])?;
@@ -272,18 +265,14 @@ pub trait DroppingSolver {
         &self,
         system: &mut System,
         join_order: (Vec<Id>, Vec<usize>),
-    ) -> Result<(), Error> {
+    ) -> Result<(), CrushError> {
         let mut keys_iter = join_order.0.iter();
         let bdd_root_id = keys_iter.next().unwrap();
         for key in keys_iter {
-            system
-                .join_bdds(*bdd_root_id, *key)
-                .expect("should not crash when joining");
+            system.join_bdds(*bdd_root_id, *key)?;
         }
         for i in (0..join_order.1.len() - 1).rev() {
-            for j in (join_order.1[i] + 1..join_order.1[i + 1]).rev() {
-                system.swap(*bdd_root_id, j, j + 1)?;
-            }
+            system.move_level(*bdd_root_id, join_order.1[i + 1], join_order.1[i] + 1)?;
             system.add(*bdd_root_id, join_order.1[i], join_order.1[i] + 1)?;
             if i != 0 {
                 system.swap(*bdd_root_id, join_order.1[i], join_order.1[i] + 1)?;
@@ -328,7 +317,7 @@ pub trait DroppingSolver {
     /// Go through all BDDs and check for equation to absorb
     /// until there are no left. If when absorbing a BDD is reduced to
     /// its sink then we remove it from the system
-    fn absorb_all_equations(system: &mut System) -> Result<(), Error> {
+    fn absorb_all_equations(system: &mut System) -> Result<(), CrushError> {
         let mut absorbed = true;
         while absorbed {
             absorbed = false;
@@ -337,11 +326,7 @@ pub trait DroppingSolver {
                 .map(|bdd| *bdd.0)
                 .collect::<Vec<Id>>();
             for id in ids.iter() {
-                if system
-                    .scan_absorb_lin_eqs(*id)
-                    .expect("shouldn't crash when absorbing lin eq")
-                    > 0
-                {
+                if system.scan_absorb_lin_eqs(*id)? > 0 {
                     absorbed = true;
                 }
             }
@@ -367,13 +352,12 @@ pub trait DroppingSolver {
             system.get_size(),
             system.get_lin_bank_size(),
         );
-        let max_size = system.iter_bdds().fold(0, |size, bdd| {
-            if bdd.1.borrow().get_size() > size {
-                (bdd.1.borrow().get_size())
-            } else {
-                size
-            }
-        });
+        let max_size = system
+            .bdd_sizes()
+            .iter()
+            .map(|(_, size)| *size)
+            .max()
+            .unwrap_or(0);
         println!("biggest bdd has {} nodes", max_size);
     }
 }
@@ -0,0 +1,134 @@
+//! Polynomials over GF(2), used to represent the algebraic normal form (ANF) of the
+//! output bits of a S-Box.
+//!
+//! A `GF2Poly` is a sum of monomials, each monomial being the product of the variables it
+//! contains (kept in a BTreeSet), the empty monomial standing for the constant 1. Since
+//! x * x = x and x + x = 0 over GF(2) a monomial is a set of variables and a polynomial is a
+//! set of monomials.
+//!
+//! Two `GF2Poly` can be added (XORed) by using the implementation of `BitXor` or
+//! `BitXorAssign`, which keep the symmetric difference of their monomials.
+
+use std::collections::BTreeSet;
+use std::ops::{BitXor, BitXorAssign};
+
+/// A monomial, the product of the variables it contains (1 if it is empty).
+pub type Monomial = BTreeSet<usize>;
+
+/// A polynomial over GF(2) in algebraic normal form, the sum of its monomials.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GF2Poly {
+    monomials: BTreeSet<Monomial>,
+}
+
+impl GF2Poly {
+    /// Returns the zero polynomial.
+    #[inline]
+    pub fn new() -> Self {
+        GF2Poly::default()
+    }
+
+    /// Returns the sum of `monomials`, a monomial appearing twice cancels out.
+    pub fn from_monomials<I: IntoIterator<Item = Monomial>>(monomials: I) -> Self {
+        let mut poly = GF2Poly::new();
+        for monomial in monomials {
+            poly ^= GF2Poly {
+                monomials: std::iter::once(monomial).collect(),
+            };
+        }
+        poly
+    }
+
+    /// Returns an iterator over the monomials of the polynomial, in increasing order.
+    #[inline]
+    pub fn monomials(&self) -> impl Iterator<Item = &Monomial> {
+        self.monomials.iter()
+    }
+
+    /// Returns true if the polynomial is zero.
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        self.monomials.is_empty()
+    }
+
+    /// Returns the degree of the polynomial, the number of variables of its biggest monomial
+    /// (0 for a constant, including the zero polynomial).
+    pub fn degree(&self) -> usize {
+        self.monomials.iter().map(|monomial| monomial.len()).max().unwrap_or(0)
+    }
+
+    /// Evaluate the polynomial, the variable `i` taking the value `assignment[i]`.
+    ///
+    /// Panics if a variable of the polynomial is out of the range of `assignment`.
+    pub fn evaluate(&self, assignment: &[bool]) -> bool {
+        self.monomials
+            .iter()
+            .filter(|monomial| monomial.iter().all(|var| assignment[*var]))
+            .count()
+            % 2
+            == 1
+    }
+}
+
+impl BitXor for GF2Poly {
+    type Output = Self;
+
+    fn bitxor(self, rhs: GF2Poly) -> Self::Output {
+        GF2Poly {
+            monomials: self
+                .monomials
+                .symmetric_difference(&rhs.monomials)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+impl BitXorAssign for GF2Poly {
+    fn bitxor_assign(&mut self, rhs: GF2Poly) {
+        self.monomials = self
+            .monomials
+            .symmetric_difference(&rhs.monomials)
+            .cloned()
+            .collect();
+    }
+}
+
+#[cfg(test)]
+fn poly(monomials: &[&[usize]]) -> GF2Poly {
+    GF2Poly::from_monomials(
+        monomials
+            .iter()
+            .map(|monomial| monomial.iter().cloned().collect()),
+    )
+}
+
+#[test]
+fn test_addition() {
+    // (x0*x1 + x2 + 1) + (x2 + x1) = x0*x1 + x1 + 1
+    let sum = poly(&[&[0, 1], &[2], &[]]) ^ poly(&[&[2], &[1]]);
+    assert_eq!(sum, poly(&[&[0, 1], &[1], &[]]));
+    let mut sum = poly(&[&[0, 1], &[2]]);
+    sum ^= poly(&[&[0, 1], &[2]]);
+    assert!(sum.is_zero());
+    assert!(poly(&[&[3], &[3]]).is_zero());
+}
+
+#[test]
+fn test_evaluation() {
+    // x0*x1 + x2 + 1
+    let p = poly(&[&[0, 1], &[2], &[]]);
+    assert!(p.evaluate(&[false, false, false]));
+    assert!(!p.evaluate(&[true, true, false]));
+    assert!(p.evaluate(&[true, true, true]));
+    assert!(!p.evaluate(&[false, true, true]));
+    assert!(!GF2Poly::new().evaluate(&[true]));
+}
+
+#[test]
+fn test_degree() {
+    assert_eq!(poly(&[&[0, 1, 3], &[2], &[]]).degree(), 3);
+    assert_eq!(poly(&[&[4]]).degree(), 1);
+    assert_eq!(poly(&[&[]]).degree(), 0);
+    assert_eq!(GF2Poly::new().degree(), 0);
+}
@@ -0,0 +1,503 @@
+//! A benchmark harness solving the supported ciphers with the different strategies.
+//!
+//! The plaintext and the key of each instance are drawn from a seeded generator so that
+//! runs can be compared with each other. For every cipher and strategy we record the wall
+//! time of the solving, the maximum number of nodes reached by the system and whether the
+//! key was recovered, one CSV row per solving.
+
+use crate::bit::{self, Bit};
+use crate::rand::{rngs::StdRng, SeedableRng};
+use crate::strategy::{self, LogLevel, UpwardDroppingSolver};
+use crate::targets::{
+    build_cipher_by_name, build_system_cipher, build_system_sponge, fix_system_values_cipher,
+    fix_system_values_cipher_with_partial_key, fix_system_values_sponge, max_rounds_by_name,
+    Cipher, SpongeConfig,
+};
+use crush::{error::CrushError, soc::system::System};
+use std::io::{Error, ErrorKind};
+use std::time::Instant;
+
+/// The header of the CSV produced by the rows of `bench_cipher`.
+pub const CSV_HEADER: &str = "cipher,rounds,strategy,seconds,peak_nodes,recovered";
+
+/// The result of solving one instance of a cipher with one strategy.
+#[derive(Clone, Debug)]
+pub struct BenchRow {
+    pub cipher: String,
+    pub rounds: usize,
+    pub strategy: &'static str,
+    pub seconds: f64,
+    pub peak_nodes: usize,
+    pub recovered: bool,
+}
+
+impl BenchRow {
+    /// Format the row following `CSV_HEADER`.
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{:.3},{},{}",
+            self.cipher, self.rounds, self.strategy, self.seconds, self.peak_nodes, self.recovered
+        )
+    }
+}
+
+/// Solve an instance of the cipher `name` with each of the supported strategies (see
+/// `strategy::supported_strategies`) and return a row for each.
+///
+/// The plaintext and the key are drawn from a generator seeded with `seed`. Returns `None` if the
+/// cipher is not supported.
+pub fn bench_cipher(name: &str, rounds: usize, seed: u64) -> Option<Vec<BenchRow>> {
+    let cipher = build_cipher_by_name(name, Some(rounds))?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let plaintext = bit::random_bits_with_rng(cipher.message_length(), &mut rng);
+    let key = bit::random_bits_with_rng(cipher.key_length(), &mut rng);
+    let ciphertext = cipher.encrypt(plaintext.clone(), key);
+    let forbid_dropping: Vec<usize> = (0..cipher.key_length()).collect();
+    let strategies = strategy::supported_strategies();
+    let mut rows = Vec::with_capacity(strategies.len());
+    for strategy in strategies.iter() {
+        // a new cipher for each system, as its S-Box keeps numbering the variables
+        let cipher = build_cipher_by_name(name, Some(rounds))?;
+        let (layout, mut system) = build_system_cipher(cipher.as_ref());
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        let (result, seconds, peak_nodes) =
+            solve_with_strategy(strategy, &mut system, &forbid_dropping)?;
+        let recovered = recovers_key(cipher.as_ref(), &plaintext, &ciphertext, &result);
+        rows.push(BenchRow {
+            cipher: name.to_string(),
+            rounds,
+            strategy,
+            seconds,
+            peak_nodes,
+            recovered,
+        });
+    }
+    Some(rows)
+}
+
+/// The result of solving many instances of a cipher with one strategy, see
+/// `run_success_experiment`.
+#[derive(Clone, Debug)]
+pub struct SuccessReport {
+    pub cipher: String,
+    pub rounds: usize,
+    pub strategy: String,
+    pub instances: usize,
+    /// The number of instances for which the key was among the solutions.
+    pub recovered: usize,
+}
+
+impl SuccessReport {
+    /// The fraction of the instances for which the key was recovered, 0 if there was none.
+    pub fn success_rate(&self) -> f64 {
+        if self.instances == 0 {
+            return 0.0;
+        }
+        self.recovered as f64 / self.instances as f64
+    }
+}
+
+/// Solve `instances` random instances of the cipher `name` with `strategy` and count how many
+/// times the key was recovered.
+///
+/// The plaintexts and the keys of all the instances are drawn in turn from a single generator
+/// seeded with `seed`. An aborted solving counts as a failure. Returns `None` if the cipher or
+/// the strategy is not supported.
+pub fn run_success_experiment(
+    name: &str,
+    rounds: usize,
+    instances: usize,
+    strategy: &str,
+    seed: u64,
+) -> Option<SuccessReport> {
+    if !strategy::supported_strategies().contains(&strategy)
+        || build_cipher_by_name(name, Some(rounds)).is_none()
+    {
+        return None;
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut recovered = 0;
+    for _ in 0..instances {
+        // a new cipher for each system, as its S-Box keeps numbering the variables
+        let cipher = build_cipher_by_name(name, Some(rounds))?;
+        let plaintext = bit::random_bits_with_rng(cipher.message_length(), &mut rng);
+        let key = bit::random_bits_with_rng(cipher.key_length(), &mut rng);
+        let ciphertext = cipher.encrypt(plaintext.clone(), key);
+        let forbid_dropping: Vec<usize> = (0..cipher.key_length()).collect();
+        let (layout, mut system) = build_system_cipher(cipher.as_ref());
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        let (result, _, _) = solve_with_strategy(strategy, &mut system, &forbid_dropping)?;
+        if recovers_key(cipher.as_ref(), &plaintext, &ciphertext, &result) {
+            recovered += 1;
+        }
+    }
+    Some(SuccessReport {
+        cipher: name.to_string(),
+        rounds,
+        strategy: strategy.to_string(),
+        instances,
+        recovered,
+    })
+}
+
+/// The result of solving one round count, see `sweep_rounds`.
+#[derive(Clone, Debug)]
+pub struct SweepStep {
+    pub rounds: usize,
+    pub seconds: f64,
+    pub peak_nodes: usize,
+    /// Whether the solving finished within the budget and recovered the key.
+    pub solved: bool,
+}
+
+/// Solve instances of the cipher `name` with an increasing number of rounds, starting from 1 (2
+/// for prince which needs an even number of rounds), until one of them can't be solved within the
+/// budget or `max_rounds` is reached (capped to the maximum of the cipher, see
+/// `max_rounds_by_name`). Return a `SweepStep` for each round count tried.
+///
+/// The budget is a maximum number of nodes of a single `Bdd` (the solving is then aborted) and a
+/// maximum solving time (checked once the solving is done). The plaintext and the key of every
+/// instance are drawn from a generator seeded with `seed`, the first `known_key_bits` bits of the
+/// key being given to the solver. Returns `None` if the cipher is not supported.
+pub fn sweep_rounds(
+    name: &str,
+    max_rounds: usize,
+    known_key_bits: usize,
+    max_bdd_nodes: Option<usize>,
+    max_seconds: Option<f64>,
+    seed: u64,
+) -> Option<Vec<SweepStep>> {
+    let step = if name.starts_with("prince") { 2 } else { 1 };
+    let max_rounds = max_rounds_by_name(name).map_or(max_rounds, |max| max.min(max_rounds));
+    let mut steps = Vec::new();
+    for rounds in (step..=max_rounds).step_by(step) {
+        let cipher = build_cipher_by_name(name, Some(rounds))?;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let plaintext = bit::random_bits_with_rng(cipher.message_length(), &mut rng);
+        let key = bit::random_bits_with_rng(cipher.key_length(), &mut rng);
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        let (layout, mut system) = build_system_cipher(cipher.as_ref());
+        system.set_max_bdd_nodes(max_bdd_nodes);
+        let known_bits: Vec<usize> = (0..known_key_bits.min(cipher.key_length())).collect();
+        fix_system_values_cipher_with_partial_key(
+            &mut system,
+            &layout,
+            &plaintext,
+            &ciphertext,
+            (key, known_bits),
+        );
+        let (result, seconds, peak_nodes) = solve_with_strategy("no_drop", &mut system, &[])?;
+        let within_time = match max_seconds {
+            Some(max_seconds) => seconds <= max_seconds,
+            None => true,
+        };
+        let solved =
+            within_time && recovers_key(cipher.as_ref(), &plaintext, &ciphertext, &result);
+        steps.push(SweepStep {
+            rounds,
+            seconds,
+            peak_nodes,
+            solved,
+        });
+        if !solved {
+            break;
+        }
+    }
+    Some(steps)
+}
+
+/// The result of looking for a preimage of one message length, see `search_preimage_length`.
+#[derive(Clone, Debug)]
+pub struct PreimageStep {
+    pub message_length: usize,
+    pub seconds: f64,
+    /// The preimage found (including the padding), `None` if there is none of this length or if
+    /// the solving was aborted.
+    pub preimage: Option<Vec<Bit>>,
+}
+
+/// Look for a preimage of `hash_value` under the sponge of `config` with an increasing message
+/// length: one block of the rate, then two blocks and so on up to `max_blocks` blocks (the message
+/// length of `config` is not used). Return a `PreimageStep` for each message length tried, the
+/// search stopping at the first length with a valid preimage.
+///
+/// For each length the system is built, its padding and output fixed (see
+/// `fix_system_values_sponge`) and solved without dropping the message variables, a solution
+/// being valid if it hashes to `hash_value`. A system found without solution (see
+/// `CrushError::NoSolution`) or a solving aborted because a `Bdd` exceeds `max_bdd_nodes` counts
+/// as no preimage of this length. Returns `None` if the sponge is not supported or doesn't take
+/// the message length (as the crunchy presets).
+pub fn search_preimage_length(
+    config: &SpongeConfig,
+    hash_value: &[Bit],
+    max_blocks: usize,
+    max_bdd_nodes: Option<usize>,
+) -> Option<Vec<PreimageStep>> {
+    let mut steps = Vec::new();
+    for blocks in 1..=max_blocks {
+        let message_length = blocks * config.rate;
+        let config = SpongeConfig {
+            message_length,
+            ..config.clone()
+        };
+        let hash = config.build()?;
+        if hash.message_length() != message_length {
+            return None;
+        }
+        let start = Instant::now();
+        let (output, mut system) = build_system_sponge(hash.as_ref());
+        system.set_max_bdd_nodes(max_bdd_nodes);
+        let forbid_dropping: Vec<usize> = (0..message_length).collect();
+        let mut solver = UpwardDroppingSolver::with_log_level(LogLevel::Quiet);
+        let result = fix_system_values_sponge(hash.as_ref(), &mut system, hash_value, &output)
+            .and_then(|_| solver.improved_solve(&mut system, Some(&forbid_dropping)));
+        let solutions = match result {
+            Ok(solutions) => solutions,
+            // no preimage of this length, or none found within the budget
+            Err(CrushError::NoSolution(_))
+            | Err(CrushError::Contradiction)
+            | Err(CrushError::NodeBudget) => Vec::new(),
+            Err(e) => panic!("the solving of the sponge failed: {}", e),
+        };
+        let preimage = solutions
+            .iter()
+            .map(|solution| {
+                solution
+                    .iter()
+                    .take(message_length)
+                    .map(|var| bit!(var.unwrap_or(false)))
+                    .collect::<Vec<Bit>>()
+            })
+            .find(|preimage| hash.hash(preimage.clone()) == hash_value);
+        let found = preimage.is_some();
+        steps.push(PreimageStep {
+            message_length,
+            seconds: start.elapsed().as_secs_f64(),
+            preimage,
+        });
+        if found {
+            break;
+        }
+    }
+    Some(steps)
+}
+
+/// The result of solving a system with one strategy, see `compare_strategies`.
+#[derive(Clone, Debug)]
+pub struct StrategyRun {
+    pub strategy: String,
+    pub seconds: f64,
+    pub peak_nodes: usize,
+    pub solutions: usize,
+    /// The distinct values of the first `key_length` variables over all the solutions, sorted.
+    pub keys: Vec<Vec<Option<bool>>>,
+}
+
+/// Solve an independent clone of `system` with each of the `strategies` and return a
+/// `StrategyRun` for each, in the same order.
+///
+/// The variables in `forbid_dropping` are never dropped by the dropping strategies. Returns an
+/// `Error` if a strategy is not supported or if a solving is aborted.
+pub fn compare_strategies(
+    system: &System,
+    strategies: &[String],
+    forbid_dropping: &[usize],
+    key_length: usize,
+) -> Result<Vec<StrategyRun>, Error> {
+    let mut runs = Vec::with_capacity(strategies.len());
+    for strategy in strategies.iter() {
+        let mut system = system.clone();
+        let (result, seconds, peak_nodes) =
+            match solve_with_strategy(strategy, &mut system, forbid_dropping) {
+                Some(solved) => solved,
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("strategy {} not supported", strategy),
+                    ))
+                }
+            };
+        let solutions = result?;
+        let mut keys: Vec<Vec<Option<bool>>> = solutions
+            .iter()
+            .map(|solution| solution.iter().take(key_length).cloned().collect())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        runs.push(StrategyRun {
+            strategy: strategy.clone(),
+            seconds,
+            peak_nodes,
+            solutions: solutions.len(),
+            keys,
+        });
+    }
+    Ok(runs)
+}
+
+/// Whether one of the solutions of `result` gives a key encrypting `plaintext` to `ciphertext`.
+///
+/// A key is recovered if it encrypts correctly, the undetermined bits (as the unused bits of the
+/// des key) being set to 0.
+fn recovers_key(
+    cipher: &dyn Cipher,
+    plaintext: &[Bit],
+    ciphertext: &[Bit],
+    result: &Result<Vec<Vec<Option<bool>>>, CrushError>,
+) -> bool {
+    match result {
+        Ok(solutions) => solutions.iter().any(|solution| {
+            let key: Vec<Bit> = solution
+                .iter()
+                .take(cipher.key_length())
+                .map(|var| bit!(var.unwrap_or(false)))
+                .collect();
+            cipher.encrypt(plaintext.to_vec(), key) == ciphertext
+        }),
+        Err(_) => false,
+    }
+}
+
+/// The solutions of a solving, its wall time and the maximum number of nodes reached.
+type Solved = (Result<Vec<Vec<Option<bool>>>, CrushError>, f64, usize);
+
+/// Solve `system` with `strategy` and return the solutions along with the wall time of the
+/// solving and the maximum number of nodes reached, or `None` if the strategy is not supported.
+fn solve_with_strategy(
+    strategy: &str,
+    system: &mut System,
+    forbid_dropping: &[usize],
+) -> Option<Solved> {
+    let start = Instant::now();
+    let (result, peak_nodes) = strategy::execute_strategy_by_name_with_peak(
+        strategy,
+        system,
+        Some(forbid_dropping),
+        LogLevel::Quiet,
+    )?;
+    Some((result, start.elapsed().as_secs_f64(), peak_nodes))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bench::{
+        bench_cipher, compare_strategies, run_success_experiment, search_preimage_length,
+        sweep_rounds, CSV_HEADER,
+    };
+    use crate::bit;
+    use crate::rand::{rngs::StdRng, SeedableRng};
+    use crate::strategy;
+    use crate::targets::{
+        build_system_cipher, fix_system_values_cipher, miniaes2x2::MiniAES2x2, Cipher,
+        SpongeConfig,
+    };
+
+    #[test]
+    fn bench_one_cipher() {
+        let rows = bench_cipher("miniaes2x2", 1, 42).unwrap();
+        let strategies = strategy::supported_strategies();
+        assert_eq!(rows.len(), strategies.len());
+        for (row, strategy) in rows.iter().zip(strategies.iter()) {
+            assert_eq!(row.strategy, *strategy);
+            assert!(row.recovered);
+            assert!(row.peak_nodes > 0);
+            assert_eq!(
+                row.to_csv().split(',').count(),
+                CSV_HEADER.split(',').count()
+            );
+        }
+        assert!(bench_cipher("aes", 1, 42).is_none());
+    }
+
+    #[test]
+    fn strategies_recover_the_same_keys() {
+        let cipher = MiniAES2x2::new(1);
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        let (layout, mut system) = build_system_cipher(&cipher);
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        let strategies: Vec<String> = strategy::supported_strategies()
+            .iter()
+            .map(|strategy| strategy.to_string())
+            .collect();
+        let forbid_dropping: Vec<usize> = (0..cipher.key_length()).collect();
+        let runs =
+            compare_strategies(&system, &strategies, &forbid_dropping, cipher.key_length())
+                .unwrap();
+        assert_eq!(runs.len(), strategies.len());
+        for (run, strategy) in runs.iter().zip(strategies.iter()) {
+            assert_eq!(&run.strategy, strategy);
+            assert_eq!(run.keys, runs[0].keys);
+        }
+        assert!(runs[0]
+            .keys
+            .contains(&key.iter().map(|bit| Some(bit.constant())).collect()));
+        let unknown = vec!["aes".to_string()];
+        assert!(compare_strategies(&system, &unknown, &forbid_dropping, 32).is_err());
+    }
+
+    #[test]
+    fn sweep_rounds_of_one_cipher() {
+        let steps = sweep_rounds("miniaes2x2", 2, 24, Some(1_000_000), Some(60.0), 42).unwrap();
+        assert_eq!(
+            steps.iter().map(|step| step.rounds).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert!(steps.iter().all(|step| step.solved));
+        assert!(steps[1].peak_nodes > 0);
+        // the first round count already exceeds the node budget
+        let steps = sweep_rounds("miniaes2x2", 2, 0, Some(10), None, 42).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert!(!steps[0].solved);
+        assert!(sweep_rounds("aes", 2, 0, None, None, 42).is_none());
+    }
+
+    #[test]
+    fn success_experiment_of_one_round() {
+        let report = run_success_experiment("miniaes2x2", 1, 5, "no_drop", 42).unwrap();
+        assert_eq!(report.instances, 5);
+        assert_eq!(report.recovered, 5);
+        assert_eq!(report.success_rate(), 1.0);
+        for strategy in strategy::supported_strategies().iter() {
+            let report = run_success_experiment("miniaes2x2", 1, 2, strategy, 42).unwrap();
+            assert_eq!(report.recovered, 2);
+        }
+        assert!(run_success_experiment("aes", 1, 5, "no_drop", 42).is_none());
+        assert!(run_success_experiment("miniaes2x2", 1, 5, "fast", 42).is_none());
+    }
+
+    #[test]
+    fn preimage_of_two_blocks_is_found() {
+        let config = SpongeConfig {
+            name: "keccak".to_string(),
+            rounds: 1,
+            message_length: 30,
+            output_length: 15,
+            rate: 15,
+            capacity: 10,
+        };
+        let hash = config.build().unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut message = bit::random_bits_with_rng(29, &mut rng);
+        // the padding
+        message.push(bit!(true));
+        // this image has no preimage of a single block
+        let hash_value = hash.hash(message);
+        let steps = search_preimage_length(&config, &hash_value, 3, None).unwrap();
+        assert_eq!(
+            steps.iter().map(|step| step.message_length).collect::<Vec<_>>(),
+            vec![15, 30]
+        );
+        assert!(steps[0].preimage.is_none());
+        let preimage = steps[1].preimage.clone().unwrap();
+        assert_eq!(preimage.len(), 30);
+        assert_eq!(hash.hash(preimage), hash_value);
+
+        let crunchy = SpongeConfig {
+            name: "keccak-crunchy-1".to_string(),
+            ..config
+        };
+        assert!(search_preimage_length(&crunchy, &hash_value, 3, None).is_none());
+    }
+}
@@ -18,9 +18,13 @@
 
 
 use crate::rand::distributions::{Distribution, Uniform};
-use std::collections::{btree_set::Iter,BTreeSet};
+use crate::rand::Rng;
+use std::collections::{btree_set::Iter, BTreeSet, HashMap};
+use std::convert::TryInto;
 use std::fmt;
+use std::io::{Error, ErrorKind};
 use std::ops::{BitXor, BitXorAssign};
+use std::str::FromStr;
 
 /// A wrapper around usize, a single variable in a system.
 #[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Copy)]
@@ -97,56 +101,283 @@ impl Bit {
     pub fn vars(&self) -> Iter<Variable> {
         self.vars.iter()
     }
+
+    /// Return the number of variables in the Bit.
+    #[inline]
+    pub fn weight(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Return the number of terms of the Bit as a XOR, its weight plus one if its constant
+    /// part is true.
+    #[inline]
+    pub fn degree_with_constant(&self) -> usize {
+        self.weight() + self.constant as usize
+    }
+
+    /// Return the value of the Bit if it contains no variable.
+    pub fn is_constant(&self) -> Option<bool> {
+        if self.vars.is_empty() {
+            Some(self.constant)
+        } else {
+            None
+        }
+    }
+
+    /// Return the id of the variable if the Bit is exactly one variable (with
+    /// a constant part of false).
+    pub fn as_single_variable(&self) -> Option<usize> {
+        match (self.weight(), self.constant) {
+            (1, false) => self.vars.iter().next().map(|var| var.id()),
+            _ => None,
+        }
+    }
+
+    /// Return the value of the Bit when its variables take the values of `assignment` (indexed
+    /// by variable id), or `None` if one of them is not assigned.
+    pub fn evaluate(&self, assignment: &[Option<bool>]) -> Option<bool> {
+        self.vars.iter().try_fold(self.constant, |value, var| {
+            assignment
+                .get(var.id())
+                .and_then(|var_value| *var_value)
+                .map(|var_value| value ^ var_value)
+        })
+    }
+
+    /// Serialize the Bit: one byte for the constant, the number of variables then the id of
+    /// each variable, both as little endian u64.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(9 + 8 * self.vars.len());
+        bytes.push(self.constant as u8);
+        bytes.extend_from_slice(&(self.vars.len() as u64).to_le_bytes());
+        for var in self.vars.iter() {
+            bytes.extend_from_slice(&(var.id() as u64).to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize a Bit serialized by `to_bytes` from the start of `bytes`.
+    /// Return the Bit along with the number of bytes read.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Bit, usize), Error> {
+        let constant = match bytes.first() {
+            Some(0) => false,
+            Some(1) => true,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "invalid constant of a bit")),
+        };
+        let nvars = read_u64(bytes, 1)? as usize;
+        let vars = (0..nvars)
+            .map(|i| read_u64(bytes, 9 + 8 * i).map(|id| Variable::new(id as usize)))
+            .collect::<Result<BTreeSet<Variable>, Error>>()?;
+        Ok((Bit { vars, constant }, 9 + 8 * nvars))
+    }
+}
+
+/// Render the Bit as the linear combination it holds, ex: `x1 + x4 + 1`, or `0`/`1` for a
+/// constant bit.
+impl fmt::Display for Bit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.vars.is_empty() {
+            return write!(f, "{}", self.constant as u8);
+        }
+        let mut terms: Vec<String> = self.vars.iter().map(|var| format!("x{}", var)).collect();
+        if self.constant {
+            terms.push("1".to_string());
+        }
+        write!(f, "{}", terms.join(" + "))
+    }
+}
+
+/// Read the little endian u64 located at `offset` in `bytes`.
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, Error> {
+    match bytes.get(offset..offset + 8) {
+        Some(slice) => Ok(u64::from_le_bytes(slice.try_into().unwrap())),
+        None => Err(Error::new(ErrorKind::UnexpectedEof, "truncated bit")),
+    }
+}
+
+/// Serialize a slice of Bit: the number of bits as a little endian u64 followed by each
+/// Bit serialized with `Bit::to_bytes`.
+pub fn bits_to_bytes(bits: &[Bit]) -> Vec<u8> {
+    let mut bytes = (bits.len() as u64).to_le_bytes().to_vec();
+    for bit in bits.iter() {
+        bytes.append(&mut bit.to_bytes());
+    }
+    bytes
+}
+
+/// Deserialize a Vec<Bit> serialized by `bits_to_bytes`.
+pub fn bits_from_bytes(bytes: &[u8]) -> Result<Vec<Bit>, Error> {
+    let len = read_u64(bytes, 0)? as usize;
+    let mut offset = 8;
+    let mut bits = Vec::with_capacity(len);
+    for _ in 0..len {
+        let (bit, read) = Bit::from_bytes(&bytes[offset..])?;
+        offset += read;
+        bits.push(bit);
+    }
+    if offset != bytes.len() {
+        return Err(Error::new(ErrorKind::InvalidData, "trailing bytes after the bits"));
+    }
+    Ok(bits)
+}
+
+/// Why a string could not be converted to bits, see `bits_from_binary_string` and
+/// `bits_from_hex_string`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The character at this position (counted in chars, once the hex prefixes and spaces
+    /// are removed for an hex string) is not a digit of the expected base.
+    InvalidChar { character: char, position: usize },
+    /// An hex string has an odd number of digits, which don't make whole bytes.
+    OddLength(usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidChar {
+                character,
+                position,
+            } => write!(f, "invalid digit {:?} at position {}", character, position),
+            ParseError::OddLength(len) => write!(
+                f,
+                "the hex string has an odd number of digits ({}), expected whole bytes",
+                len
+            ),
+        }
+    }
 }
 
+impl std::error::Error for ParseError {}
+
 /// Convert a binary string (ie a string composed of '0' and '1') to the corresponding Vec<Bit>
 /// with all Bit in the Vec constants.
-pub fn bits_from_binary_string(b_str: &str) -> Vec<Bit> {
-    let out_bits: Vec<Bit> = b_str
+///
+/// Return a `ParseError` locating the first character which is not '0' or '1'.
+pub fn bits_from_binary_string(b_str: &str) -> Result<Vec<Bit>, ParseError> {
+    b_str
         .chars()
-        .map(|char| match char {
-            '0' => bit!(false),
-            '1' => bit!(true),
-            _ => panic!(format!("{} this is not a binary string", b_str)),
+        .enumerate()
+        .map(|(position, character)| match character {
+            '0' => Ok(bit!(false)),
+            '1' => Ok(bit!(true)),
+            _ => Err(ParseError::InvalidChar {
+                character,
+                position,
+            }),
         })
-        .collect();
-    out_bits
+        .collect()
+}
+
+/// Same as `bits_from_binary_string` for a string known to be valid (ex: built by a `format!`).
+///
+/// Panics if `b_str` is not a binary string.
+pub fn bits_from_binary_string_unchecked(b_str: &str) -> Vec<Bit> {
+    bits_from_binary_string(b_str).unwrap_or_else(|e| panic!("{:?} {}", b_str, e))
 }
 
 /// Convert an hex string (ie a string composed of hexadecimal characters) to the corresponding Vec<Bit>
 /// with all Bit in the Vec constants.
-pub fn bits_from_hex_string(h_str: &str) -> Vec<Bit> {
-    let h_str = h_str
+///
+/// Return a `ParseError` locating the first character which is not an hex digit, or if the
+/// digits don't make whole bytes.
+pub fn bits_from_hex_string(h_str: &str) -> Result<Vec<Bit>, ParseError> {
+    let digits = strip_hex_prefixes(h_str)
+        .chars()
+        .enumerate()
+        .map(|(position, character)| {
+            character.to_digit(16).ok_or(ParseError::InvalidChar {
+                character,
+                position,
+            })
+        })
+        .collect::<Result<Vec<u32>, ParseError>>()?;
+    if digits.len() % 2 != 0 {
+        return Err(ParseError::OddLength(digits.len()));
+    }
+    Ok(digits
+        .iter()
+        .flat_map(|digit| (0..4).rev().map(move |i| bit!((digit >> i) & 1 == 1)))
+        .collect())
+}
+
+/// Same as `bits_from_hex_string` for a string known to be valid (ex: a test vector).
+///
+/// Panics if `h_str` is not an hex string of whole bytes.
+pub fn bits_from_hex_string_unchecked(h_str: &str) -> Vec<Bit> {
+    bits_from_hex_string(h_str).unwrap_or_else(|e| panic!("{:?} {}", h_str, e))
+}
+
+/// The order of the bits of each byte of an hexadecimal string, which depends on the convention
+/// of the specification the value comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The most significant bit of each byte comes first (ex: DES, PRESENT), as parsed by
+    /// `bits_from_hex_string`.
+    Msb0,
+    /// The least significant bit of each byte comes first (ex: the Keccak reference), as parsed
+    /// by `keccak::bits_from_hex_string_keccak`.
+    Lsb0,
+}
+
+impl FromStr for BitOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BitOrder, String> {
+        match s {
+            "msb0" => Ok(BitOrder::Msb0),
+            "lsb0" => Ok(BitOrder::Lsb0),
+            _ => Err(format!("unknown bit order {}, expected msb0 or lsb0", s)),
+        }
+    }
+}
+
+impl fmt::Display for BitOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BitOrder::Msb0 => "msb0",
+            BitOrder::Lsb0 => "lsb0",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Same as `bits_from_hex_string`, the bits of each byte being in the order `order`.
+pub fn bits_from_hex_string_with_order(
+    h_str: &str,
+    order: BitOrder,
+) -> Result<Vec<Bit>, ParseError> {
+    let bits = bits_from_hex_string(h_str)?;
+    Ok(match order {
+        BitOrder::Msb0 => bits,
+        BitOrder::Lsb0 => bits
+            .chunks(8)
+            .flat_map(|byte| byte.iter().rev().cloned())
+            .collect(),
+    })
+}
+
+/// Same as `bits_from_hex_string`, but works with nibbles: every hex character gives 4 bits, so
+/// that strings of odd length (ex: a 12 bits value) can be parsed without padding.
+pub fn bits_from_hex_nibbles(h_str: &str) -> Vec<Bit> {
+    strip_hex_prefixes(h_str)
+        .chars()
+        .flat_map(|c| {
+            let nibble = c.to_digit(16).expect("illegal char in hex string");
+            (0..4).rev().map(move |i| bit!((nibble >> i) & 1 == 1))
+        })
+        .collect()
+}
+
+fn strip_hex_prefixes(h_str: &str) -> String {
+    h_str
         .replace("0x", "")
         .replace("0X", "")
         .replace("\\x", "")
         .replace("\\X", "")
         .replace("x", "")
         .replace("X", "")
-        .replace(" ", "");
-    assert!(h_str.len() % 2 == 0);
-    let mut b_str = String::new();
-    for i in 0..h_str.len() / 2 {
-        b_str.push_str(
-            format!(
-                "{:08b}",
-                u8::from_str_radix(
-                    h_str
-                        .chars()
-                        .skip(i * 2)
-                        .take(2)
-                        .collect::<String>()
-                        .as_str(),
-                    16
-                )
-                .unwrap()
-            )
-            .chars()
-            .collect::<String>()
-            .as_str(),
-        )
-    }
-    bits_from_binary_string(&b_str)
+        .replace(" ", "")
 }
 
 /// Convert a Vec<Bit> to an hexadecimal string by taking the value
@@ -172,21 +403,134 @@ pub fn bits_to_hex_string(bits: Vec<Bit>) -> String {
     hex
 }
 
-/// Convert a Vec<Bit> to a binary string by taking the value
-/// of the constants. If some variable are in the bit they will be ignored.
+/// Same as `bits_to_hex_string`, but works with nibbles: the number of bits only has to be a
+/// multiple of 4. This is the inverse of `bits_from_hex_nibbles`.
+pub fn bits_to_hex_nibbles(bits: &[Bit]) -> String {
+    assert_eq!(bits.len() % 4, 0, "the number of bits should be a multiple of 4");
+    bits.chunks(4)
+        .map(|nibble| {
+            let value = nibble
+                .iter()
+                .fold(0, |acc, bit| (acc << 1) | bit.constant() as u32);
+            std::char::from_digit(value, 16).unwrap()
+        })
+        .collect()
+}
+
+/// Convert a Vec<Bit> to a binary string by taking the value of the constants. A bit which is
+/// not a constant (an unknown value, see `bits_from_values`) is shown as '?'.
 pub fn bits_to_binary_string(bits: Vec<Bit>) -> String {
     bits.iter()
-        .map(|bit| if bit.constant() { '1' } else { '0' })
+        .map(|bit| match bit.is_constant() {
+            Some(true) => '1',
+            Some(false) => '0',
+            None => '?',
+        })
         .collect::<String>()
 }
 
+/// Convert a Vec<Bit> of any length to an hexadecimal string, each digit being made of 4 bits
+/// (the first one being the most significant) and the last digit being padded with 0s. A digit
+/// with a bit which is not a constant is shown as '?'.
+pub fn bits_to_padded_hex_string(bits: Vec<Bit>) -> String {
+    bits.chunks(4)
+        .map(|digit| {
+            digit
+                .iter()
+                .map(Bit::is_constant)
+                .chain(std::iter::repeat(Some(false)))
+                .take(4)
+                .try_fold(0, |value, bit| bit.map(|b| value * 2 + b as u32))
+                .map_or('?', |value| std::char::from_digit(value, 16).unwrap())
+        })
+        .collect()
+}
+
+/// Convert the values of a solution, `None` being an undetermined value, to a Vec<Bit>: each
+/// known value becomes a constant and each undetermined one the variable at its index.
+pub fn bits_from_values(values: &[Option<bool>]) -> Vec<Bit> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| match value {
+            Some(value) => bit!(*value),
+            None => Bit::from_variable_id(i),
+        })
+        .collect()
+}
+
+/// How the values of bits (keys, preimages) are printed, see `format_bits`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Hex,
+    Bin,
+    /// The hexadecimal string followed by the binary one.
+    Both,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OutputFormat, String> {
+        match s {
+            "hex" => Ok(OutputFormat::Hex),
+            "bin" => Ok(OutputFormat::Bin),
+            "both" => Ok(OutputFormat::Both),
+            _ => Err(format!(
+                "unknown output format {}, expected hex, bin or both",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Hex => "hex",
+            OutputFormat::Bin => "bin",
+            OutputFormat::Both => "both",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Format the value of `bits` following `format`, using `bits_to_hex_string` for the
+/// hexadecimal string (see `format_bits_with_hex`).
+pub fn format_bits(bits: Vec<Bit>, format: OutputFormat) -> String {
+    format_bits_with_hex(bits, format, bits_to_hex_string)
+}
+
+/// Format the value of `bits` following `format`, `to_hex` giving the hexadecimal string
+/// (ex: the FIPS 202 conversion for the sponges), `both` gives `<hex> (bin <binary>)`.
+pub fn format_bits_with_hex(
+    bits: Vec<Bit>,
+    format: OutputFormat,
+    to_hex: fn(Vec<Bit>) -> String,
+) -> String {
+    match format {
+        OutputFormat::Hex => to_hex(bits),
+        OutputFormat::Bin => bits_to_binary_string(bits),
+        OutputFormat::Both => format!(
+            "{} (bin {})",
+            to_hex(bits.clone()),
+            bits_to_binary_string(bits)
+        ),
+    }
+}
+
 /// Produce a Vec<Bit> of the provided len with constants random bits.
 pub fn random_bits(len: usize) -> Vec<Bit> {
-    let mut rng = rand::thread_rng();
+    random_bits_with_rng(len, &mut rand::thread_rng())
+}
+
+/// Produce a Vec<Bit> of the provided len with constants random bits drawn from `rng`,
+/// useful to get reproducible values from a seeded generator.
+pub fn random_bits_with_rng<R: Rng>(len: usize, rng: &mut R) -> Vec<Bit> {
     let die = Uniform::from(0..2);
     let mut bits = Vec::with_capacity(len);
     for _ in 0..len {
-        let throw = die.sample(&mut rng);
+        let throw = die.sample(rng);
         match throw {
             0 => bits.push(bit!(false)),
             1 => bits.push(bit!(true)),
@@ -206,6 +550,40 @@ pub fn bit_vector_xoring(mut a: Vec<Bit>, mut b: Vec<Bit>) -> Vec<Bit> {
         .collect::<Vec<Bit>>()
 }
 
+/// Return a new Vec<Bit> with the bits reordered following `perm`: `out[i] = bits[perm[i]]`.
+/// `perm` must be a permutation of the indexes of `bits`.
+pub fn permute(bits: &[Bit], perm: &[usize]) -> Vec<Bit> {
+    assert_eq!(bits.len(), perm.len(), "the permutation should have the length of the bits");
+    perm.iter().map(|i| bits[*i].clone()).collect()
+}
+
+/// Return a new Vec<Bit> where the variables of `assignment` are replaced by their value: they
+/// are removed from each Bit and their value XORed into its constant. The variables missing from
+/// `assignment` are kept, so the Bits are only constant if all their variables are assigned.
+pub fn substitute(bits: &[Bit], assignment: &HashMap<usize, bool>) -> Vec<Bit> {
+    let mut evaluated = bits.to_vec();
+    fold_constants(&mut evaluated, assignment);
+    evaluated
+}
+
+/// Replace in place the variables of `known` by their value (see `substitute`).
+///
+/// Folding the variables known to be constant (ex: a zeroed padding) out of the bits before they
+/// go through an S-Box shrinks the relations of the system, and the S-Boxes whose input becomes
+/// constant don't add any relation.
+pub fn fold_constants(bits: &mut [Bit], known: &HashMap<usize, bool>) {
+    for bit in bits.iter_mut() {
+        let constant = &mut bit.constant;
+        bit.vars.retain(|var| match known.get(&var.id()) {
+            Some(value) => {
+                *constant ^= value;
+                false
+            }
+            None => true,
+        });
+    }
+}
+
 impl BitXor for Bit {
     type Output = Self;
 
@@ -254,3 +632,219 @@ fn test_xor() {
     bit_3.constant = true;
     assert_eq!(bit_3, bit_1 ^ bit_2);
 }
+
+#[test]
+fn test_constant_and_single_variable() {
+    assert_eq!(Some(true), bit!(true).is_constant());
+    assert_eq!(Some(false), bit!(false).is_constant());
+    assert_eq!(None, bit!(true).as_single_variable());
+
+    let single = Bit::from_variable_id(7);
+    assert_eq!(None, single.is_constant());
+    assert_eq!(Some(7), single.as_single_variable());
+    // the negation of a variable is not the variable itself
+    assert_eq!(None, (single.clone() ^ bit!(true)).as_single_variable());
+
+    let multi = single ^ Bit::from_variable_id(3);
+    assert_eq!(None, multi.is_constant());
+    assert_eq!(None, multi.as_single_variable());
+    // variables cancelling each other leave a constant
+    assert_eq!(Some(false), (multi.clone() ^ multi).is_constant());
+}
+
+#[test]
+fn test_weight() {
+    assert_eq!(bit!(false).weight(), 0);
+    assert_eq!(bit!(true).weight(), 0);
+    assert_eq!(bit!(true).degree_with_constant(), 1);
+    let single = Bit::from_variable_id(3);
+    assert_eq!(single.weight(), 1);
+    assert_eq!(single.degree_with_constant(), 1);
+    let bit = single ^ Bit::from_variable_id(5) ^ Bit::from_variable_id(8) ^ bit!(true);
+    assert_eq!(bit.weight(), 3);
+    assert_eq!(bit.degree_with_constant(), 4);
+    // a variable XORed twice cancels out
+    assert_eq!((bit ^ Bit::from_variable_id(5)).weight(), 2);
+}
+
+#[test]
+fn test_evaluate() {
+    let bit = Bit::from_variable_id(0) ^ Bit::from_variable_id(2) ^ bit!(true);
+    assert_eq!(Some(true), bit.evaluate(&[Some(true), None, Some(true)]));
+    assert_eq!(Some(false), bit.evaluate(&[Some(false), Some(true), Some(true)]));
+    assert_eq!(None, bit.evaluate(&[Some(true), Some(true), None]));
+    assert_eq!(None, bit.evaluate(&[Some(true)]));
+    assert_eq!(Some(true), bit!(true).evaluate(&[]));
+}
+
+#[test]
+fn test_substitute() {
+    let bits = vec![
+        Bit::from_variable_id(0) ^ Bit::from_variable_id(1),
+        Bit::from_variable_id(1) ^ Bit::from_variable_id(2) ^ bit!(true),
+        bit!(true),
+    ];
+    let assignment: HashMap<usize, bool> = vec![(0, true), (1, true)].into_iter().collect();
+    let substituted = substitute(&bits, &assignment);
+    assert_eq!(substituted[0], bit!(false));
+    // the unassigned variable is kept, with the values of the others in the constant
+    assert_eq!(substituted[1], Bit::from_variable_id(2));
+    assert_eq!(substituted[2], bit!(true));
+    assert_eq!(substitute(&bits, &HashMap::new()), bits);
+}
+
+#[test]
+fn test_fold_constants() {
+    use crate::crush::soc::utils::{build_system_from_spec, SystemSpec};
+    use crate::sbox::Sbox;
+
+    // the second block of 8 variables is a zeroed padding
+    let linear_layer = || {
+        (0..16)
+            .map(|i| {
+                if i < 8 {
+                    Bit::from_variable_id(i) ^ Bit::from_variable_id(8 + i)
+                } else {
+                    Bit::from_variable_id(i) ^ Bit::from_variable_id(8 + (i + 1) % 8)
+                }
+            })
+            .collect::<Vec<Bit>>()
+    };
+    let known: HashMap<usize, bool> = (8..16).map(|var| (var, false)).collect();
+    let mut folded = linear_layer();
+    fold_constants(&mut folded, &known);
+    for i in 0..8 {
+        assert_eq!(folded[i], Bit::from_variable_id(i));
+        assert_eq!(folded[8 + i], bit!(false));
+    }
+    assert_eq!(folded, substitute(&linear_layer(), &known));
+
+    let table = vec![
+        0x06, 0x0b, 0x05, 0x04, 0x02, 0x0e, 0x07, 0x0a, 0x09, 0x0d, 0x0f, 0x0c, 0x03, 0x01, 0x00,
+        0x08,
+    ];
+    let build = |bits: &[Bit]| {
+        let mut sbox = Sbox::new(4, 4, table.clone(), 16);
+        sbox.apply_layer(bits);
+        build_system_from_spec(SystemSpec::new(sbox.next_var_id(), sbox.bdds()))
+    };
+    let system = build(&linear_layer());
+    let folded_system = build(&folded);
+    // the S-Boxes of the padding have a constant input and add no relation
+    assert_eq!(system.iter_bdds().len(), 4);
+    assert_eq!(folded_system.iter_bdds().len(), 2);
+    assert!(folded_system.get_size() < system.get_size());
+}
+
+#[test]
+fn test_parse_binary_string() {
+    let bits = bits_from_binary_string("1101").unwrap();
+    assert_eq!(bits_to_binary_string(bits), "1101");
+    assert_eq!(bits_from_binary_string(""), Ok(Vec::new()));
+    assert_eq!(
+        bits_from_binary_string("1021"),
+        Err(ParseError::InvalidChar {
+            character: '2',
+            position: 2
+        })
+    );
+}
+
+#[test]
+fn test_parse_hex_string() {
+    let bits = bits_from_hex_string("0xa50f").unwrap();
+    assert_eq!(bits_to_binary_string(bits), "1010010100001111");
+    assert_eq!(bits_from_hex_string(""), Ok(Vec::new()));
+    let err = bits_from_hex_string("a5g0").unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::InvalidChar {
+            character: 'g',
+            position: 2
+        }
+    );
+    assert_eq!(err.to_string(), "invalid digit 'g' at position 2");
+    assert_eq!(bits_from_hex_string("a50"), Err(ParseError::OddLength(3)));
+}
+
+#[test]
+#[should_panic]
+fn test_parse_unchecked_panics() {
+    bits_from_hex_string_unchecked("zz");
+}
+
+#[test]
+fn test_parse_hex_string_with_order() {
+    let msb0 = bits_from_hex_string_with_order("a50f", BitOrder::Msb0).unwrap();
+    assert_eq!(msb0, bits_from_hex_string("a50f").unwrap());
+    let lsb0 = bits_from_hex_string_with_order("a50f", BitOrder::Lsb0).unwrap();
+    assert_eq!(bits_to_binary_string(lsb0), "1010010111110000");
+    assert_eq!("lsb0".parse(), Ok(BitOrder::Lsb0));
+    assert_eq!(BitOrder::Msb0.to_string(), "msb0");
+    assert!("lsb".parse::<BitOrder>().is_err());
+}
+
+#[test]
+fn test_hex_nibbles() {
+    let bits = bits_from_hex_nibbles("abc");
+    assert_eq!(bits_to_binary_string(bits.clone()), "101010111100");
+    assert_eq!(bits_to_hex_nibbles(&bits), "abc");
+    assert_eq!(bits_from_hex_nibbles("0x0f"), bits_from_hex_string_unchecked("0x0f"));
+    assert_eq!(bits_to_hex_nibbles(&bits_from_hex_nibbles("00c")), "00c");
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(bit!(false).to_string(), "0");
+    assert_eq!(bit!(true).to_string(), "1");
+    assert_eq!(Bit::from_variable_id(5).to_string(), "x5");
+    let bit = Bit::from_variable_id(4) ^ Bit::from_variable_id(1) ^ bit!(true);
+    assert_eq!(bit.to_string(), "x1 + x4 + 1");
+}
+
+#[test]
+fn test_permute() {
+    let bits = bits_from_binary_string_unchecked("1100");
+    assert_eq!(permute(&bits, &[0, 1, 2, 3]), bits);
+    assert_eq!(permute(&bits, &[2, 0, 3, 1]), bits_from_binary_string_unchecked("0101"));
+    let vars: Vec<Bit> = (0..3).map(Bit::from_variable_id).collect();
+    let permuted = permute(&vars, &[1, 2, 0]);
+    assert_eq!(permuted[0].as_single_variable(), Some(1));
+    assert_eq!(permuted[2].as_single_variable(), Some(0));
+}
+
+#[test]
+#[should_panic]
+fn test_permute_length_mismatch() {
+    permute(&bits_from_binary_string_unchecked("1100"), &[0, 1, 2]);
+}
+
+#[test]
+fn test_format_bits() {
+    let bits = bits_from_binary_string_unchecked("1010010100001111");
+    assert_eq!(format_bits(bits.clone(), OutputFormat::Hex), "a50f");
+    assert_eq!(format_bits(bits.clone(), OutputFormat::Bin), "1010010100001111");
+    assert_eq!(
+        format_bits(bits, OutputFormat::Both),
+        "a50f (bin 1010010100001111)"
+    );
+    assert_eq!("both".parse::<OutputFormat>(), Ok(OutputFormat::Both));
+    assert!("binary".parse::<OutputFormat>().is_err());
+    // a partial solution, the last digit is padded
+    let bits = bits_from_values(&[Some(true), Some(true), None, Some(false), Some(true)]);
+    assert_eq!(
+        format_bits_with_hex(bits, OutputFormat::Both, bits_to_padded_hex_string),
+        "?8 (bin 11?01)"
+    );
+}
+
+#[test]
+fn test_bytes_round_trip() {
+    let bit = Bit::from_variable_id(3) ^ Bit::from_variable_id(17) ^ Bit::from_variable_id(1 << 40) ^ bit!(true);
+    let bytes = bit.to_bytes();
+    assert_eq!((bit.clone(), bytes.len()), Bit::from_bytes(&bytes).unwrap());
+    assert!(Bit::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+    let bits = vec![bit, bit!(false), Bit::from_variable_id(0)];
+    assert_eq!(bits, bits_from_bytes(&bits_to_bytes(&bits)).unwrap());
+}
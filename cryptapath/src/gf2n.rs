@@ -0,0 +1,115 @@
+//! Multiplications of `Bit` vectors in the binary field GF(2^n) used by the MixColumns
+//! layers of the AES-like ciphers.
+//!
+//! A vector of `n` bits stands for an element of GF(2^n), most significant bit first, so
+//! its first bit is the coefficient of x^(n-1) and its last one the constant coefficient.
+//! The field is given by its modulus, the irreducible polynomial of degree `n` written as
+//! an integer with the coefficient of x^i in bit i (0x13 for x^4 + x + 1, 0x11b for the
+//! AES field x^8 + x^4 + x^3 + x + 1).
+//!
+//! Multiplying by a constant is linear, the `Bit`s of the result are XORs of the input
+//! `Bit`s and can be computed on variables as well as on constants.
+
+use crate::bit::Bit;
+
+/// Returns `bits` multiplied by x in the field defined by `modulus`.
+///
+/// Panics if `modulus` is not of degree `bits.len()`.
+pub fn xtime(bits: &[Bit], modulus: u16) -> Vec<Bit> {
+    let n = bits.len();
+    assert!(
+        n > 0 && n < 16 && modulus >> n == 1,
+        "modulus {:#x} is not of degree {}",
+        modulus,
+        n
+    );
+    let mut out_bits = bits[1..].to_vec();
+    out_bits.push(Bit::new());
+    for (i, out_bit) in out_bits.iter_mut().enumerate() {
+        if (modulus >> (n - 1 - i)) & 1 == 1 {
+            *out_bit ^= bits[0].clone();
+        }
+    }
+    out_bits
+}
+
+/// Returns `bits` multiplied by the constant `c` in the field defined by `modulus`, `c`
+/// being written as the modulus is.
+///
+/// Panics if `modulus` is not of degree `bits.len()` or if `c` is not an element of the
+/// field.
+pub fn mul_const(bits: &[Bit], c: u16, modulus: u16) -> Vec<Bit> {
+    assert!(
+        c >> bits.len() == 0,
+        "{:#x} is not an element of GF(2^{})",
+        c,
+        bits.len()
+    );
+    let mut out_bits = vec![Bit::new(); bits.len()];
+    let mut power = bits.to_vec();
+    for i in 0..bits.len() {
+        if (c >> i) & 1 == 1 {
+            for (out_bit, bit) in out_bits.iter_mut().zip(power.iter()) {
+                *out_bit ^= bit.clone();
+            }
+        }
+        power = xtime(&power, modulus);
+    }
+    out_bits
+}
+
+#[cfg(test)]
+fn element(value: u16, n: usize) -> Vec<Bit> {
+    (0..n)
+        .rev()
+        .map(|i| Bit::from_value((value >> i) & 1 == 1))
+        .collect()
+}
+
+#[cfg(test)]
+fn value(bits: &[Bit]) -> u16 {
+    bits.iter().fold(0, |value, bit| {
+        (value << 1) | bit.is_constant().expect("the bit should be a constant") as u16
+    })
+}
+
+#[test]
+fn test_xtime() {
+    // x^3 * x = x^4 = x + 1 in GF(2^4)/(x^4 + x + 1)
+    assert_eq!(value(&xtime(&element(0x8, 4), 0x13)), 0x3);
+    assert_eq!(value(&xtime(&element(0x5, 4), 0x13)), 0xa);
+    // the examples of FIPS-197
+    assert_eq!(value(&xtime(&element(0x57, 8), 0x11b)), 0xae);
+    assert_eq!(value(&xtime(&element(0xae, 8), 0x11b)), 0x47);
+    assert_eq!(value(&xtime(&element(0x8e, 8), 0x11b)), 0x07);
+}
+
+#[test]
+fn test_mul_const() {
+    // {57} * {13} = {fe} in FIPS-197
+    assert_eq!(value(&mul_const(&element(0x57, 8), 0x13, 0x11b)), 0xfe);
+    for a in 0..16 {
+        assert_eq!(value(&mul_const(&element(a, 4), 0x1, 0x13)), a);
+        assert_eq!(value(&mul_const(&element(a, 4), 0x0, 0x13)), 0);
+        // (x + 1) * a = x * a + a
+        let x_a = value(&xtime(&element(a, 4), 0x13));
+        assert_eq!(value(&mul_const(&element(a, 4), 0x3, 0x13)), x_a ^ a);
+    }
+}
+
+#[test]
+fn test_mul_const_on_variables() {
+    let bits = (0..4).map(Bit::from_variable_id).collect::<Vec<Bit>>();
+    let product = mul_const(&bits, 0x3, 0x13);
+    for a in 0..16 {
+        let assignment = element(a, 4)
+            .iter()
+            .map(|bit| bit.is_constant())
+            .collect::<Vec<Option<bool>>>();
+        let evaluated = product
+            .iter()
+            .map(|bit| Bit::from_value(bit.evaluate(&assignment).unwrap()))
+            .collect::<Vec<Bit>>();
+        assert_eq!(value(&evaluated), value(&mul_const(&element(a, 4), 0x3, 0x13)));
+    }
+}
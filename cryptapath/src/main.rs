@@ -1,67 +1,164 @@
 #[macro_use]
 extern crate crush;
 extern crate rand;
+extern crate rayon;
 extern crate structopt;
 extern crate vob;
 #[macro_use]
 extern crate structopt_derive;
 
+pub mod anf;
 #[macro_use]
 pub mod bit;
+pub mod bench;
+pub mod gf2n;
 pub mod options;
 pub mod sbox;
 pub mod strategy;
 pub mod targets;
 
 use crush::soc::utils::*;
-use options::CryptaPathOptions;
+use options::{CryptaPathArgs, CryptaPathOptions};
+use std::time::Instant;
 use structopt::StructOpt;
 use targets::*;
 
 fn main() {
-    match CryptaPathOptions::from_args() {
+    let args = CryptaPathArgs::from_args();
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("the thread pool should only be built once");
+    }
+    match args.command {
         CryptaPathOptions::Cipher {
             cipher_name,
             rounds,
             chosen_plaintext_ciphertext,
+            input_hex_order,
             key,
+            key_relations,
             out,
+            cache_dir,
             strategy,
+            max_bdd_nodes,
+            log_level,
+            max_solutions,
+            compare_strategies,
+            output_format,
+            full_solution,
+            absorb_only,
+            profile,
+            profile_json,
+            explain,
         } => {
-            let cipher = match build_cipher_by_name(cipher_name.as_ref(), rounds) {
+            let rounds = match resolve_rounds(cipher_name.as_ref(), rounds) {
+                Ok(rounds) => rounds,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            let cipher = match build_cipher_by_name(cipher_name.as_ref(), Some(rounds)) {
                 Some(c) => c,
                 None => {
-                    println!("Cipher not supported. Check --help for supported ciphers.");
+                    println!(
+                        "Cipher not supported. Supported ciphers: {}.",
+                        supported_ciphers().join(", ")
+                    );
+                    return;
+                }
+            };
+            let key_relations = match key_relations {
+                Some(path) => match load_key_relations(&path, cipher.key_length()) {
+                    Ok(relations) => relations,
+                    Err(e) => {
+                        println!("{}", e);
+                        return;
+                    }
+                },
+                None => Vec::new(),
+            };
+            // the random key has to satisfy the relations
+            let key = match key {
+                None if !key_relations.is_empty() && chosen_plaintext_ciphertext.is_none() => {
+                    Some("x".repeat(cipher.key_length()))
+                }
+                key => key,
+            };
+            if let Some(partial_key) = &key {
+                if partial_key.chars().count() != cipher.key_length() {
+                    println!(
+                        "The provided partial key has a size different from the key of {} bits",
+                        cipher.key_length()
+                    );
                     return;
                 }
+            }
+            let mut phases = Vec::new();
+            let start = Instant::now();
+            let (layout, mut system) = match cache_dir {
+                Some(cache_dir) => {
+                    match build_system_cipher_cached(cipher.as_ref(), &cipher_name, &cache_dir) {
+                        Ok(built) => built,
+                        Err(e) => {
+                            println!("Can't cache the system in {:?}: {}", cache_dir, e);
+                            return;
+                        }
+                    }
+                }
+                None => build_system_cipher(cipher.as_ref()),
             };
-            let (input, output, mut system) = build_system_cipher(cipher.as_ref());
+            system.set_max_bdd_nodes(max_bdd_nodes);
+            phases.push(("build", start.elapsed().as_secs_f64()));
+            let start = Instant::now();
             let (plaintext, ciphertext);
             if let Some(plaintext_ciphertext) = chosen_plaintext_ciphertext {
-                assert_eq!(
-                    plaintext_ciphertext.len(),
-                    2,
-                    "You can only provide one plaintext and one ciphertext"
-                );
-                plaintext = bit::bits_from_hex_string(&plaintext_ciphertext[0]);
-                ciphertext = bit::bits_from_hex_string(&plaintext_ciphertext[1]);
+                if plaintext_ciphertext.len() != 2 {
+                    println!("You can only provide one plaintext and one ciphertext");
+                    return;
+                }
+                let order = input_hex_order.unwrap_or_else(|| cipher.hex_bit_order());
+                plaintext = match bit::bits_from_hex_string_with_order(&plaintext_ciphertext[0], order)
+                {
+                    Ok(bits) => bits,
+                    Err(e) => {
+                        println!("Invalid plaintext: {}", e);
+                        return;
+                    }
+                };
+                ciphertext = match bit::bits_from_hex_string_with_order(&plaintext_ciphertext[1], order)
+                {
+                    Ok(bits) => bits,
+                    Err(e) => {
+                        println!("Invalid ciphertext: {}", e);
+                        return;
+                    }
+                };
                 if let Some(partial_key) = key {
                     let filled_key = fill_partial_value(partial_key.as_ref());
-                    assert_eq!(cipher.key_length(), filled_key.0.len(),
-                    "the provided partial key has a size different from the key expected by the chosen cipher");
                     fix_system_values_cipher_with_partial_key(
                         &mut system,
+                        &layout,
                         &plaintext,
                         &ciphertext,
                         filled_key,
-                        &input,
-                        &output,
                     );
                 } else {
-                    fix_system_values_cipher(&mut system, &plaintext, &ciphertext, &input, &output);
+                    fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
                 }
             } else if let Some(partial_key) = key {
-                let filled_key = fill_partial_value(partial_key.as_ref());
+                let mut filled_key = fill_partial_value(partial_key.as_ref());
+                if !key_relations.is_empty() {
+                    filled_key.0 = match random_key_with_relations(&filled_key, &key_relations) {
+                        Some(key) => key,
+                        None => {
+                            println!("No key satisfies the key relations and the known key bits");
+                            return;
+                        }
+                    };
+                }
                 let tmp = get_random_plaintext_ciphertext_with_partial_key(
                     cipher.as_ref(),
                     filled_key.0.clone(),
@@ -70,40 +167,123 @@ fn main() {
                 ciphertext = tmp.1;
                 fix_system_values_cipher_with_partial_key(
                     &mut system,
+                    &layout,
                     &plaintext,
                     &ciphertext,
                     filled_key,
-                    &input,
-                    &output,
                 );
             } else {
                 let tmp = get_random_plaintext_ciphertext_key(cipher.as_ref());
                 plaintext = tmp.0;
                 ciphertext = tmp.1;
-                fix_system_values_cipher(&mut system, &plaintext, &ciphertext, &input, &output);
+                fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
             }
+            if let Err(e) = fix_key_relations(&mut system, &layout, &key_relations) {
+                println!("Key relations rejected: {}", e);
+                return;
+            }
+            phases.push(("fix", start.elapsed().as_secs_f64()));
             if let Some(path) = out {
                 print_system_to_file(&system, &path);
             }
-            let forbid_dropping: Vec<usize> = (0..cipher.key_length()).collect();
-            let mut sols = match strategy {
-                Some(name) => match strategy::execute_strategy_by_name(
-                        name.as_ref(),
-                        &mut system,
-                        Some(&forbid_dropping),
-                    ) {
-                        Some(sols) => sols,
-                        None => {
-                            println!("Strategy not supported. Check --help for supported strategies.");
-                            return;
-                        }
+            if absorb_only {
+                match strategy::absorb_only(&mut system) {
+                    Ok(report) => {
+                        println!("linear equations found : {}", report.lin_eqs);
+                        println!("bdds remaining : {}", report.bdds);
+                        println!("total nodes remaining : {}", report.nodes);
+                    }
+                    Err(e) => println!("Absorption aborted: {}", e),
+                }
+                return;
+            }
+            let forbid_dropping: Vec<usize> = layout.key_vars.clone().collect();
+            if !compare_strategies.is_empty() {
+                let runs = match bench::compare_strategies(
+                    &system,
+                    &compare_strategies,
+                    &forbid_dropping,
+                    cipher.key_length(),
+                ) {
+                    Ok(runs) => runs,
+                    Err(e) => {
+                        println!("Comparison aborted: {}", e);
+                        return;
                     }
-                ,
+                };
+                println!("strategy,seconds,peak_nodes,solutions");
+                for run in runs.iter() {
+                    println!(
+                        "{},{:.3},{},{}",
+                        run.strategy, run.seconds, run.peak_nodes, run.solutions
+                    );
+                }
+                if runs.iter().any(|run| run.keys != runs[0].keys) {
+                    println!("The strategies recovered different keys, something went wrong");
+                    return;
+                }
+                println!("all strategies recovered the same {} key(s)", runs[0].keys.len());
+                return;
+            }
+            if profile || profile_json {
+                // the strategies start with this absorption, it is done beforehand to time it
+                let start = Instant::now();
+                if let Err(e) = strategy::absorb_only(&mut system) {
+                    println!("Solving aborted: {}", e);
+                    return;
+                }
+                phases.push(("absorb", start.elapsed().as_secs_f64()));
+            }
+            let log_level = if explain {
+                strategy::LogLevel::Explain
+            } else {
+                log_level
+            };
+            let start = Instant::now();
+            let result = match strategy {
+                Some(name) => strategy::execute_strategy_by_name(
+                    name.as_ref(),
+                    &mut system,
+                    Some(&forbid_dropping),
+                    log_level,
+                ),
                 None => {
-                    strategy::execute_strategy_by_name("no_drop", &mut system, None).unwrap()
+                    strategy::execute_strategy_by_name("no_drop", &mut system, None, log_level)
                 }
             };
+            phases.push(("solve", start.elapsed().as_secs_f64()));
+            let sols = match result {
+                Some(Ok(sols)) => sols,
+                Some(Err(e)) => {
+                    println!("Solving aborted: {}", e);
+                    return;
+                }
+                None => {
+                    println!("Strategy not supported. Check --help for supported strategies.");
+                    return;
+                }
+            };
+            // the solutions of the solver are capped, enumerate again up to the maximum
+            let (mut sols, truncated) = match max_solutions {
+                Some(max) => match system.get_solutions_bounded(max) {
+                    Ok(bounded) => bounded,
+                    Err(e) => {
+                        println!("Solving aborted: {}", e);
+                        return;
+                    }
+                },
+                None => (sols, false),
+            };
             for sol in sols.iter_mut() {
+                if full_solution {
+                    for (label, part) in layout.label_solution(sol).iter() {
+                        println!(
+                            "{} : {}",
+                            label,
+                            bit::bits_to_binary_string(bit::bits_from_values(part))
+                        );
+                    }
+                }
                 sol.split_off(cipher.key_length());
                 let mut binary_string_sol = String::new();
                 for var in sol.iter() {
@@ -117,25 +297,129 @@ fn main() {
                             }
                         },
                         None => {
-                            if cipher_name == "des" {
-                                // with des this will always be the case as some bits of the 64 bit key are
-                                // unused. We can therefore just push 0 and the encryption will validate.
-                                // Kind of an ugly fix, the better fix would be to limit des to 56 bits and change
-                                // the test vectors
-                                binary_string_sol.push('0')
-                            } else {
-                                panic!("Some bits of the key are not determined, something wrong happened during the solving")
-                            }
+                            // the bit is not used by the encryption (ex: the parity bits of
+                            // des, or the key bits of katan32 not used by the first rounds) so
+                            // any value validates, the check below catches a wrong solving
+                            binary_string_sol.push('0')
                         }
                     }
                 }
-                let key = bit::bits_from_binary_string(&binary_string_sol);
+                let key = bit::bits_from_binary_string_unchecked(&binary_string_sol);
+                assert_eq!(
+                    ciphertext,
+                    cipher.encrypt(plaintext.clone(), key.clone()),
+                    "A solution was found but it doesn't encrypt correctly, something went wrong"
+                );
+                println!("valid solution : {}", bit::format_bits(key, output_format));
+            }
+            if truncated {
+                println!("... and more");
+            }
+            if profile_json {
+                let phases: Vec<String> = phases
+                    .iter()
+                    .map(|(phase, seconds)| format!("\"{}\":{:.6}", phase, seconds))
+                    .collect();
+                println!("{{\"profile\":{{{}}}}}", phases.join(","));
+            } else if profile {
+                for (phase, seconds) in phases.iter() {
+                    println!("{} : {:.3} seconds", phase, seconds);
+                }
+            }
+        }
+
+        CryptaPathOptions::RecoverPlaintext {
+            cipher_name,
+            rounds,
+            key,
+            ciphertext,
+            strategy,
+            max_bdd_nodes,
+            log_level,
+            output_format,
+        } => {
+            let rounds = match resolve_rounds(cipher_name.as_ref(), rounds) {
+                Ok(rounds) => rounds,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            let cipher = match build_cipher_by_name(cipher_name.as_ref(), Some(rounds)) {
+                Some(c) => c,
+                None => {
+                    println!(
+                        "Cipher not supported. Supported ciphers: {}.",
+                        supported_ciphers().join(", ")
+                    );
+                    return;
+                }
+            };
+            let key = match key {
+                Some(key) => match bit::bits_from_hex_string(&key) {
+                    Ok(bits) => bits,
+                    Err(e) => {
+                        println!("Invalid key: {}", e);
+                        return;
+                    }
+                },
+                None => bit::random_bits(cipher.key_length()),
+            };
+            if key.len() != cipher.key_length() {
+                println!(
+                    "The provided key has a size different from the key of {} bits",
+                    cipher.key_length()
+                );
+                return;
+            }
+            let ciphertext = match ciphertext {
+                Some(ciphertext) => match bit::bits_from_hex_string(&ciphertext) {
+                    Ok(bits) => bits,
+                    Err(e) => {
+                        println!("Invalid ciphertext: {}", e);
+                        return;
+                    }
+                },
+                None => cipher.encrypt(bit::random_bits(cipher.message_length()), key.clone()),
+            };
+            if ciphertext.len() != cipher.message_length() {
+                println!(
+                    "The provided ciphertext has a size different from the block of {} bits",
+                    cipher.message_length()
+                );
+                return;
+            }
+            let (layout, mut system) = build_system_cipher(cipher.as_ref());
+            system.set_max_bdd_nodes(max_bdd_nodes);
+            fix_system_values_cipher_with_key(&mut system, &layout, &key, &ciphertext);
+            let forbid_dropping: Vec<usize> = layout.message_vars.clone().collect();
+            let sols = match strategy::execute_strategy_by_name(
+                strategy.as_ref(),
+                &mut system,
+                Some(&forbid_dropping),
+                log_level,
+            ) {
+                Some(Ok(sols)) => sols,
+                Some(Err(e)) => {
+                    println!("Solving aborted: {}", e);
+                    return;
+                }
+                None => {
+                    println!("Strategy not supported. Check --help for supported strategies.");
+                    return;
+                }
+            };
+            for sol in sols.iter() {
+                let plaintext: Vec<bit::Bit> = sol[layout.message_vars.clone()]
+                    .iter()
+                    .map(|var| bit!(var.expect("Some bits of the plaintext are not determined")))
+                    .collect();
                 assert_eq!(
                     ciphertext,
                     cipher.encrypt(plaintext.clone(), key.clone()),
                     "A solution was found but it doesn't encrypt correctly, something went wrong"
                 );
-                println!("valid solution : {}", bit::bits_to_hex_string(key));
+                println!("valid solution : {}", bit::format_bits(plaintext, output_format));
             }
         }
 
@@ -148,13 +432,12 @@ fn main() {
             capacity,
             image,
             preimage,
+            search_length,
             out,
+            max_bdd_nodes,
+            log_level,
+            output_format,
         } => {
-            assert_eq!(
-                message_length % rate,
-                0,
-                "message_length should be a multiple of rate"
-            );
             let hash = match build_sponge_by_name(
                 sponge.as_ref(),
                 rounds,
@@ -165,15 +448,23 @@ fn main() {
             ) {
                 Some(h) => h,
                 None => {
-                    println!("Sponge not supported. Check --help for supported sponges.");
+                    println!(
+                        "Sponge not supported. Supported sponges: {}.",
+                        supported_sponges().join(", ")
+                    );
                     return;
                 }
             };
-            let (output, mut system) = build_system_sponge(hash.as_ref());
+            if hash.message_length() % hash.rate_length() != 0 {
+                println!("The message length should be a multiple of the rate");
+                return;
+            }
             let preimage_filled = match preimage {
                 Some(pre) => {
-                    assert!(pre.ends_with('1'),
-                    "the last bit of preimage has to be a 1 (padding is included in the preimage provided)");
+                    if !pre.ends_with('1') {
+                        println!("The last bit of the preimage has to be a 1 (the padding)");
+                        return;
+                    }
                     Some(fill_partial_value(pre.as_ref()))
                 }
                 None => None,
@@ -183,9 +474,59 @@ fn main() {
                     None => get_random_sponge_output(hash.as_ref()),
                     Some(p) => get_sponge_output_with_partial_preimage(hash.as_ref(), p.0),
                 },
-                Some(image) => keccak::bits_from_hex_string_keccak(image.as_ref()),
+                Some(image) => {
+                    match bit::bits_from_hex_string_with_order(image.as_ref(), hash.hex_bit_order()) {
+                        Ok(bits) => bits,
+                        Err(e) => {
+                            println!("Invalid image: {}", e);
+                            return;
+                        }
+                    }
+                }
             };
-            match preimage_filled {
+            if let Some(max_blocks) = search_length {
+                let config = SpongeConfig {
+                    name: sponge,
+                    rounds,
+                    message_length,
+                    output_length: hash_length,
+                    rate,
+                    capacity,
+                };
+                let steps = match bench::search_preimage_length(
+                    &config,
+                    &hash_value,
+                    max_blocks,
+                    max_bdd_nodes,
+                ) {
+                    Some(steps) => steps,
+                    None => {
+                        println!("The message length of {} can't be searched.", config.name);
+                        return;
+                    }
+                };
+                for step in steps {
+                    match step.preimage {
+                        Some(preimage) => println!(
+                            "message length {} : valid solution : {}",
+                            step.message_length,
+                            bit::format_bits_with_hex(
+                                preimage,
+                                output_format,
+                                keccak::bits_to_hex_string_keccak
+                            )
+                        ),
+                        None => println!(
+                            "message length {} : no preimage found ({:.3} seconds)",
+                            step.message_length, step.seconds
+                        ),
+                    }
+                }
+                return;
+            }
+            let (output, mut system) = build_system_sponge(hash.as_ref());
+            system.set_max_bdd_nodes(max_bdd_nodes);
+            let fixed = match preimage_filled {
                 Some(p) => fix_system_values_sponge_with_partial_preimage(
                     hash.as_ref(),
                     &mut system,
@@ -194,17 +535,29 @@ fn main() {
                     p,
                 ),
                 None => fix_system_values_sponge(hash.as_ref(), &mut system, &hash_value, &output),
+            };
+            if let Err(e) = fixed {
+                println!("No preimage of this length: {}", e);
+                return;
             }
             if let Some(path) = out {
                 print_system_to_file(&system, &path);
             }
             let forbid_dropping: Vec<usize> = (0..hash.message_length()).collect();
-            let mut sols = strategy::execute_strategy_by_name(
+            let mut sols = match strategy::execute_strategy_by_name(
                 "drop",
                 &mut system,
                 Some(&forbid_dropping),
+                log_level,
             )
-            .unwrap();
+            .unwrap()
+            {
+                Ok(sols) => sols,
+                Err(e) => {
+                    println!("Solving aborted: {}", e);
+                    return;
+                }
+            };
             for sol in sols.iter_mut() {
                 sol.split_off(hash.message_length());
                 let mut binary_string_sol = String::new();
@@ -221,32 +574,285 @@ fn main() {
                         None => panic!("shouldn't happen"),
                     }
                 }
-                let preimage = bit::bits_from_binary_string(&binary_string_sol);
+                let preimage = bit::bits_from_binary_string_unchecked(&binary_string_sol);
                 assert_eq!(hash_value, hash.hash(preimage.clone()));
                 println!(
                     "valid solution : {}",
-                    keccak::bits_to_hex_string_keccak(preimage)
+                    bit::format_bits_with_hex(
+                        preimage,
+                        output_format,
+                        keccak::bits_to_hex_string_keccak
+                    )
                 );
             }
         }
 
-        CryptaPathOptions::MakeParam { cipher, rounds } => {
-            let cipher = match build_cipher_by_name(cipher.as_ref(), rounds) {
+        CryptaPathOptions::MakeParam {
+            cipher,
+            rounds,
+            output_format,
+        } => {
+            let rounds = match resolve_rounds(cipher.as_ref(), rounds) {
+                Ok(rounds) => rounds,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            let cipher = match build_cipher_by_name(cipher.as_ref(), Some(rounds)) {
                 Some(c) => c,
                 None => {
-                    println!("Cipher not supported. Check --help for supported ciphers.");
+                    println!(
+                        "Cipher not supported. Supported ciphers: {}.",
+                        supported_ciphers().join(", ")
+                    );
                     return;
                 }
             };
             let (plaintext, ciphertext, key) = get_random_plaintext_ciphertext_key(cipher.as_ref());
             println!("plaintext : {}", bit::bits_to_hex_string(plaintext));
             println!("ciphertext : {}", bit::bits_to_hex_string(ciphertext));
-            println!("key : {}", bit::bits_to_binary_string(key));
+            println!("key : {}", bit::format_bits(key, output_format));
         }
-        CryptaPathOptions::FromFile { file } => {
+        CryptaPathOptions::Benchmark {
+            rounds,
+            seed,
+            ciphers,
+        } => {
+            let ciphers: Vec<String> = if ciphers.is_empty() {
+                supported_ciphers().iter().map(|name| name.to_string()).collect()
+            } else {
+                ciphers
+            };
+            println!("{}", bench::CSV_HEADER);
+            for name in ciphers.iter() {
+                // prince panics with an odd number of rounds
+                let rounds = if name.starts_with("prince") && rounds % 2 == 1 {
+                    rounds + 1
+                } else {
+                    rounds
+                };
+                if let Err(e) = check_rounds(name, rounds) {
+                    println!("skipping {} : {}", name, e);
+                    continue;
+                }
+                match bench::bench_cipher(name, rounds, seed) {
+                    Some(rows) => rows.iter().for_each(|row| println!("{}", row.to_csv())),
+                    None => println!(
+                        "Cipher not supported. Supported ciphers: {}.",
+                        supported_ciphers().join(", ")
+                    ),
+                }
+            }
+        }
+        CryptaPathOptions::RoundsSweep {
+            cipher_name,
+            max_rounds,
+            known_key_bits,
+            max_bdd_nodes,
+            max_seconds,
+            seed,
+        } => {
+            let steps = match bench::sweep_rounds(
+                cipher_name.as_ref(),
+                max_rounds,
+                known_key_bits,
+                max_bdd_nodes,
+                max_seconds,
+                seed,
+            ) {
+                Some(steps) => steps,
+                None => {
+                    println!(
+                        "Cipher not supported. Supported ciphers: {}.",
+                        supported_ciphers().join(", ")
+                    );
+                    return;
+                }
+            };
+            println!("rounds,seconds,peak_nodes,solved");
+            for step in steps.iter() {
+                println!(
+                    "{},{:.3},{},{}",
+                    step.rounds, step.seconds, step.peak_nodes, step.solved
+                );
+            }
+            match steps.iter().rev().find(|step| step.solved) {
+                Some(step) => println!(
+                    "max rounds solved within budget : {} ({:.3}s, {} nodes)",
+                    step.rounds, step.seconds, step.peak_nodes
+                ),
+                None => println!("no round count solved within budget"),
+            }
+        }
+        CryptaPathOptions::Analyze {
+            cipher_name,
+            rounds,
+        } => {
+            let rounds = match resolve_rounds(cipher_name.as_ref(), rounds) {
+                Ok(rounds) => rounds,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            let cipher = match build_cipher_by_name(cipher_name.as_ref(), Some(rounds)) {
+                Some(cipher) => cipher,
+                None => {
+                    println!(
+                        "Cipher not supported. Supported ciphers: {}.",
+                        supported_ciphers().join(", ")
+                    );
+                    return;
+                }
+            };
+            let sbox = cipher.sbox();
+            println!("sbox is a permutation : {}", sbox.is_permutation());
+            println!("sbox algebraic degree : {}", sbox.algebraic_degree());
+            if let Some((matrix, word_size)) = cipher.linear_layer_matrix() {
+                // beyond a few nonzero input words the search takes too long on big states
+                let max_active_words = 3;
+                match crush::algebra::branch_number_bounded(&matrix, word_size, max_active_words) {
+                    (branch_number, true) => {
+                        println!("linear layer branch number : {}", branch_number)
+                    }
+                    (bound, false) => println!(
+                        "linear layer branch number : at most {} (inputs of up to {} nonzero words searched)",
+                        bound, max_active_words
+                    ),
+                }
+            }
+            let profile = cipher.equation_profile();
+            for (round, equations) in profile.iter().enumerate() {
+                println!(
+                    "round {} : {} sbox relations, {} linear equations",
+                    round + 1,
+                    equations.sbox_relations,
+                    equations.linear_equations
+                );
+            }
+            println!(
+                "total : {} sbox relations, {} linear equations",
+                profile.iter().map(|round| round.sbox_relations).sum::<usize>(),
+                profile.iter().map(|round| round.linear_equations).sum::<usize>()
+            );
+        }
+        CryptaPathOptions::FromFile {
+            file,
+            max_bdd_nodes,
+            log_level,
+            json,
+            output_format,
+        } => {
             let specs = parse_system_spec_from_file(&file);
             let mut system = build_system_from_spec(specs);
-            strategy::execute_strategy_by_name("no_drop", &mut system, None).unwrap();
+            system.set_max_bdd_nodes(max_bdd_nodes);
+            let log_level = if json { strategy::LogLevel::Quiet } else { log_level };
+            let sols =
+                match strategy::execute_strategy_by_name("no_drop", &mut system, None, log_level)
+                    .unwrap()
+                {
+                    Ok(sols) => sols,
+                    Err(e) => {
+                        println!("Solving aborted: {}", e);
+                        return;
+                    }
+                };
+            let render = |sol: &[Option<bool>], format| {
+                let bits = bit::bits_from_values(sol);
+                bit::format_bits_with_hex(bits, format, bit::bits_to_padded_hex_string)
+            };
+            if json {
+                let solutions: Vec<String> = sols
+                    .iter()
+                    .map(|sol| {
+                        format!(
+                            "{{\"binary\":\"{}\",\"hex\":\"{}\"}}",
+                            render(sol, bit::OutputFormat::Bin),
+                            render(sol, bit::OutputFormat::Hex)
+                        )
+                    })
+                    .collect();
+                println!("{{\"solutions\":[{}]}}", solutions.join(","));
+            } else {
+                for sol in sols.iter() {
+                    println!("solution : {}", render(sol, output_format));
+                }
+            }
+        }
+        CryptaPathOptions::FromAnf {
+            file,
+            max_bdd_nodes,
+            log_level,
+            output_format,
+        } => {
+            let (spec, nvar) = match parse_system_spec_from_anf_file(&file) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    println!("Could not read the equations: {}", e);
+                    return;
+                }
+            };
+            let mut system = build_system_from_spec(spec);
+            system.set_max_bdd_nodes(max_bdd_nodes);
+            let sols =
+                match strategy::execute_strategy_by_name("no_drop", &mut system, None, log_level)
+                    .unwrap()
+                {
+                    Ok(sols) => sols,
+                    Err(e) => {
+                        println!("Solving aborted: {}", e);
+                        return;
+                    }
+                };
+            for sol in sols.iter() {
+                // the auxiliary variables of the quadratic monomials come after x0..xn
+                let bits = bit::bits_from_values(&sol[..nvar]);
+                println!(
+                    "solution (x0..x{}) : {}",
+                    nvar.saturating_sub(1),
+                    bit::format_bits_with_hex(bits, output_format, bit::bits_to_padded_hex_string)
+                );
+            }
+        }
+        CryptaPathOptions::List => {
+            println!("ciphers :");
+            for name in supported_ciphers().iter() {
+                let (cipher, rounds) = match build_cipher_by_name(name, None) {
+                    Some(cipher) => {
+                        let rounds = format!("{} rounds", cipher.full_rounds());
+                        (cipher, rounds)
+                    }
+                    // lowmc has no full number of rounds, its sizes do not depend on the rounds
+                    None => match build_cipher_by_name(name, Some(1)) {
+                        Some(cipher) => (cipher, "any number of rounds".to_string()),
+                        None => {
+                            println!("Cipher {} is listed but can't be built.", name);
+                            return;
+                        }
+                    },
+                };
+                println!(
+                    "  {} (block {} bits, key {} bits, {}) : {}",
+                    name,
+                    cipher.message_length(),
+                    cipher.key_length(),
+                    rounds,
+                    cipher_description(name).unwrap()
+                );
+            }
+            println!("sponges :");
+            for name in supported_sponges().iter() {
+                println!("  {} : {}", name, sponge_description(name).unwrap());
+            }
+            println!("strategies :");
+            for name in strategy::supported_strategies().iter() {
+                println!(
+                    "  {} : {}",
+                    name,
+                    strategy::strategy_description(name).unwrap()
+                );
+            }
         }
     }
 }
@@ -1,3 +1,5 @@
+use crate::bit::{BitOrder, OutputFormat};
+use crate::strategy::LogLevel;
 use std::path::PathBuf;
 
 #[derive(Clone, StructOpt)]
@@ -6,16 +8,27 @@ use std::path::PathBuf;
     about = "A tool to generate systems of BDD from an implementation and solve it",
     author = "SimulaUiB"
 )]
+pub struct CryptaPathArgs {
+    #[structopt(long = "threads", global = true)]
+    /// The number of threads used by the parallel parts of the building and of the solving,
+    /// one per core by default. With 1 everything runs sequentially.
+    pub threads: Option<usize>,
+    #[structopt(subcommand)]
+    pub command: CryptaPathOptions,
+}
+
+#[derive(Clone, StructOpt)]
 pub enum CryptaPathOptions {
     #[structopt(name = "cipher")]
     Cipher {
         #[structopt(short = "c", long = "cipher")]
         ///Name of the target cipher. Currently supported: 
-        ///skinny64128, skinny128128, lowmc64, lowmc128, lowmc256, miniaes2x2, miniaes4x4, present80, prince, prince-core, des
+        ///skinny64128, skinny128128, lowmc64, lowmc128, lowmc256, miniaes2x2, miniaes4x4, present80, prince, prince-core, des, katan32
         cipher_name: String,
         #[structopt(short = "r", long = "rounds")]
-        ///The number of rounds to run on the cipher
-        rounds: usize,
+        ///The number of rounds to run on the cipher, the full number of rounds of the
+        /// cipher by default (required for the lowmc ciphers)
+        rounds: Option<usize>,
         #[structopt(short = "p", long = "plaintext_ciphertext")]
         /// A pair of plaintext/ciphertext encrypted under a valid key by the target cipher
         /// The expected format is hexadecimal.
@@ -24,7 +37,11 @@ pub enum CryptaPathOptions {
         /// If not provided a random pair will be generate by generating a random plaintext and encrypting
         /// it under a key.
         chosen_plaintext_ciphertext: Option<Vec<String>>,
-        #[structopt(short = "k", long = "key")]
+        #[structopt(long = "input-hex-order")]
+        /// The order of the bits in each byte of the plaintext/ciphertext pair, "msb0" (most
+        /// significant bit first) or "lsb0". By default the order of the specification of the cipher.
+        input_hex_order: Option<BitOrder>,
+        #[structopt(short = "k", long = "key", alias = "key-bits")]
         ///If provided, this indicate the known bits of the key.
         ///The String should contain only X or x for the unknown bits and 0 or 1 for the known bits,
         ///and be the exact length of the key used by the chosen cipher.
@@ -33,18 +50,104 @@ pub enum CryptaPathOptions {
         ///if none are provided.
         ///If not provided a completely random key will be generated at run time (equivalent to all X).
         key: Option<String>,
+        #[structopt(long = "key-relations", parse(from_os_str))]
+        /// If provided, a file of linear relations between the bits of the key, one per line
+        /// (ex: 0+1 = 0 for the first two bits being equal), restricting the keys to recover.
+        /// The random key generated when no plaintext/ciphertext pair is provided satisfies them.
+        key_relations: Option<PathBuf>,
         #[structopt(short = "o", long = "output", parse(from_os_str))]
         /// If provided will output a .bdd file of the system (after fixing the values) at the provided path
         out: Option<PathBuf>,
+        #[structopt(long = "cache-dir", parse(from_os_str))]
+        /// If provided, the system of the cipher is loaded from this directory, where it is saved
+        /// the first time, instead of being built by running the cipher.
+        cache_dir: Option<PathBuf>,
         #[structopt(short = "s", long = "strategy")]
         /// Choose the strategy when trying to solve.
-        /// Available choices: "drop" "no_drop", default: "no_drop"
+        /// Available choices: "drop" "no_drop" "single_first", default: "no_drop"
         strategy: Option<String>,
+        #[structopt(long = "max-bdd-nodes")]
+        /// If provided, the solving is aborted as soon as a single BDD would grow
+        /// past this number of nodes, instead of running out of memory.
+        max_bdd_nodes: Option<usize>,
+        #[structopt(long = "log-level", default_value = "normal")]
+        /// How much progress to print while solving.
+        /// Available choices: "quiet", "normal", "verbose", "tui" (verbose, clearing the screen at each step)
+        /// and "explain" (one line describing each resolution)
+        log_level: LogLevel,
+        #[structopt(long = "max-solutions")]
+        /// If provided, at most this number of solutions are validated and printed.
+        max_solutions: Option<usize>,
+        #[structopt(long = "compare-strategies")]
+        /// If provided, solve the same instance with each of these strategies (ex: --compare-strategies no_drop drop)
+        /// and print the time, the maximum number of nodes reached and the number of solutions of each,
+        /// instead of printing the solutions.
+        compare_strategies: Vec<String>,
+        #[structopt(long = "output-format", default_value = "hex")]
+        /// How the recovered keys are printed.
+        /// Available choices: "hex", "bin" and "both" (hexadecimal followed by binary)
+        output_format: OutputFormat,
+        #[structopt(long = "full-solution")]
+        /// If provided, also print the value of every variable of each solution (in binary, '?' if
+        /// undetermined): the key, the tweak, the message and the intermediate variables.
+        full_solution: bool,
+        #[structopt(long = "absorb-only")]
+        /// If provided, only absorb the linear equations readily found in the system and print
+        /// the number of linear equations found, of BDDs and of nodes remaining, without solving.
+        absorb_only: bool,
+        #[structopt(long = "profile")]
+        /// If provided, print at the end how long each phase took: building the system, fixing
+        /// the values, the first absorption of the linear equations and the solving.
+        profile: bool,
+        #[structopt(long = "profile-json")]
+        /// Same as --profile, but the timings are printed as a JSON object of the seconds taken
+        /// by each phase.
+        profile_json: bool,
+        #[structopt(long = "explain")]
+        /// If provided, print instead of the progress one line per resolution, telling which
+        /// dependency was chosen (same as --log-level explain).
+        explain: bool,
+    },
+    #[structopt(name = "recover-plaintext")]
+    RecoverPlaintext {
+        #[structopt(short = "c", long = "cipher")]
+        ///Name of the target cipher. Currently supported:
+        ///skinny64128, skinny128128, lowmc64, lowmc128, lowmc256, miniaes2x2, miniaes4x4, present80, prince, prince-core, des, katan32
+        cipher_name: String,
+        #[structopt(short = "r", long = "rounds")]
+        ///The number of rounds to run on the cipher, the full number of rounds of the
+        /// cipher by default (required for the lowmc ciphers)
+        rounds: Option<usize>,
+        #[structopt(short = "k", long = "key")]
+        /// The key the ciphertext was encrypted under, in hexadecimal.
+        /// If not provided a random key will be generated at run time.
+        key: Option<String>,
+        #[structopt(long = "ciphertext")]
+        /// The ciphertext to recover the plaintext of, in hexadecimal.
+        /// If not provided a random plaintext will be encrypted under the key.
+        ciphertext: Option<String>,
+        #[structopt(short = "s", long = "strategy", default_value = "drop")]
+        /// Choose the strategy when trying to solve, the message variables are never dropped.
+        /// Available choices: "drop" "no_drop" "single_first"
+        strategy: String,
+        #[structopt(long = "max-bdd-nodes")]
+        /// If provided, the solving is aborted as soon as a single BDD would grow
+        /// past this number of nodes, instead of running out of memory.
+        max_bdd_nodes: Option<usize>,
+        #[structopt(long = "log-level", default_value = "normal")]
+        /// How much progress to print while solving.
+        /// Available choices: "quiet", "normal", "verbose" and "tui" (verbose, clearing the screen at each step)
+        log_level: LogLevel,
+        #[structopt(long = "output-format", default_value = "hex")]
+        /// How the recovered plaintexts are printed.
+        /// Available choices: "hex", "bin" and "both" (hexadecimal followed by binary)
+        output_format: OutputFormat,
     },
     #[structopt(name = "sponge")]
     Sponge {
         #[structopt(short = "s", long = "sponge")]
-        ///Name of the target SpongeHash. Currently supported: keccak
+        ///Name of the target SpongeHash. Currently supported: keccak, and the crunchy contest
+        /// presets keccak-crunchy-1 to keccak-crunchy-3 (which ignore the other parameters)
         sponge: String,
         #[structopt(short = "r", long = "rounds")]
         ///The number of rounds to run on the hash
@@ -75,25 +178,134 @@ pub enum CryptaPathOptions {
         /// If no image where provided the partial preimage will be fill
         /// with random value on unknown bits and hashed to create an image.
         preimage: Option<String>,
+        #[structopt(long = "search-length")]
+        /// If provided, the message length is searched instead: a preimage of the image is
+        /// looked for with a message of one block of the rate, then two blocks and so on up to
+        /// this number of blocks, stopping at the first length with a preimage.
+        /// Without an image, the image of a random message of message-length bits (or of the
+        /// partial preimage) is used.
+        search_length: Option<usize>,
         #[structopt(short = "o", long = "output", parse(from_os_str))]
         /// If provided will output a .bdd file of the system (after fixing the values) at the provided path
-        out: Option<PathBuf>
+        out: Option<PathBuf>,
+        #[structopt(long = "max-bdd-nodes")]
+        /// If provided, the solving is aborted as soon as a single BDD would grow
+        /// past this number of nodes, instead of running out of memory.
+        max_bdd_nodes: Option<usize>,
+        #[structopt(long = "log-level", default_value = "normal")]
+        /// How much progress to print while solving.
+        /// Available choices: "quiet", "normal", "verbose" and "tui" (verbose, clearing the screen at each step)
+        log_level: LogLevel,
+        #[structopt(long = "output-format", default_value = "hex")]
+        /// How the preimages found are printed.
+        /// Available choices: "hex", "bin" and "both" (hexadecimal followed by binary)
+        output_format: OutputFormat,
     },
 
     #[structopt(name = "make-cipher-param")]
     MakeParam {
         #[structopt(short = "c", long = "cipher")]
         ///Name of the target cipher. Currently supported: 
-        ///skinny64128, skinny128128, lowmc64, lowmc128, lowmc256, miniaes2x2, miniaes4x4, present80, prince, prince-core, des
+        ///skinny64128, skinny128128, lowmc64, lowmc128, lowmc256, miniaes2x2, miniaes4x4, present80, prince, prince-core, des, katan32
         cipher: String,
         #[structopt(short = "r", long = "rounds")]
-        ///The number of rounds to run on the cipher
+        ///The number of rounds to run on the cipher, the full number of rounds of the
+        /// cipher by default (required for the lowmc ciphers)
+        rounds: Option<usize>,
+        #[structopt(long = "output-format", default_value = "bin")]
+        /// How the key is printed (binary by default, the format of the --key option of cipher).
+        /// Available choices: "hex", "bin" and "both" (hexadecimal followed by binary)
+        output_format: OutputFormat,
+    },
+    #[structopt(name = "benchmark")]
+    Benchmark {
+        #[structopt(short = "r", long = "rounds", default_value = "1")]
+        /// The number of rounds to run on the ciphers (prince always runs an even number of rounds)
         rounds: usize,
+        #[structopt(long = "seed", default_value = "0")]
+        /// The seed used to draw the plaintext and the key of every instance
+        seed: u64,
+        #[structopt(short = "c", long = "cipher")]
+        /// The ciphers to benchmark, all the supported ciphers by default
+        ciphers: Vec<String>,
+    },
+    #[structopt(name = "rounds-sweep")]
+    RoundsSweep {
+        #[structopt(short = "c", long = "cipher")]
+        ///Name of the target cipher. Currently supported:
+        ///skinny64128, skinny128128, lowmc64, lowmc128, lowmc256, miniaes2x2, miniaes4x4, present80, prince, prince-core, des, katan32
+        cipher_name: String,
+        #[structopt(long = "max-rounds", default_value = "20")]
+        /// The highest number of rounds tried
+        max_rounds: usize,
+        #[structopt(long = "known-key-bits", default_value = "0")]
+        /// The number of bits of the key (starting from the first one) given to the solver
+        known_key_bits: usize,
+        #[structopt(long = "max-bdd-nodes")]
+        /// If provided, a round count is not solved within budget if a single BDD
+        /// grows past this number of nodes.
+        max_bdd_nodes: Option<usize>,
+        #[structopt(long = "max-seconds")]
+        /// If provided, a round count is not solved within budget if the solving takes longer
+        /// than this number of seconds (the solving is not interrupted).
+        max_seconds: Option<f64>,
+        #[structopt(long = "seed", default_value = "0")]
+        /// The seed used to draw the plaintext and the key of every instance
+        seed: u64,
+    },
+    #[structopt(name = "analyze")]
+    Analyze {
+        #[structopt(short = "c", long = "cipher")]
+        ///Name of the target cipher. Currently supported:
+        ///skinny64128, skinny128128, lowmc64, lowmc128, lowmc256, miniaes2x2, miniaes4x4, present80, prince, prince-core, des, katan32
+        cipher_name: String,
+        #[structopt(short = "r", long = "rounds")]
+        ///The number of rounds to run on the cipher, the full number of rounds of the
+        /// cipher by default (required for the lowmc ciphers)
+        rounds: Option<usize>,
     },
     #[structopt(name = "from-file")]
     FromFile {
         #[structopt(short = "f", long = "file", parse(from_os_str))]
         /// The source bdd file
-        file: PathBuf
-    }
+        file: PathBuf,
+        #[structopt(long = "max-bdd-nodes")]
+        /// If provided, the solving is aborted as soon as a single BDD would grow
+        /// past this number of nodes, instead of running out of memory.
+        max_bdd_nodes: Option<usize>,
+        #[structopt(long = "log-level", default_value = "normal")]
+        /// How much progress to print while solving.
+        /// Available choices: "quiet", "normal", "verbose" and "tui" (verbose, clearing the screen at each step)
+        log_level: LogLevel,
+        #[structopt(long = "json")]
+        /// Print the solutions as a single JSON object instead of one line per solution
+        /// (the progress is then not printed, as with --log-level quiet), giving both formats.
+        json: bool,
+        #[structopt(long = "output-format", default_value = "both")]
+        /// How the solutions are printed, an undetermined bit being a '?'.
+        /// Available choices: "hex", "bin" and "both" (hexadecimal followed by binary)
+        output_format: OutputFormat,
+    },
+    #[structopt(name = "list")]
+    /// Print the supported ciphers, sponges and strategies
+    List,
+    #[structopt(name = "from-anf")]
+    FromAnf {
+        #[structopt(short = "f", long = "file", parse(from_os_str))]
+        /// The source file of GF(2) polynomial equations in ANF, one equation equal to 0 per line
+        /// (ex: x0*x1 + x2 + 1). Monomials can be of degree at most 2.
+        file: PathBuf,
+        #[structopt(long = "max-bdd-nodes")]
+        /// If provided, the solving is aborted as soon as a single BDD would grow
+        /// past this number of nodes, instead of running out of memory.
+        max_bdd_nodes: Option<usize>,
+        #[structopt(long = "log-level", default_value = "normal")]
+        /// How much progress to print while solving.
+        /// Available choices: "quiet", "normal", "verbose" and "tui" (verbose, clearing the screen at each step)
+        log_level: LogLevel,
+        #[structopt(long = "output-format", default_value = "bin")]
+        /// How the solutions are printed, an undetermined bit being a '?'.
+        /// Available choices: "hex", "bin" and "both" (hexadecimal followed by binary)
+        output_format: OutputFormat,
+    },
 }
\ No newline at end of file
@@ -10,11 +10,13 @@
 //! fresh Bit containing new variables and generate a BDD that will be use later
 //! when trying to solve the system of BDD obtain by running the cryptosystem
 
+use crate::anf::GF2Poly;
 use crate::bit::Bit;
 use crate::crush::soc::{
     utils::{BddSpec, LevelSpec, NodeSpec},
     Id,
 };
+use rayon::prelude::*;
 use std::cell::{Cell, RefCell};
 
 /// A S-Box, with in_size the number of bit to enter the S-Box, out_size the
@@ -33,8 +35,11 @@ pub struct Sbox {
 impl Sbox {
 
     /// A constructor for an SBox
+    ///
+    /// Panics if `table` doesn't have `2^in_size` entries or if one of them doesn't fit in
+    /// `out_size` bits.
     pub fn new(in_size: usize, out_size: usize, table: Vec<u8>, next_var_id: usize) -> Self {
-        assert_eq!(1 << in_size, table.len());
+        Self::check_table(in_size, out_size, &table);
 
         Sbox {
             in_size,
@@ -53,7 +58,7 @@ impl Sbox {
         table: Vec<u8>,
         mut old_sbox:Sbox,
     ) -> Self {
-        assert_eq!(1 << in_size, table.len());
+        Self::check_table(in_size, out_size, &table);
         Sbox {
             in_size,
             out_size,
@@ -63,36 +68,142 @@ impl Sbox {
         }
     }
 
+    fn check_table(in_size: usize, out_size: usize, table: &[u8]) {
+        assert_eq!(
+            table.len(),
+            1 << in_size,
+            "a {}x{} S-Box needs a table of {} entries, got {}",
+            in_size,
+            out_size,
+            1usize << in_size,
+            table.len()
+        );
+        if let Some((input, output)) = table
+            .iter()
+            .enumerate()
+            .find(|(_, output)| (**output as usize) >> out_size != 0)
+        {
+            panic!(
+                "the entry {:#x} of the table (for the input {:#x}) doesn't fit in {} bits",
+                output, input, out_size
+            );
+        }
+    }
+
+    /// Return the S-Box applying `self` then `other`, whose lookup table is
+    /// `other.table[self.table[x]]`.
+    ///
+    /// The new S-Box starts from the next_var_id of `self` and holds no BDD.
+    /// Will panic if the output size of `self` is not the input size of `other`.
+    pub fn compose(&self, other: &Sbox) -> Sbox {
+        assert_eq!(
+            self.out_size, other.in_size,
+            "the output of the first S-Box should have the size of the input of the second"
+        );
+        let table = self
+            .table
+            .iter()
+            .map(|out_value| other.table[*out_value as usize])
+            .collect();
+        Sbox::new(self.in_size, other.out_size, table, self.next_var_id())
+    }
+
+    /// Return true if the S-Box is a permutation, ie it has the same input and output size
+    /// and its lookup table is a bijection.
+    pub fn is_permutation(&self) -> bool {
+        if self.in_size != self.out_size {
+            return false;
+        }
+        let mut seen = vec![false; self.table.len()];
+        self.table.iter().all(|out_value| {
+            match seen.get_mut(*out_value as usize) {
+                Some(seen) if !*seen => {
+                    *seen = true;
+                    true
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// Return the inverse S-Box, whose lookup table maps each output of `self` to its input.
+    ///
+    /// The new S-Box starts from the next_var_id of `self` and holds no BDD.
+    /// Will panic with "only a permutation S-Box can be inverted" if `self` is not a
+    /// permutation (see `is_permutation`).
+    pub fn inverse(&self) -> Sbox {
+        assert!(
+            self.is_permutation(),
+            "only a permutation S-Box can be inverted"
+        );
+        let mut table = vec![0; self.table.len()];
+        for (in_value, out_value) in self.table.iter().enumerate() {
+            table[*out_value as usize] = in_value as u8;
+        }
+        Sbox::new(self.in_size, self.out_size, table, self.next_var_id())
+    }
+
+    /// Return the algebraic normal forms of the output bits of the S-Box, computed from the
+    /// lookup table with the Möbius transform.
+    ///
+    /// The polynomial `i` gives the bit `i` of the output (`(table[x] >> i) & 1`) and its
+    /// variable `j` is the bit `j` of the input (`(x >> j) & 1`).
+    pub fn anf(&self) -> Vec<GF2Poly> {
+        (0..self.out_size)
+            .map(|output_bit| {
+                let mut anf: Vec<u8> = self
+                    .table
+                    .iter()
+                    .map(|out_value| (out_value >> output_bit) & 1)
+                    .collect();
+                for i in 0..self.in_size {
+                    for x in 0..anf.len() {
+                        if x & (1 << i) != 0 {
+                            anf[x] ^= anf[x ^ (1 << i)];
+                        }
+                    }
+                }
+                GF2Poly::from_monomials(
+                    anf.iter()
+                        .enumerate()
+                        .filter(|(_, coefficient)| **coefficient == 1)
+                        .map(|(monomial, _)| {
+                            (0..self.in_size).filter(|i| monomial & (1 << i) != 0).collect()
+                        }),
+                )
+            })
+            .collect()
+    }
+
+    /// Return the algebraic degree of the S-Box, ie the highest degree among the algebraic normal
+    /// forms of its output bits (see `anf`).
+    pub fn algebraic_degree(&self) -> usize {
+        self.anf().iter().map(GF2Poly::degree).max().unwrap_or(0)
+    }
+
+    /// Return the number of input bits of the S-Box.
+    #[inline]
+    pub fn in_size(&self) -> usize {
+        self.in_size
+    }
+
+    /// Return the lookup table of the S-Box.
+    #[inline]
+    pub fn table(&self) -> &[u8] {
+        &self.table
+    }
+
     /// Produce a deterministic output of constant bits by using the
     /// lookup table.
     fn sbox_fixed_output(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
-        let in_value = usize::from_str_radix(
-            in_bits
-                .iter()
-                .map(|bit| if bit.constant() { "1" } else { "0" })
-                .collect::<String>()
-                .as_str(),
-            2,
-        )
-        .unwrap();
-        assert!(in_value < (1 << self.in_size));
-        let out_value = self.table[in_value];
-        let mut out_bits = Vec::with_capacity(self.out_size);
-        for i in 0..self.out_size {
-            match out_value >> (self.out_size - i - 1) & 0x01 {
-                0 => out_bits.push(bit!(false)),
-                1 => out_bits.push(bit!(true)),
-                _ => panic!("will not happen"),
-            }
-        }
-        out_bits
+        fixed_output(&in_bits, &self.table, self.out_size)
     }
 
     /// Return either a Vec of constant bits if all bits are constant
     /// or new set of Bit containing new Variable if at least one of them
     /// is not constant. In that case also produce and store a BDD.
     pub fn apply(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
-        if in_bits.iter().find(|bit| bit.vars().next().is_some()).is_none() {
+        if in_bits.iter().all(|bit| bit.is_constant().is_some()) {
             self.sbox_fixed_output(in_bits)
         } else {
             let mut out_bits = Vec::with_capacity(self.out_size);
@@ -107,6 +218,54 @@ impl Sbox {
         }
     }
     
+    /// Apply the S-Box in parallel to each cell of `in_bits` (ie each chunk of in_size bits)
+    /// and return the outputs concatenated in the order of the cells.
+    ///
+    /// The variables of the non constant cells are allocated up front in cell order, so
+    /// the output bits and the BDDs stored are the same as when calling `apply` on each
+    /// cell one after the other.
+    /// Will panic if the length of `in_bits` is not a multiple of in_size.
+    pub fn apply_layer(&self, in_bits: &[Bit]) -> Vec<Bit> {
+        assert!(
+            in_bits.chunks_exact(self.in_size).remainder().is_empty(),
+            "the layer should be made of whole cells"
+        );
+        let mut next_var_id = self.next_var_id.get();
+        let cells: Vec<(&[Bit], Option<usize>)> = in_bits
+            .chunks(self.in_size)
+            .map(|cell| {
+                if cell.iter().all(|bit| bit.is_constant().is_some()) {
+                    (cell, None)
+                } else {
+                    next_var_id += self.out_size;
+                    (cell, Some(next_var_id - self.out_size))
+                }
+            })
+            .collect();
+        self.next_var_id.set(next_var_id);
+        // the S-Box itself is not Sync, only share what the cells need
+        let (table, out_size) = (&self.table, self.out_size);
+        let outputs: Vec<(Vec<Bit>, Option<BddSpec>)> = cells
+            .par_iter()
+            .map(|(cell, first_var_id)| match first_var_id {
+                None => (fixed_output(cell, table, out_size), None),
+                Some(id) => {
+                    let out_bits: Vec<Bit> =
+                        (*id..*id + out_size).map(Bit::from_variable_id).collect();
+                    let bdd = buid_bdd_spec(cell.to_vec(), out_bits.clone(), table);
+                    (out_bits, Some(bdd))
+                }
+            })
+            .collect();
+        let mut bdds = self.bdds.borrow_mut();
+        let mut out_bits = Vec::with_capacity(outputs.len() * out_size);
+        for (mut bits, bdd) in outputs {
+            out_bits.append(&mut bits);
+            bdds.extend(bdd);
+        }
+        out_bits
+    }
+
     /// Returns the BDDs built by the S-Box (remove them from the S-Box).
     pub fn bdds(&mut self) -> Vec<BddSpec> {
         self.bdds.replace(Vec::new())
@@ -120,6 +279,31 @@ impl Sbox {
     }
 }
 
+/// Produce a deterministic output of out_size constant bits by looking up
+/// the value of `in_bits` in `table`.
+fn fixed_output(in_bits: &[Bit], table: &[u8], out_size: usize) -> Vec<Bit> {
+    let in_value = usize::from_str_radix(
+        in_bits
+            .iter()
+            .map(|bit| if bit.constant() { "1" } else { "0" })
+            .collect::<String>()
+            .as_str(),
+        2,
+    )
+    .unwrap();
+    assert!(in_value < table.len());
+    let out_value = table[in_value];
+    let mut out_bits = Vec::with_capacity(out_size);
+    for i in 0..out_size {
+        match out_value >> (out_size - i - 1) & 0x01 {
+            0 => out_bits.push(bit!(false)),
+            1 => out_bits.push(bit!(true)),
+            _ => panic!("will not happen"),
+        }
+    }
+    out_bits
+}
+
 /// Make a BDDSpec out of the entry bits and the out bits of the SBox by using the 
 /// lookup table to build the graph.
 /// First the top part of the BDD is made by making the top layers of the BDD (from the top_layers).
@@ -244,3 +428,132 @@ fn test_apply_fixed_output_sbox_keccak() {
     ];
     assert_eq!(expected, sbox.apply(bits));
 }
+
+#[test]
+fn test_compose_with_inverse_is_identity() {
+    use crate::targets::{miniaes4x4::MiniAES4x4, Cipher};
+    let sbox = MiniAES4x4::new(1).sbox();
+    let mut inverse_table = vec![0; sbox.table().len()];
+    for (x, y) in sbox.table().iter().enumerate() {
+        inverse_table[*y as usize] = x as u8;
+    }
+    let inverse = Sbox::new(4, 4, inverse_table, 0);
+    let identity: Vec<u8> = (0..16).collect();
+    assert_eq!(identity, sbox.compose(&inverse).table());
+    assert_eq!(identity, inverse.compose(&sbox).table());
+    // applying the composition is the same as applying both S-Boxes in series
+    let bits = vec![bit!(true), bit!(false), bit!(true), bit!(true)];
+    assert_eq!(
+        inverse.apply(sbox.apply(bits.clone())),
+        sbox.compose(&inverse).apply(bits)
+    );
+}
+
+#[test]
+fn test_apply_layer_matches_serial_apply() {
+    use crate::targets::{miniaes4x4::MiniAES4x4, Cipher};
+    use std::collections::HashSet;
+    let mut serial = MiniAES4x4::new(1).sbox();
+    let mut parallel = serial.clone();
+    // a mix of constant and unknown cells
+    let mut in_bits = Vec::new();
+    for cell in 0..16 {
+        for bit in 0..4 {
+            if cell % 3 == 0 {
+                in_bits.push(bit!((cell + bit) & 1 == 1));
+            } else {
+                in_bits.push(Bit::from_variable_id(cell * 4 + bit));
+            }
+        }
+    }
+    let mut expected = Vec::new();
+    for cell in in_bits.chunks(4) {
+        expected.append(&mut serial.apply(cell.to_vec()));
+    }
+    assert_eq!(expected, parallel.apply_layer(&in_bits));
+    assert_eq!(serial.next_var_id(), parallel.next_var_id());
+    assert_eq!(
+        format!("{:?}", serial.bdds()),
+        format!("{:?}", parallel.bdds())
+    );
+    let vars: Vec<usize> = expected
+        .iter()
+        .flat_map(|bit| bit.vars().map(|var| var.id()).collect::<Vec<_>>())
+        .collect();
+    assert_eq!(vars.len(), 10 * 4);
+    assert_eq!(vars.len(), vars.iter().collect::<HashSet<_>>().len());
+}
+
+#[test]
+fn test_is_permutation() {
+    use crate::targets::{des::DES, miniaes2x2::MiniAES2x2, Cipher};
+    let sbox = MiniAES2x2::new(1).sbox();
+    assert!(sbox.is_permutation());
+    assert!(sbox.inverse().is_permutation());
+    // a 6x4 S-Box
+    assert!(!DES::new(1).sbox().is_permutation());
+    // same size but not a bijection
+    assert!(!Sbox::new(2, 2, vec![0, 1, 1, 3], 0).is_permutation());
+}
+
+#[test]
+#[should_panic(expected = "only a permutation S-Box can be inverted")]
+fn test_inverse_of_des_sbox() {
+    use crate::targets::{des::DES, Cipher};
+    DES::new(1).sbox().inverse();
+}
+
+#[test]
+fn test_table_validation() {
+    let table = vec![
+        0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2,
+    ];
+    assert!(Sbox::new(4, 4, table, 0).is_permutation());
+    // 6x4: the entries fit in 4 bits even if the table is bigger
+    assert!(!Sbox::new(6, 4, vec![0xf; 64], 0).is_permutation());
+}
+
+#[test]
+#[should_panic(expected = "a 4x4 S-Box needs a table of 16 entries, got 15")]
+fn test_table_of_wrong_length() {
+    Sbox::new(4, 4, (0..15).collect(), 0);
+}
+
+#[test]
+#[should_panic(expected = "the entry 0x1f of the table (for the input 0x3) doesn't fit in 4 bits")]
+fn test_table_entry_too_big() {
+    let table = vec![
+        0xc, 0x5, 0x6, 0x1f, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2,
+    ];
+    Sbox::new(4, 4, table, 0);
+}
+
+#[test]
+fn test_algebraic_degree() {
+    use crate::targets::{miniaes4x4::MiniAES4x4, Cipher};
+    assert_eq!(MiniAES4x4::new(1).sbox().algebraic_degree(), 3);
+    assert_eq!(Sbox::new(4, 4, (0..16).collect(), 0).algebraic_degree(), 1);
+    // x0 * x1 on the first output bit
+    assert_eq!(Sbox::new(2, 1, vec![0, 0, 0, 1], 0).algebraic_degree(), 2);
+}
+
+#[test]
+fn test_anf_matches_the_table() {
+    use crate::targets::{miniaes4x4::MiniAES4x4, Cipher};
+    let sbox = MiniAES4x4::new(1).sbox();
+    let anf = sbox.anf();
+    assert_eq!(anf.len(), 4);
+    for (x, out_value) in sbox.table().iter().enumerate() {
+        let input: Vec<bool> = (0..4).map(|j| (x >> j) & 1 == 1).collect();
+        for (i, poly) in anf.iter().enumerate() {
+            assert_eq!(poly.evaluate(&input), (out_value >> i) & 1 == 1);
+        }
+    }
+    // x0 * x1 on the first output bit
+    let anf = Sbox::new(2, 1, vec![0, 0, 0, 1], 0).anf();
+    let monomials: Vec<Vec<usize>> = anf[0]
+        .monomials()
+        .map(|monomial| monomial.iter().cloned().collect())
+        .collect();
+    assert_eq!(monomials, vec![vec![0, 1]]);
+}
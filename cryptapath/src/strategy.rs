@@ -4,12 +4,64 @@
 
 use crush::{
     algebra,
+    error::CrushError,
     soc::{system::System, Id},
     solver::{Dependency, DroppingSolver, Independency, Solver},
 };
+use rayon::prelude::*;
 use std::cell::Cell;
-use std::io::Error;
+use std::fmt;
 use std::result::Result;
+use std::str::FromStr;
+
+/// How much the solvers report about the solving while it is running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogLevel {
+    /// Print nothing.
+    Quiet,
+    /// Print one line per resolved dependency (or dropped variable).
+    #[default]
+    Normal,
+    /// Print the full statistics block at each step.
+    Verbose,
+    /// Same as `Verbose` but clear the screen before each block.
+    Tui,
+    /// Print instead one line per resolution describing the dependency (or independency)
+    /// chosen: whether it was absorbed or dropped, the score given by `minimize_distance` and
+    /// the involved levels of each `Bdd`.
+    Explain,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<LogLevel, String> {
+        match s {
+            "quiet" => Ok(LogLevel::Quiet),
+            "normal" => Ok(LogLevel::Normal),
+            "verbose" => Ok(LogLevel::Verbose),
+            "tui" => Ok(LogLevel::Tui),
+            "explain" => Ok(LogLevel::Explain),
+            _ => Err(format!(
+                "unknown log level {}, expected quiet, normal, verbose, tui or explain",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LogLevel::Quiet => "quiet",
+            LogLevel::Normal => "normal",
+            LogLevel::Verbose => "verbose",
+            LogLevel::Tui => "tui",
+            LogLevel::Explain => "explain",
+        };
+        write!(f, "{}", name)
+    }
+}
 
 /// Describe the informations about a `Bdd` involved in a `NodeRankedDependency` or a `NodeRankedIndependency`.
 #[derive(Clone, Debug)]
@@ -56,6 +108,20 @@ impl InvolvedBdd {
     }
 }
 
+/// Print the line describing a resolution for `LogLevel::Explain`.
+fn explain_resolution(kind: &str, distance: usize, involved_bdds: &[InvolvedBdd]) {
+    let involved: Vec<String> = involved_bdds
+        .iter()
+        .map(|bdd| format!("bdd {} levels {:?}", bdd.get_id(), bdd.get_involved_levels()))
+        .collect();
+    println!(
+        "resolved {} : distance {}, {}",
+        kind,
+        distance,
+        involved.join(", ")
+    );
+}
+
 /// NodeRankedDependency impl the Dependency traits and for the function `minimize_distance`
 /// and `best_join_order` use the number of nodes involved in the depencdy as the metrics.
 /// The join order is chosen by the amount of nodes we avoid and the distance is the amount of nodes
@@ -300,6 +366,8 @@ pub struct UpwardSolver {
     remaining: usize,
     solved: usize,
     max_reached: Cell<usize>,
+    log_level: LogLevel,
+    last_reported: Cell<usize>,
 }
 
 impl UpwardSolver {
@@ -307,12 +375,32 @@ impl UpwardSolver {
         Default::default()
     }
 
-    pub fn improved_solve(&mut self, system: &mut System) -> Result<Vec<Vec<Option<bool>>>, Error> {
+    pub fn with_log_level(log_level: LogLevel) -> UpwardSolver {
+        UpwardSolver {
+            log_level,
+            ..Default::default()
+        }
+    }
+
+    /// The maximum number of nodes the system reached while solving.
+    pub fn max_reached(&self) -> usize {
+        self.max_reached.get()
+    }
+
+    pub fn improved_solve(&mut self, system: &mut System) -> Result<Vec<Vec<Option<bool>>>, CrushError> {
         Self::absorb_all_equations(system)?;
         let mut deps = NodeRankedDependency::extract(system);
         self.remaining = deps.len();
         while !deps.is_empty() {
             deps = find_best_bdd_pattern_dep(&deps);
+            if self.log_level == LogLevel::Explain {
+                // the dependency pick_best_dep resolves
+                let best = deps
+                    .iter()
+                    .min_by_key(|dep| dep.minimize_distance())
+                    .unwrap();
+                explain_resolution("absorb", best.minimize_distance(), &best.involved_bdds);
+            }
             Self::resolve(self, system, Self::pick_best_dep(deps))?;
             self.solved += 1;
             Self::feedback(self, system);
@@ -321,13 +409,36 @@ impl UpwardSolver {
             self.remaining = deps.len();
             Self::feedback(self, system);
         }
+        if self.log_level == LogLevel::Explain {
+            println!("solved dependencies {}", self.solved);
+        }
         Ok(system.get_solutions())
     }
 }
 
 impl Solver for UpwardSolver {
     fn feedback(&self, system: &System) {
-        print!("\x1Bc");
+        if system.get_size() > self.max_reached.get() {
+            self.max_reached.set(system.get_size());
+        }
+        match self.log_level {
+            LogLevel::Quiet | LogLevel::Explain => return,
+            LogLevel::Normal => {
+                if self.solved != self.last_reported.get() {
+                    self.last_reported.set(self.solved);
+                    println!(
+                        "solved dependencies {}, {} remaining, {} bdds, {} nodes",
+                        self.solved,
+                        self.remaining,
+                        system.iter_bdds().len(),
+                        system.get_size()
+                    );
+                }
+                return;
+            }
+            LogLevel::Verbose => (),
+            LogLevel::Tui => print!("\x1Bc"),
+        }
         println!(
             "{} bdds remaining\n{} total nodes remaining\ntotal linear equations found {}\nsolved dependencies {}, {} remaining",
             system.iter_bdds().len(),
@@ -336,20 +447,13 @@ impl Solver for UpwardSolver {
             self.solved,
             self.remaining,
         );
-        let max_size = system.iter_bdds().fold(0, |size, bdd| {
-            if bdd.1.borrow().get_size() > size {
-                (bdd.1.borrow().get_size())
-            } else {
-                size
-            }
-        });
+        let max_size = system
+            .bdd_sizes()
+            .iter()
+            .map(|(_, size)| *size)
+            .max()
+            .unwrap_or(0);
         println!("biggest bdd has {} nodes", max_size);
-        let total_nodes = system
-            .iter_bdds()
-            .fold(0, |acc, bdd| acc + bdd.1.borrow().get_size());
-        if total_nodes > self.max_reached.get() {
-            self.max_reached.set(total_nodes);
-        }
         println!(
             "max node reach 2**{}",
             (self.max_reached.get() as f64).log(2.0)
@@ -357,6 +461,74 @@ impl Solver for UpwardSolver {
     }
 }
 
+/// A `Solver` resolving first the dependencies confined to a single `Bdd`, which need no join,
+/// and among them (or among the others if there is none) the one with the lowest
+/// `minimize_distance`, as `UpwardSolver` does.
+///
+/// The progress is reported as by `UpwardSolver`.
+#[derive(Default)]
+pub struct SingleBddFirstSolver {
+    progress: UpwardSolver,
+}
+
+impl SingleBddFirstSolver {
+    pub fn new() -> SingleBddFirstSolver {
+        Default::default()
+    }
+
+    pub fn with_log_level(log_level: LogLevel) -> SingleBddFirstSolver {
+        SingleBddFirstSolver {
+            progress: UpwardSolver::with_log_level(log_level),
+        }
+    }
+
+    /// The maximum number of nodes the system reached while solving.
+    pub fn max_reached(&self) -> usize {
+        self.progress.max_reached()
+    }
+
+    pub fn improved_solve(&mut self, system: &mut System) -> Result<Vec<Vec<Option<bool>>>, CrushError> {
+        Self::absorb_all_equations(system)?;
+        let mut deps = NodeRankedDependency::extract(system);
+        self.progress.remaining = deps.len();
+        while !deps.is_empty() {
+            if self.progress.log_level == LogLevel::Explain {
+                let best = deps.iter().min_by_key(|dep| Self::rank(*dep)).unwrap();
+                explain_resolution("absorb", best.minimize_distance(), &best.involved_bdds);
+            }
+            Self::resolve(self, system, Self::pick_best_dep(deps))?;
+            self.progress.solved += 1;
+            Self::feedback(self, system);
+            Self::absorb_all_equations(system)?;
+            deps = NodeRankedDependency::extract(system);
+            self.progress.remaining = deps.len();
+            Self::feedback(self, system);
+        }
+        if self.progress.log_level == LogLevel::Explain {
+            println!("solved dependencies {}", self.progress.solved);
+        }
+        Ok(system.get_solutions())
+    }
+
+    /// The dependencies confined to a single `Bdd` come first, then the lowest distance.
+    fn rank<T: Dependency>(dep: &T) -> (bool, usize) {
+        (dep.best_join_order().0.len() != 1, dep.minimize_distance())
+    }
+}
+
+impl Solver for SingleBddFirstSolver {
+    fn pick_best_dep<T: Dependency>(deps: Vec<T>) -> (Vec<Id>, Vec<usize>) {
+        deps.iter()
+            .min_by_key(|dep| Self::rank(*dep))
+            .unwrap()
+            .best_join_order()
+    }
+
+    fn feedback(&self, system: &System) {
+        self.progress.feedback(system)
+    }
+}
+
 /// NodeRankedIndependency impl the Independency traits and for the function `minimize_distance`
 /// and `best_join_order` use the number of nodes involved in the independency as the metric.
 /// The join order is chosen by the amount of nodes we avoid and the distance is the amount of nodes
@@ -519,12 +691,28 @@ impl Independency for NodeRankedIndependency {
     }
 }
 
-#[derive(Default)]
 pub struct UpwardDroppingSolver {
     remaining: usize,
     solved: usize,
     dropped: usize,
     max_reached: Cell<usize>,
+    log_level: LogLevel,
+    last_reported: Cell<usize>,
+    drop_bias: f64,
+}
+
+impl Default for UpwardDroppingSolver {
+    fn default() -> Self {
+        UpwardDroppingSolver {
+            remaining: 0,
+            solved: 0,
+            dropped: 0,
+            max_reached: Cell::new(0),
+            log_level: LogLevel::default(),
+            last_reported: Cell::new(0),
+            drop_bias: 1.0,
+        }
+    }
 }
 
 impl UpwardDroppingSolver {
@@ -532,23 +720,54 @@ impl UpwardDroppingSolver {
         Default::default()
     }
 
+    pub fn with_log_level(log_level: LogLevel) -> UpwardDroppingSolver {
+        UpwardDroppingSolver {
+            log_level,
+            ..Default::default()
+        }
+    }
+
+    /// The maximum number of nodes the system reached while solving.
+    pub fn max_reached(&self) -> usize {
+        self.max_reached.get()
+    }
+
+    /// Set the factor applied to the distance of the best independency when choosing
+    /// between dropping and resolving: a variable is dropped when
+    /// `min_distance_indep * drop_bias < min_distance_dep`.
+    ///
+    /// A bias above 1.0 favors resolving (keeping more information), a bias below 1.0
+    /// favors dropping (keeping the BDDs smaller). Defaults to 1.0.
+    pub fn set_drop_bias(&mut self, drop_bias: f64) {
+        self.drop_bias = drop_bias;
+    }
+
     pub fn improved_solve(
         &mut self,
         system: &mut System,
         forbid_dropping: Option<&[usize]>,
-    ) -> Result<Vec<Vec<Option<bool>>>, Error> {
+    ) -> Result<Vec<Vec<Option<bool>>>, CrushError> {
         Self::absorb_all_equations(system)?;
         let mut deps = NodeRankedDependency::extract(system);
-        let mut indeps = NodeRankedIndependency::extract(system, forbid_dropping);
+        let protected = forbid_dropping.map(|vars| system.protected_variables(vars));
+        let mut indeps = NodeRankedIndependency::extract(system, protected.as_deref());
         self.remaining = deps.len();
         while !deps.is_empty() {
             deps = find_best_bdd_pattern_dep(&deps);
             let (id_dep, min_distance_dep) = Self::pick_best_dep(&deps);
             let (id_indep, min_distance_indep) = Self::pick_best_indep(&indeps);
-            if min_distance_indep < min_distance_dep {
+            if !indeps.is_empty()
+                && (min_distance_indep as f64) * self.drop_bias < min_distance_dep as f64
+            {
+                if self.log_level == LogLevel::Explain {
+                    explain_resolution("drop", min_distance_indep, &indeps[id_indep].involved_bdds);
+                }
                 Self::indep_resolver(self, system, indeps[id_indep].best_join_order())?;
                 self.dropped += 1;
             } else {
+                if self.log_level == LogLevel::Explain {
+                    explain_resolution("absorb", min_distance_dep, &deps[id_dep].involved_bdds);
+                }
                 Self::dep_resolver(self, system, deps[id_dep].best_join_order())?;
                 self.solved += 1;
             }
@@ -556,17 +775,45 @@ impl UpwardDroppingSolver {
             Self::feedback(self, system);
             Self::absorb_all_equations(system)?;
             deps = NodeRankedDependency::extract(system);
-            indeps = NodeRankedIndependency::extract(system, forbid_dropping);
+            let protected = forbid_dropping.map(|vars| system.protected_variables(vars));
+            indeps = NodeRankedIndependency::extract(system, protected.as_deref());
             self.remaining = deps.len();
             Self::feedback(self, system);
         }
+        if self.log_level == LogLevel::Explain {
+            println!(
+                "solved dependencies {}, dropped variables {}",
+                self.solved, self.dropped
+            );
+        }
         Ok(system.get_solutions())
     }
 }
 
 impl DroppingSolver for UpwardDroppingSolver {
     fn feedback(&self, system: &System) {
-        print!( "\x1Bc");
+        if system.get_size() > self.max_reached.get() {
+            self.max_reached.set(system.get_size());
+        }
+        match self.log_level {
+            LogLevel::Quiet | LogLevel::Explain => return,
+            LogLevel::Normal => {
+                if self.solved + self.dropped != self.last_reported.get() {
+                    self.last_reported.set(self.solved + self.dropped);
+                    println!(
+                        "solved dependencies {}, {} remaining, dropped variables {}, {} bdds, {} nodes",
+                        self.solved,
+                        self.remaining,
+                        self.dropped,
+                        system.iter_bdds().len(),
+                        system.get_size()
+                    );
+                }
+                return;
+            }
+            LogLevel::Verbose => (),
+            LogLevel::Tui => print!("\x1Bc"),
+        }
         println!(
             
             "{} bdds remaining\n{} total nodes remaining\ntotal linear equations found {}\nsolved dependencies {}, {} remaining\ndropped variables {}",
@@ -578,20 +825,13 @@ impl DroppingSolver for UpwardDroppingSolver {
             self.dropped
         )
         ;
-        let max_size = system.iter_bdds().fold(0, |size, bdd| {
-            if bdd.1.borrow().get_size() > size {
-                (bdd.1.borrow().get_size())
-            } else {
-                size
-            }
-        });
+        let max_size = system
+            .bdd_sizes()
+            .iter()
+            .map(|(_, size)| *size)
+            .max()
+            .unwrap_or(0);
         println!( "biggest bdd has {} nodes", max_size);
-        let total_nodes = system
-            .iter_bdds()
-            .fold(0, |acc, bdd| acc + bdd.1.borrow().get_size());
-        if total_nodes > self.max_reached.get() {
-            self.max_reached.set(total_nodes);
-        }
         println!(
             "max node reach 2**{}",
             (self.max_reached.get() as f64).log(2.0)
@@ -599,20 +839,556 @@ impl DroppingSolver for UpwardDroppingSolver {
     }
 }
 
+/// What the absorption of the linear equations readily found in a `System` left of it, see
+/// `absorb_only`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AbsorbReport {
+    /// The number of linear equations in the `LinBank`.
+    pub lin_eqs: usize,
+    /// The number of `Bdd`s remaining in the `System`.
+    pub bdds: usize,
+    /// The total number of nodes remaining in the `System`.
+    pub nodes: usize,
+}
+
+/// Absorb the linear equations readily found in the `Bdd`s of `system`, as every strategy does
+/// before resolving the first dependency, and report what is left of the `System`.
+///
+/// This gives an idea of how much free structure an instance has without solving it.
+pub fn absorb_only(system: &mut System) -> Result<AbsorbReport, CrushError> {
+    UpwardSolver::absorb_all_equations(system)?;
+    Ok(AbsorbReport {
+        lin_eqs: system.get_lin_bank_size(),
+        bdds: system.iter_bdds().len(),
+        nodes: system.get_size(),
+    })
+}
+
+/// The names accepted by `execute_strategy_by_name`.
+pub fn supported_strategies() -> &'static [&'static str] {
+    &["no_drop", "drop", "single_first"]
+}
+
+/// A one-line description of the strategy `name`, `None` if it is not supported (see
+/// `supported_strategies`).
+pub fn strategy_description(name: &str) -> Option<&'static str> {
+    match name {
+        "no_drop" => Some("resolve the closest linear dependency until none is left"),
+        "drop" => Some("as no_drop, but drop a variable when it is closer than any dependency"),
+        "single_first" => {
+            Some("as no_drop, but resolve first the dependencies inside a single bdd")
+        }
+        _ => None,
+    }
+}
+
+/// Run the strategy called `name` on `system`, reporting the progress according to `log_level`.
+///
+/// Returns `None` if no strategy of this name exists, otherwise the result of the solving
+/// (which is a `CrushError` if the solving was aborted, for example because the node budget
+/// of the `System` was exceeded).
 pub fn execute_strategy_by_name(
     name: &str,
     system: &mut System,
     forbid_dropping: Option<&[usize]>,
-) -> Option<Vec<Vec<Option<bool>>>> {
+    log_level: LogLevel,
+) -> Option<Result<Vec<Vec<Option<bool>>>, CrushError>> {
+    execute_strategy_by_name_with_peak(name, system, forbid_dropping, log_level)
+        .map(|(result, _)| result)
+}
+
+/// The result of a solving along with the maximum number of nodes reached.
+pub type SolvedWithPeak = (Result<Vec<Vec<Option<bool>>>, CrushError>, usize);
+
+/// Same as `execute_strategy_by_name`, also returning the maximum number of nodes reached by
+/// `system` during the solving.
+pub fn execute_strategy_by_name_with_peak(
+    name: &str,
+    system: &mut System,
+    forbid_dropping: Option<&[usize]>,
+    log_level: LogLevel,
+) -> Option<SolvedWithPeak> {
     match name {
         "no_drop" => {
-            let mut solver = UpwardSolver::new();
-            Some(solver.improved_solve(system).unwrap())
+            let mut solver = UpwardSolver::with_log_level(log_level);
+            let result = solver.improved_solve(system);
+            Some((result, solver.max_reached()))
         }
         "drop" => {
-            let mut solver = UpwardDroppingSolver::new();
-            Some(solver.improved_solve(system, forbid_dropping).unwrap())
+            let mut solver = UpwardDroppingSolver::with_log_level(log_level);
+            let result = solver.improved_solve(system, forbid_dropping);
+            Some((result, solver.max_reached()))
+        }
+        "single_first" => {
+            let mut solver = SingleBddFirstSolver::with_log_level(log_level);
+            let result = solver.improved_solve(system);
+            Some((result, solver.max_reached()))
         }
         _ => None,
     }
 }
+
+/// Run the strategy called `name` on each connected component of `system` (see
+/// `System::connected_components`) in parallel, on the threads of the rayon pool (see the
+/// `--threads` option), and combine the solutions of the components into the solutions of
+/// `system`.
+///
+/// The `Bdd`s of `system` are moved to the components, only its `LinBank` is kept. Returns `None`
+/// if no strategy of this name exists, otherwise the result of the solving (the first
+/// `CrushError` of the components if one of them was aborted).
+pub fn solve_components_parallel(
+    name: &str,
+    system: &mut System,
+    forbid_dropping: Option<&[usize]>,
+    log_level: LogLevel,
+) -> Option<Result<Vec<Vec<Option<bool>>>, CrushError>> {
+    if !supported_strategies().contains(&name) {
+        return None;
+    }
+    let mut components = Vec::new();
+    for ids in system.connected_components().iter() {
+        match system.split(ids) {
+            Ok(component) => components.push(component),
+            Err(e) => return Some(Err(e)),
+        }
+    }
+    let results: Vec<_> = components
+        .into_par_iter()
+        .map(|mut component| {
+            execute_strategy_by_name(name, &mut component, forbid_dropping, log_level).unwrap()
+        })
+        .collect();
+    let mut parts = Vec::with_capacity(results.len());
+    for result in results {
+        match result {
+            Ok(solutions) => parts.push(solutions),
+            Err(e) => return Some(Err(e)),
+        }
+    }
+    Some(Ok(system.combine_solutions(&parts)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crush::{
+        error::CrushError,
+        soc::{
+            system::{System, SystemOp},
+            utils::{build_system_from_spec, BddSpec, LevelSpec, NodeSpec, SystemSpec},
+            Id,
+        },
+        solver::{Dependency, Solver},
+    };
+    use crate::bit;
+    use crate::strategy::{
+        absorb_only, execute_strategy_by_name, solve_components_parallel, LogLevel,
+        NodeRankedDependency, SingleBddFirstSolver, UpwardDroppingSolver, UpwardSolver,
+    };
+    use crate::targets::{
+        build_system_cipher, build_system_sponge, fix_system_values_cipher,
+        fix_system_values_sponge, get_random_plaintext_ciphertext_key, keccak::Keccak,
+        miniaes2x2::MiniAES2x2, Cipher, SpongeHash,
+    };
+
+    #[test]
+    fn node_budget_aborts_solving() {
+        let cipher = MiniAES2x2::new(2);
+        let (layout, mut system) = build_system_cipher(&cipher);
+        let (plaintext, ciphertext, _) = get_random_plaintext_ciphertext_key(&cipher);
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        let biggest = system
+            .iter_bdds()
+            .map(|bdd| bdd.1.borrow().get_size())
+            .max()
+            .unwrap();
+        system.set_max_bdd_nodes(Some(biggest));
+        for name in ["no_drop", "drop"].iter() {
+            let err = execute_strategy_by_name(name, &mut system, None, LogLevel::Quiet)
+                .unwrap()
+                .unwrap_err();
+            assert_eq!(err, CrushError::NodeBudget);
+            assert!(system
+                .iter_bdds()
+                .all(|bdd| bdd.1.borrow().get_size() <= biggest));
+        }
+    }
+
+    #[test]
+    fn absorb_only_is_stable() {
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let ciphertext = bit::bits_from_hex_string_unchecked("b1497afd");
+        let report = || {
+            let cipher = MiniAES2x2::new(1);
+            let (layout, mut system) = build_system_cipher(&cipher);
+            fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+            let fixed = system.get_lin_bank_size();
+            let report = absorb_only(&mut system).unwrap();
+            assert!(report.lin_eqs >= fixed);
+            assert_eq!(report.bdds, system.iter_bdds().len());
+            report
+        };
+        let first = report();
+        assert!(first.bdds > 0);
+        assert!(first.nodes > 0);
+        for _ in 0..3 {
+            assert_eq!(report(), first);
+        }
+    }
+
+    #[test]
+    fn count_solutions_matches_an_enumeration() {
+        let cipher = MiniAES2x2::new(1);
+        let (layout, mut system) = build_system_cipher(&cipher);
+        let (plaintext, ciphertext, _) = get_random_plaintext_ciphertext_key(&cipher);
+        // leaving 8 bits of the ciphertext unknown gives far more than 21 solutions
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext[..24]);
+        execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        let (solutions, truncated) = system.get_solutions_bounded(usize::MAX).unwrap();
+        assert!(!truncated);
+        // each solution stands for every value of its undetermined variables
+        let exact: u128 = solutions
+            .iter()
+            .map(|solution| 1 << solution.iter().filter(|bit| bit.is_none()).count())
+            .sum();
+        assert!(exact > 21);
+        assert_eq!(system.count_solutions().unwrap(), exact);
+    }
+
+    #[test]
+    fn solve_system_from_anf() {
+        // the only solution is x0 = 0, x1 = 1, x2 = 1
+        let equations: Vec<Vec<Vec<usize>>> = vec![
+            vec![vec![0, 1], vec![2], vec![]],
+            vec![vec![0], vec![1, 2], vec![1]],
+            vec![vec![0, 2], vec![0], vec![1], vec![]],
+        ];
+        let anf = "x0*x1 + x2 + 1\nx0 + x1*x2 + x1\nx0*x2 + x0 + x1 + 1";
+        let mut system = System::from_anf(anf).unwrap();
+        let solutions = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        assert_eq!(solutions.len(), 1);
+        let solution: Vec<bool> = solutions[0].iter().map(|var| var.unwrap()).collect();
+        assert_eq!(solution[..3], [false, true, true]);
+        for equation in equations.iter() {
+            let value = equation.iter().fold(false, |value, monomial| {
+                value ^ monomial.iter().all(|var| solution[*var])
+            });
+            assert!(!value);
+        }
+    }
+
+    #[test]
+    fn higher_drop_bias_drops_less() {
+        let cipher = MiniAES2x2::new(1);
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let plaintext = bit::bits_from_hex_string_unchecked("9c6904e1");
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        let forbid_dropping: Vec<usize> = (0..cipher.key_length()).collect();
+        let mut dropped = Vec::new();
+        for bias in [1.0, 2.0, 5.0].iter() {
+            // a fresh cipher, as its S-Box keeps numbering the variables from the last build
+            let cipher = MiniAES2x2::new(1);
+            let (layout, mut system) = build_system_cipher(&cipher);
+            fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+            let mut solver = UpwardDroppingSolver::with_log_level(LogLevel::Quiet);
+            solver.set_drop_bias(*bias);
+            let solutions = solver
+                .improved_solve(&mut system, Some(&forbid_dropping))
+                .unwrap();
+            assert!(solutions.iter().any(|solution| solution
+                .iter()
+                .take(cipher.key_length())
+                .zip(key.iter())
+                .all(|(var, bit)| *var == Some(bit.constant()))));
+            dropped.push(solver.dropped);
+        }
+        assert!(dropped[0] > dropped[1]);
+        assert!(dropped[1] > dropped[2]);
+    }
+
+    #[test]
+    fn fix_equal_key_bits() {
+        let cipher = MiniAES2x2::new(1);
+        // the first two bits of the key are both 1
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let plaintext = bit::bits_from_hex_string_unchecked("9c6904e1");
+        let ciphertext = cipher.encrypt(plaintext.clone(), key);
+        let (layout, mut system) = build_system_cipher(&cipher);
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        system.fix_equal(0, 1).unwrap();
+        let solutions = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        assert!(!solutions.is_empty());
+        assert!(solutions
+            .iter()
+            .all(|solution| solution[0].is_some() && solution[0] == solution[1]));
+    }
+
+    #[test]
+    fn solve_systems_in_threads() {
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let handles: Vec<_> = ["9c6904e1", "0123abcd"]
+            .iter()
+            .map(|plaintext| {
+                let cipher = MiniAES2x2::new(1);
+                let plaintext = bit::bits_from_hex_string_unchecked(plaintext);
+                let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+                let (layout, mut system) = build_system_cipher(&cipher);
+                fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+                std::thread::spawn(move || {
+                    execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+                        .unwrap()
+                        .unwrap()
+                })
+            })
+            .collect();
+        for handle in handles {
+            let solutions = handle.join().unwrap();
+            assert!(solutions.iter().any(|solution| solution
+                .iter()
+                .zip(key.iter())
+                .all(|(var, bit)| *var == Some(bit.constant()))));
+        }
+    }
+
+    #[test]
+    fn solve_components_in_threads() {
+        // two subproblems sharing no variable, x6 and x7 standing for x0*x1 and x3*x4
+        let anf = "x0*x1 + x2
+            x0 + x1 + x2 + 1
+            x3*x4 + x5 + 1
+            x3 + x5";
+        let mut system = System::from_anf(anf).unwrap();
+        assert_eq!(system.connected_components().len(), 2);
+        let mut parallel =
+            solve_components_parallel("no_drop", &mut system, None, LogLevel::Quiet)
+                .unwrap()
+                .unwrap();
+        assert_eq!(system.iter_bdds().len(), 0);
+        let mut system = System::from_anf(anf).unwrap();
+        let mut serial = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        parallel.sort();
+        serial.sort();
+        assert!(!serial.is_empty());
+        assert_eq!(parallel, serial);
+        assert!(solve_components_parallel("fast", &mut system, None, LogLevel::Quiet).is_none());
+    }
+
+    #[test]
+    fn single_thread_pool_gives_the_same_solutions() {
+        let anf = "x0*x1 + x2
+            x0 + x1 + x2 + 1
+            x3*x4 + x5 + 1
+            x3 + x5";
+        let solve = || {
+            let mut system = System::from_anf(anf).unwrap();
+            let mut solutions =
+                solve_components_parallel("no_drop", &mut system, None, LogLevel::Quiet)
+                    .unwrap()
+                    .unwrap();
+            solutions.sort();
+            solutions
+        };
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let serial = pool.install(|| {
+            assert_eq!(rayon::current_num_threads(), 1);
+            solve()
+        });
+        assert!(!serial.is_empty());
+        assert_eq!(serial, solve());
+    }
+
+    #[test]
+    fn drop_variable_keeps_the_key_solutions() {
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let key_solutions = |drop: bool| {
+            let cipher = MiniAES2x2::new(1);
+            let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+            let (layout, mut system) = build_system_cipher(&cipher);
+            fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+            if drop {
+                // the first variable produced by the S-Boxes
+                system
+                    .drop_variable(cipher.key_length() + cipher.message_length())
+                    .unwrap();
+            }
+            let mut keys: Vec<Vec<Option<bool>>> =
+                execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+                    .unwrap()
+                    .unwrap()
+                    .into_iter()
+                    .map(|mut solution| {
+                        solution.truncate(cipher.key_length());
+                        solution
+                    })
+                    .collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(key_solutions(false), key_solutions(true));
+    }
+
+    #[test]
+    fn forbidden_variables_stay_determined() {
+        // two rounds over a 25 bits state, the solver drops about 30 variables
+        let k = Keccak::new(2, 10, 10, 10, 15);
+        // the last bit is the padding, always 1
+        let message = bit::bits_from_binary_string_unchecked("1110001011");
+        let image = k.hash(message.clone());
+        let (output, mut system) = build_system_sponge(&k);
+        fix_system_values_sponge(&k, &mut system, &image, &output).unwrap();
+        let forbid_dropping: Vec<usize> = (0..k.message_length()).collect();
+        let mut solver = UpwardDroppingSolver::with_log_level(LogLevel::Quiet);
+        let solutions = solver
+            .improved_solve(&mut system, Some(&forbid_dropping))
+            .unwrap();
+        assert!(solver.dropped > 0);
+        let preimages: Vec<Vec<bit::Bit>> = solutions
+            .iter()
+            .map(|solution| {
+                solution[..k.message_length()]
+                    .iter()
+                    .map(|var| bit!(var.expect("a forbidden variable was dropped")))
+                    .collect()
+            })
+            .collect();
+        assert!(preimages.contains(&message));
+        for preimage in preimages {
+            assert_eq!(k.hash(preimage), image);
+        }
+    }
+
+    #[test]
+    fn reduce_once_until_no_dependency_is_left() {
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let cipher = MiniAES2x2::new(1);
+        let ciphertext = cipher.encrypt(plaintext.clone(), key);
+        let (layout, mut system) = build_system_cipher(&cipher);
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        let mut stepped = system.clone();
+        let mut expected = UpwardSolver::with_log_level(LogLevel::Quiet)
+            .improved_solve(&mut system)
+            .unwrap();
+        let mut steps = 0;
+        while let Some(report) = stepped.reduce_once::<NodeRankedDependency>().unwrap() {
+            assert!(!report.joined.is_empty());
+            steps += 1;
+        }
+        assert!(steps > 0);
+        let mut solutions = stepped.get_solutions();
+        solutions.sort();
+        expected.sort();
+        assert!(!expected.is_empty());
+        assert_eq!(solutions, expected);
+    }
+
+    #[test]
+    fn replaying_the_op_log_of_a_solve() {
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let cipher = MiniAES2x2::new(1);
+        let ciphertext = cipher.encrypt(plaintext.clone(), key);
+        let (layout, mut system) = build_system_cipher(&cipher);
+        system.set_op_recording(true);
+        let initial = system.clone();
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        execute_strategy_by_name("drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        assert!(!system.op_log().is_empty());
+        let replayed = System::replay(system.op_log(), initial).unwrap();
+        assert_eq!(replayed, system);
+    }
+
+    #[test]
+    fn system_is_solved_once_no_dependency_is_left() {
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let cipher = MiniAES2x2::new(1);
+        let ciphertext = cipher.encrypt(plaintext.clone(), key);
+        let (layout, mut system) = build_system_cipher(&cipher);
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        assert!(!system.is_solved());
+        assert!(!NodeRankedDependency::extract(&system).is_empty());
+        execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        assert!(system.is_solved());
+        assert!(NodeRankedDependency::extract(&system).is_empty());
+    }
+
+    /// A `System` of 3 `Bdd`s: the first one has a level x0 + x1 below the levels x0, x1 and x2
+    /// (a dependency confined to it, away from its top), the two others both start with x4 (a
+    /// dependency between them, closer) and ensure x5 = x4 and x6 = x4.
+    fn single_and_cross_dependencies() -> System {
+        let node = |id, e0, e1| NodeSpec::new(Id::new(id), Id::new(e0), Id::new(e1));
+        let level = |lhs: Vec<i64>, nodes| LevelSpec::new(lhs, nodes);
+        let single = BddSpec::new(
+            Id::new(0),
+            vec![
+                level(vec![0], vec![node(1, 2, 3)]),
+                level(vec![1], vec![node(2, 4, 5), node(3, 5, 4)]),
+                level(vec![2], vec![node(4, 6, 6), node(5, 7, 7)]),
+                level(vec![0, 1], vec![node(6, 8, 0), node(7, 0, 8)]),
+                level(vec![], vec![node(8, 0, 0)]),
+            ],
+        );
+        let cross = |id, var| {
+            BddSpec::new(
+                Id::new(id),
+                vec![
+                    level(vec![4], vec![node(1, 2, 3)]),
+                    level(vec![var], vec![node(2, 4, 0), node(3, 0, 4)]),
+                    level(vec![], vec![node(4, 0, 0)]),
+                ],
+            )
+        };
+        build_system_from_spec(SystemSpec::new(7, vec![single, cross(1, 5), cross(2, 6)]))
+    }
+
+    #[test]
+    fn single_bdd_dependency_is_resolved_first() {
+        let mut system = single_and_cross_dependencies();
+        let deps = NodeRankedDependency::extract(&system);
+        assert!(UpwardSolver::pick_best_dep(deps.clone()).0.len() > 1);
+        assert_eq!(SingleBddFirstSolver::pick_best_dep(deps).0, vec![Id::new(0)]);
+
+        let mut expected = system.clone();
+        let mut expected = execute_strategy_by_name("no_drop", &mut expected, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        system.set_op_recording(true);
+        let mut solutions =
+            execute_strategy_by_name("single_first", &mut system, None, LogLevel::Quiet)
+                .unwrap()
+                .unwrap();
+        // the first resolution needs no join
+        let first_join = system
+            .op_log()
+            .iter()
+            .position(|op| matches!(op, SystemOp::JoinBdds { .. }))
+            .unwrap();
+        let first_absorb = system
+            .op_log()
+            .iter()
+            .position(|op| matches!(op, SystemOp::Absorb { bdd_id, .. } if *bdd_id == Id::new(0)))
+            .unwrap();
+        assert!(first_absorb < first_join);
+        solutions.sort();
+        expected.sort();
+        assert_eq!(solutions, expected);
+        for solution in solutions.iter() {
+            let x: Vec<bool> = solution.iter().map(|var| var.unwrap_or(false)).collect();
+            assert_eq!(x[5], x[4]);
+            assert_eq!(x[6], x[4]);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::bit::{Bit, *};
 use crate::sbox::Sbox;
-use crate::targets::Cipher;
+use crate::targets::{Cipher, RoundProfile};
 use std::cell::RefCell;
 
 pub struct DES {
@@ -14,7 +14,16 @@ pub struct DES {
 }
 
 impl DES {
+    /// The number of rounds of the specification, and the most this implementation supports.
+    pub const MAX_ROUNDS: usize = 16;
+
     pub fn new(n_rounds: usize) -> Self {
+        assert!(
+            n_rounds <= Self::MAX_ROUNDS,
+            "des has at most {} rounds, got {}",
+            Self::MAX_ROUNDS,
+            n_rounds
+        );
         let sbox_tables = vec![
             vec![
                 0xe, 0x0, 0x4, 0xf, 0xd, 0x7, 0x1, 0x4, 0x2, 0xe, 0xf, 0x2, 0xb, 0xd, 0x8, 0x1,
@@ -226,6 +235,10 @@ impl Cipher for DES {
         self.final_permutation(out_bits)
     }
 
+    fn round_keys(&self, key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        Some(self.make_round_keys(key))
+    }
+
     fn message_length(&self) -> usize {
         self.message_length
     }
@@ -238,9 +251,22 @@ impl Cipher for DES {
         self.n_rounds
     }
 
+    fn full_rounds(&self) -> usize {
+        Self::MAX_ROUNDS
+    }
+
     fn sbox(&self) -> Sbox {
         self.sbox.borrow().clone()
     }
+
+    /// A round of DES goes through the 8 S-Boxes and computes half of the state.
+    fn equation_profile(&self) -> Vec<RoundProfile> {
+        let profile = RoundProfile {
+            sbox_relations: 8,
+            linear_equations: self.message_length / 2,
+        };
+        vec![profile; self.n_rounds]
+    }
 }
 
 // from https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nbsspecialpublication500-20e1980.pdf
@@ -251,18 +277,18 @@ mod test {
     #[test]
     fn validate_encrypt() {
         let des = DES::new(16);
-        let message = bit::bits_from_hex_string("95f8a5e5dd31d900");
-        let key = bit::bits_from_hex_string("0101010101010101");
+        let message = bit::bits_from_hex_string_unchecked("95f8a5e5dd31d900");
+        let key = bit::bits_from_hex_string_unchecked("0101010101010101");
         let ciphertext = des.encrypt(message, key);
         assert_eq!("8000000000000000", bit::bits_to_hex_string(ciphertext));
 
-        let message = bit::bits_from_hex_string("0000000000000000");
-        let key = bit::bits_from_hex_string("1007103489988020");
+        let message = bit::bits_from_hex_string_unchecked("0000000000000000");
+        let key = bit::bits_from_hex_string_unchecked("1007103489988020");
         let ciphertext = des.encrypt(message, key);
         assert_eq!("0c0cc00c83ea48fd", bit::bits_to_hex_string(ciphertext));
 
-        let message = bit::bits_from_hex_string("42fd443059577fa2");
-        let key = bit::bits_from_hex_string("04b915ba43feb5b6");
+        let message = bit::bits_from_hex_string_unchecked("42fd443059577fa2");
+        let key = bit::bits_from_hex_string_unchecked("04b915ba43feb5b6");
         let ciphertext = des.encrypt(message, key);
         assert_eq!("af37fb421f8c4095", bit::bits_to_hex_string(ciphertext));
     }
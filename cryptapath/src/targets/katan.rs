@@ -0,0 +1,198 @@
+//! KATAN32, the 32-bit block member of the KATAN family of NLFSR-based block ciphers.
+//!
+//! The state is split into two registers L1 (13 bits) and L2 (19 bits). Each round computes
+//! a nonlinear function of each register, made of XORs and ANDs of some of its bits and of
+//! two bits of the linear key schedule, shifts both registers and feeds the function of L1
+//! into L2 and the function of L2 into L1. There is no S-Box, the ANDs of non constant bits
+//! go through a 2-bit AND gate modelled as a S-Box whose output is a new variable (see
+//! `Sbox::apply`), so each round brings at most 3 new variables and 3 BDDs.
+
+use crate::bit::Bit;
+use crate::sbox::Sbox;
+use crate::targets::{Cipher, RoundProfile};
+
+pub struct Katan32 {
+    n_rounds: usize,
+    message_length: usize,
+    key_length: usize,
+    irregular_update: Vec<bool>,
+    and_gate: Sbox,
+}
+
+impl Katan32 {
+    /// The number of rounds of the specification, and the most this implementation supports.
+    pub const MAX_ROUNDS: usize = 254;
+
+    pub fn new(n_rounds: usize) -> Self {
+        assert!(
+            n_rounds <= Self::MAX_ROUNDS,
+            "katan32 has at most {} rounds, got {}",
+            Self::MAX_ROUNDS,
+            n_rounds
+        );
+        let message_length = 32;
+        let key_length = 80;
+        Katan32 {
+            n_rounds,
+            message_length,
+            key_length,
+            irregular_update: irregular_update(Self::MAX_ROUNDS),
+            and_gate: Sbox::new(2, 1, vec![0, 0, 0, 1], message_length + key_length),
+        }
+    }
+
+    /// The AND of `a` and `b`, a new variable related to them by a BDD if neither of them is
+    /// constant.
+    fn and(&self, a: &Bit, b: &Bit) -> Bit {
+        match (a.is_constant(), b.is_constant()) {
+            (Some(false), _) | (_, Some(false)) => bit!(false),
+            (Some(true), _) => b.clone(),
+            (_, Some(true)) => a.clone(),
+            (None, None) => self.and_gate.apply(vec![a.clone(), b.clone()]).remove(0),
+        }
+    }
+
+    /// The key bits (k_a, k_b) of each round. The first 80 bits of the schedule are the bits of
+    /// the key, the next ones are given by the LFSR
+    /// k_i = k_(i-80) + k_(i-61) + k_(i-50) + k_(i-13).
+    fn make_round_keys(&self, key: Vec<Bit>) -> Vec<Vec<Bit>> {
+        assert_eq!(key.len(), self.key_length);
+        // the bit i of the key is its i-th least significant bit
+        let mut schedule: Vec<Bit> = key.into_iter().rev().collect();
+        for i in self.key_length..2 * self.n_rounds {
+            let bit = schedule[i - 80].clone()
+                ^ schedule[i - 61].clone()
+                ^ schedule[i - 50].clone()
+                ^ schedule[i - 13].clone();
+            schedule.push(bit);
+        }
+        schedule
+            .chunks(2)
+            .take(self.n_rounds)
+            .map(|keys| keys.to_vec())
+            .collect()
+    }
+}
+
+/// The irregular update sequence IR of the `n_rounds` first rounds, given by the LFSR
+/// a_(i+8) = a_i + a_(i+1) + a_(i+3) + a_(i+5) started from 8 ones, IR_i being a_(i+1).
+fn irregular_update(n_rounds: usize) -> Vec<bool> {
+    let mut lfsr = vec![true; 8];
+    while lfsr.len() < n_rounds + 1 {
+        let i = lfsr.len() - 8;
+        lfsr.push(lfsr[i] ^ lfsr[i + 1] ^ lfsr[i + 3] ^ lfsr[i + 5]);
+    }
+    lfsr[1..=n_rounds].to_vec()
+}
+
+impl Cipher for Katan32 {
+    fn encrypt(&self, in_bits: Vec<Bit>, key_bits: Vec<Bit>) -> Vec<Bit> {
+        assert_eq!(in_bits.len(), self.message_length);
+        let round_keys = self.make_round_keys(key_bits);
+        // the bit i of a register is the bit 19 + i (L1) or i (L2) of the plaintext, counting
+        // from its least significant bit
+        let mut state: Vec<Bit> = in_bits.into_iter().rev().collect();
+        let mut l1 = state.split_off(19);
+        let mut l2 = state;
+        for (round, keys) in round_keys.iter().enumerate() {
+            let mut fa = l1[12].clone() ^ l1[7].clone() ^ self.and(&l1[8], &l1[5]);
+            if self.irregular_update[round] {
+                fa ^= l1[3].clone();
+            }
+            fa ^= keys[0].clone();
+            let fb = l2[18].clone()
+                ^ l2[7].clone()
+                ^ self.and(&l2[12], &l2[10])
+                ^ self.and(&l2[8], &l2[3])
+                ^ keys[1].clone();
+            l1.pop();
+            l1.insert(0, fb);
+            l2.pop();
+            l2.insert(0, fa);
+        }
+        l2.append(&mut l1);
+        l2.into_iter().rev().collect()
+    }
+
+    fn round_keys(&self, key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        Some(self.make_round_keys(key))
+    }
+
+    fn message_length(&self) -> usize {
+        self.message_length
+    }
+
+    fn key_length(&self) -> usize {
+        self.key_length
+    }
+
+    fn n_rounds(&self) -> usize {
+        self.n_rounds
+    }
+
+    fn full_rounds(&self) -> usize {
+        Self::MAX_ROUNDS
+    }
+
+    fn sbox(&self) -> Sbox {
+        self.and_gate.clone()
+    }
+
+    /// A round goes through 3 AND gates and computes 2 bits of the state.
+    fn equation_profile(&self) -> Vec<RoundProfile> {
+        let profile = RoundProfile {
+            sbox_relations: 3,
+            linear_equations: 2,
+        };
+        vec![profile; self.n_rounds]
+    }
+}
+
+// from https://www.iacr.org/archive/ches2009/57470273/57470273.pdf
+#[cfg(test)]
+mod test {
+    use crate::bit;
+    use crate::strategy::{execute_strategy_by_name, LogLevel};
+    use crate::targets::{
+        build_system_cipher, fix_system_values_cipher, katan::Katan32, Cipher,
+    };
+
+    #[test]
+    fn validate_encrypt() {
+        let katan = Katan32::new(254);
+        let message = bit::bits_from_hex_string_unchecked("00000000");
+        let key = bit::bits_from_hex_string_unchecked("ffffffffffffffffffff");
+        let ciphertext = katan.encrypt(message, key);
+        assert_eq!("7e1ff945", bit::bits_to_hex_string(ciphertext));
+
+        let message = bit::bits_from_hex_string_unchecked("ffffffff");
+        let key = bit::bits_from_hex_string_unchecked("00000000000000000000");
+        let ciphertext = katan.encrypt(message, key);
+        assert_eq!("432e61da", bit::bits_to_hex_string(ciphertext));
+    }
+
+    #[test]
+    fn reduced_rounds_key_recovery() {
+        let cipher = Katan32::new(16);
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550e3a1f08b5d27");
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        let (layout, mut system) = build_system_cipher(&cipher);
+        // after 16 rounds the 3 last bits of L2 still are bits of the plaintext, their
+        // equations are redundant
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        assert_eq!(system.get_lin_bank_size(), 61);
+        let solutions = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        // 16 rounds only use the 32 least significant bits of the key, the others stay free
+        let expected: Vec<Option<bool>> = key
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| if i < 48 { None } else { Some(bit.constant()) })
+            .collect();
+        assert!(solutions
+            .iter()
+            .any(|solution| solution[layout.key_vars.clone()] == expected[..]));
+    }
+}
@@ -1,6 +1,35 @@
 use crate::sbox::Sbox;
 use crate::targets::SpongeHash;
-use crate::{bit, bit::Bit};
+use crate::{bit, bit::Bit, bit::BitOrder};
+
+/// The 24 round constants of the iota step of Keccak-f[1600], from the Keccak reference.
+/// A smaller permutation of lanes of `w` bits uses the `w` lowest bits of each constant.
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
 
 pub struct Keccak {
     n_rounds: usize,
@@ -9,6 +38,7 @@ pub struct Keccak {
     rate: usize,
     capacity: usize,
     chi_sbox: Sbox,
+    initial_state: Vec<Bit>,
 }
 
 impl Keccak {
@@ -19,6 +49,38 @@ impl Keccak {
         rate: usize,
         capacity: usize,
     ) -> Self {
+        Self::with_initial_state(
+            n_rounds,
+            message_length,
+            output_length,
+            rate,
+            capacity,
+            vec![bit!(false); rate + capacity],
+        )
+    }
+
+    /// Build a Keccak instance whose state starts from the constant bits of `initial_state`
+    /// (an IV, or a domain separation in the capacity) instead of all zeros.
+    ///
+    /// The bits are in the order of the internal state, bit z of the lane (x, y) being at
+    /// the index x + 5 * y + 25 * z.
+    pub fn with_initial_state(
+        n_rounds: usize,
+        message_length: usize,
+        output_length: usize,
+        rate: usize,
+        capacity: usize,
+        initial_state: Vec<Bit>,
+    ) -> Self {
+        assert_eq!(
+            initial_state.len(),
+            rate + capacity,
+            "the initial state should be as long as the state"
+        );
+        assert!(
+            initial_state.iter().all(|bit| bit.is_constant().is_some()),
+            "the initial state should be made of constant bits"
+        );
         let table = vec![
             0x00, 0x05, 0x0a, 0x0b, 0x14, 0x11, 0x16, 0x17, 0x09, 0x0c, 0x03, 0x02, 0x0d, 0x08,
             0x0f, 0x0e, 0x12, 0x15, 0x18, 0x1b, 0x06, 0x01, 0x04, 0x07, 0x1a, 0x1d, 0x10, 0x13,
@@ -32,6 +94,29 @@ impl Keccak {
             rate,
             capacity,
             chi_sbox: Sbox::new(5, 5, table, message_length),
+            initial_state,
+        }
+    }
+
+    /// The number of presets of `crunchy`.
+    pub const CRUNCHY_LEVELS: usize = 3;
+
+    /// Build the instance of a challenge of the Keccak crunchy contest with a published
+    /// solution, from the easiest (level 1) to the hardest:
+    ///
+    /// 1. preimage of Keccak[r=40, c=160, nr=1], 120 bits of message, 80 bits of output
+    /// 2. preimage of Keccak[r=1440, c=160, nr=2], 1440 bits of message, 80 bits of output
+    /// 3. collision of Keccak[r=640, c=160, nr=5], 640 bits of message, 160 bits of output
+    pub fn crunchy(level: usize) -> Self {
+        match level {
+            1 => Self::new(1, 120, 80, 40, 160),
+            2 => Self::new(2, 1440, 80, 1440, 160),
+            3 => Self::new(5, 640, 160, 640, 160),
+            _ => panic!(
+                "there is no crunchy contest preset {}, the levels go from 1 to {}",
+                level,
+                Self::CRUNCHY_LEVELS
+            ),
         }
     }
 
@@ -192,6 +277,69 @@ impl Keccak {
         }
         in_bits
     }
+
+    /// Apply the permutation as `keccak_permutation` does and return the state after each
+    /// step of each round, named after the step: 5 states per round, in the order theta, rho,
+    /// pi, chi and iota (the last one being the output of the permutation).
+    pub fn keccak_permutation_trace(
+        &self,
+        mut in_bits: Vec<Bit>,
+    ) -> Vec<(&'static str, Vec<Bit>)> {
+        let mut trace = Vec::with_capacity(5 * self.n_rounds);
+        for round_index in 0..self.n_rounds {
+            in_bits = self.theta(in_bits);
+            trace.push(("theta", in_bits.clone()));
+            in_bits = self.rho(in_bits);
+            trace.push(("rho", in_bits.clone()));
+            in_bits = self.pi(in_bits);
+            trace.push(("pi", in_bits.clone()));
+            in_bits = self.chi(in_bits);
+            trace.push(("chi", in_bits.clone()));
+            in_bits = self.iota(in_bits, round_index);
+            trace.push(("iota", in_bits.clone()));
+        }
+        trace
+    }
+
+    /// Render a state of 25 lanes as 5 lines, one per row y, giving the lanes x = 0 to 4 of the
+    /// row in hexadecimal (ex: `y = 0 : e9 f5 7f 02 a9` for lanes of 8 bits).
+    ///
+    /// The bit z of a lane is the coefficient of 2^z of its value, as in the round constants.
+    /// A hex digit with a bit which is not a constant is shown as '?'.
+    ///
+    /// Panics if the length of `bits` is not a multiple of 25.
+    pub fn debug_state(bits: &[Bit]) -> String {
+        assert_eq!(
+            bits.len() % 25,
+            0,
+            "a state of {} bits is not made of 25 lanes",
+            bits.len()
+        );
+        let w = bits.len() / 25;
+        let digits = w.div_ceil(4);
+        let mut out = String::new();
+        for y in 0..5 {
+            let lanes: Vec<String> = (0..5)
+                .map(|x| {
+                    (0..digits)
+                        .rev()
+                        .map(|digit| {
+                            (digit * 4..(digit * 4 + 4).min(w))
+                                .rev()
+                                .try_fold(0, |value, z| {
+                                    bits[x + y * 5 + z * 25]
+                                        .is_constant()
+                                        .map(|b| value * 2 + b as u32)
+                                })
+                                .map_or('?', |value| std::char::from_digit(value, 16).unwrap())
+                        })
+                        .collect()
+                })
+                .collect();
+            out.push_str(&format!("y = {} : {}\n", y, lanes.join(" ")));
+        }
+        out
+    }
 }
 
 impl SpongeHash for Keccak {
@@ -203,7 +351,7 @@ impl SpongeHash for Keccak {
             message_shards.push(message_bits);
             message_bits = tmp;
         }
-        let mut state: Vec<Bit> = vec![bit!(false); self.state_length()];
+        let mut state = self.initial_state();
         let w = state.len() / 25;
         for shard in message_shards.iter() {
             'xor: for y in 0..5 {
@@ -260,6 +408,27 @@ impl SpongeHash for Keccak {
     fn sbox(&self) -> Sbox {
         self.chi_sbox.clone()
     }
+
+    fn initial_state(&self) -> Vec<Bit> {
+        self.initial_state.clone()
+    }
+
+    /// Apply iota to the zero state for each round covered by the reference and compare the
+    /// first lane to the round constant.
+    fn verify_round_constants(&self) -> bool {
+        let w = self.state_length() / 25;
+        (0..ROUND_CONSTANTS.len()).all(|round_index| {
+            let state = self.iota(vec![bit!(false); self.state_length()], round_index);
+            (0..w).all(|z| {
+                state[z * 25].is_constant() == Some((ROUND_CONSTANTS[round_index] >> z) & 1 == 1)
+            })
+        })
+    }
+
+    /// The Keccak reference gives the bits of each byte least significant bit first.
+    fn hex_bit_order(&self) -> BitOrder {
+        BitOrder::Lsb0
+    }
 }
 
 pub fn bits_from_hex_string_keccak(h_str: &str) -> Vec<Bit> {
@@ -294,7 +463,7 @@ pub fn bits_from_hex_string_keccak(h_str: &str) -> Vec<Bit> {
             .as_str(),
         )
     }
-    bit::bits_from_binary_string(&b_str)
+    bit::bits_from_binary_string_unchecked(&b_str)
 }
 
 pub fn bits_to_hex_string_keccak(bits: Vec<Bit>) -> String {
@@ -324,14 +493,17 @@ pub fn bits_to_hex_string_keccak(bits: Vec<Bit>) -> String {
 #[cfg(test)]
 mod test {
     use crate::bit;
+    use crate::strategy::{execute_strategy_by_name, LogLevel};
     use crate::targets::{
+        build_sponge_by_name, build_system_sponge, fix_system_values_sponge_partial,
+        fix_system_values_sponge_with_partial_preimage,
         keccak::{Keccak, *},
-        SpongeHash,
+        supported_sponges, SpongeHash,
     };
 
     #[test]
     fn test_hex() {
-        let expected_bits = bit::bits_from_binary_string("100000000000000000000000000000000000000000011010100110100111000010011010111111011000001001111001100110001000000101101101");
+        let expected_bits = bit::bits_from_binary_string_unchecked("100000000000000000000000000000000000000000011010100110100111000010011010111111011000001001111001100110001000000101101101");
         let bits = bits_from_hex_string_keccak(
             "\\x01\\x00\\x00\\x00\\x00\\x58\\x59\\x0e\\x59\\xbf\\x41\\x9e\\x19\\x81\\xb6",
         );
@@ -371,8 +543,8 @@ mod test {
 
     #[test]
     fn validate_hashing() {
-        let mut message_bits = bit::bits_from_binary_string("100000000000000000000000000000000000000000011010100110100111000010011010111111011000001001111001100110001000000101101");
-        let k = Keccak::new(1, 120, 80, 40, 160);
+        let mut message_bits = bit::bits_from_binary_string_unchecked("100000000000000000000000000000000000000000011010100110100111000010011010111111011000001001111001100110001000000101101");
+        let k = Keccak::crunchy(1);
         k.add_padding(&mut message_bits);
         let hash = k.hash(message_bits);
         let hex_hash = bits_to_hex_string_keccak(hash);
@@ -380,26 +552,163 @@ mod test {
         assert_eq!(hex_hash, expected_hash);
 
         let message_bits = bits_from_hex_string_keccak("\\x11\\xFE\\x35\\xC8\\x5C\\x41\\x5B\\x35\\xF6\\x11\\xBC\\x40\\xD5\\x5E\\xCA\\x16\\xBA\\x51\\x98\\xFA\\x6C\\x42\\xC7\\x08\\x79\\x3A\\x86\\xE9\\xBC\\x50\\x48\\x1F\\xAD\\x98\\xB8\\xCB\\x1B\\x7E\\x87\\xB6\\xA3\\x93\\x59\\x24\\xDB\\x03\\xB0\\xEB\\x23\\xB0\\x97\\xD0\\x87\\xA4\\x7C\\xF0\\x14\\x61\\x3A\\x43\\xF4\\x3B\\x97\\x43\\xBA\\x4B\\x5D\\x04\\xAA\\xBD\\xC5\\x22\\xB5\\x66\\x59\\x9B\\x2C\\x5E\\xF8\\x1A\\xB3\\xBC\\x8C\\x2F\\x21\\x89\\xC0\\xAC\\x33\\xE7\\x38\\xAB\\x4B\\x99\\x18\\xA4\\x0B\\x02\\x4C\\xF0\\x69\\xA3\\xED\\xD5\\x17\\xA1\\xEB\\x7F\\x87\\x61\\xC9\\x5C\\x23\\xC6\\x6B\\x08\\x88\\xE9\\x86\\x94\\x67\\x75\\x0D\\x0B\\x4D\\xD6\\x13\\xAC\\xA1\\x92\\x6A\\x89\\xF5\\xAD\\x8B\\x57\\x87\\xD8\\x6E\\x4F\\xDC\\xD0\\x2B\\x28\\x2A\\x93\\x1E\\xE8\\x10\\xB6\\xAB\\xF5\\x36\\x34\\xB7\\x11\\x6D\\xDF\\xCA\\x1A\\x88\\x83\\xBA\\x57\\x61\\xE3\\xC9\\x5E\\x38\\x63\\xC0\\x04\\x6F\\x43\\x68\\xCA\\x0A\\xA0\\xAE\\x9A");
-        let k = Keccak::new(2, 1440, 80, 1440, 160);
+        let k = Keccak::crunchy(2);
         let hash = k.hash(message_bits);
         let hex_hash = bits_to_hex_string_keccak(hash);
         let expected_hash = "6390220e7b5d3284d23e";
         assert_eq!(hex_hash, expected_hash);
     }
 
+    #[test]
+    fn trace_of_the_permutation() {
+        // the first message of validate_hashing, absorbed one block at a time
+        let mut message_bits = bit::bits_from_binary_string_unchecked("100000000000000000000000000000000000000000011010100110100111000010011010111111011000001001111001100110001000000101101");
+        let k = Keccak::crunchy(1);
+        k.add_padding(&mut message_bits);
+        let mut state = k.initial_state();
+        for shard in message_bits.chunks(k.rate_length()) {
+            for (i, bit) in shard.iter().enumerate() {
+                // bit z + 8 * x of the block goes to the lane x of the first row
+                state[i / 8 + (i % 8) * 25] ^= bit.clone();
+            }
+            let trace = k.keccak_permutation_trace(state.clone());
+            let steps: Vec<&str> = trace.iter().map(|(step, _)| *step).collect();
+            assert_eq!(steps, vec!["theta", "rho", "pi", "chi", "iota"]);
+            assert_eq!(trace[4].1, k.keccak_permutation(state));
+            state = trace[4].1.clone();
+        }
+        // the lanes of the first row after this round are the first 40 bits of the hash
+        let rendered = Keccak::debug_state(&state);
+        assert_eq!(rendered.lines().count(), 5);
+        assert_eq!(rendered.lines().next(), Some("y = 0 : e9 f5 7f 02 a9"));
+        let undetermined = vec![Bit::from_variable_id(0); 25];
+        assert_eq!(
+            Keccak::debug_state(&undetermined).lines().next(),
+            Some("y = 0 : ? ? ? ? ?")
+        );
+    }
+
+    #[test]
+    fn round_constants_match_the_reference() {
+        // Keccak-f[1600] uses the whole constants, the crunchy presets (Keccak-f[200])
+        // only their 8 lowest bits
+        assert!(Keccak::new(1, 0, 0, 1088, 512).verify_round_constants());
+        assert!(Keccak::crunchy(1).verify_round_constants());
+    }
+
     #[test]
     fn validate_collision() {
         let message_bits = bits_from_hex_string_keccak("\\x3f\\xb7\\x7d\\x29\\x6d\\xb4\\x5f\\xce\\xab\\xd5\\xef\\x63\\xb2\\xdb\\x75\\xab\\xe7\\x19\\x01\\x02\\x73\\x77\\x92\\x06\\xa4\\xa6\\x45\\xa6\\xf8\\xe3\\xe6\\x68\\x62\\x24\\x28\\x15\\x83\\xab\\x3a\\x63\\xfb\\xa5\\xc7\\x96\\xb5\\xbe\\x4c\\x5e\\x96\\x4c\\x61\\x92\\xda\\x47\\x96\\xdd\\x4f\\x09\\xb0\\xd4\\x6f\\x37\\x68\\x4c\\x51\\x37\\xb6\\xd3\\x56\\xab\\x86\\x62\\x52\\x7a\\x57\\xde\\x0f\\xea\\x03\\x90");
-        let k = Keccak::new(5, 640, 160, 640, 160);
+        let k = Keccak::crunchy(3);
         let hash = k.hash(message_bits);
         let hex_hash = bits_to_hex_string_keccak(hash);
         let expected_hash = "ba5a0bf92d683074628c6685adb0e16635ac52b0";
         assert_eq!(hex_hash, expected_hash);
         let message_bits = bits_from_hex_string_keccak("\\xcb\\x0b\\x15\\x5b\\xfc\\xf2\\xf3\\xc0\\xa5\\xb5\\x07\\x59\\xc3\\x6d\\x73\\x22\\xc5\\xf0\\x4c\\x91\\x63\\x7d\\x47\\x87\\x49\\xa6\\x75\\xa6\\x6f\\xa9\\xbe\\x8b\\xe3\\x8a\\xeb\\x52\\x41\\x2d\\x40\\x19\\xc3\\x4c\\xfb\\xd9\\x30\\xd6\\x9f\\x66\\x71\\xfc\\xc9\\xd8\\x54\\x85\\x55\\x57\\x4a\\xf6\\x62\\x06\\xc5\\xb5\\xb4\\x64\\x56\\xbf\\x12\\x7f\\xf0\\xdb\\xea\\x2b\\x10\\x7b\\x20\\xf6\\x87\\x97\\xfd\\xf2");
-        let k = Keccak::new(5, 640, 160, 640, 160);
+        let k = Keccak::crunchy(3);
         let hash = k.hash(message_bits);
         let hex_hash = bits_to_hex_string_keccak(hash);
         let expected_hash = "ba5a0bf92d683074628c6685adb0e16635ac52b0";
         assert_eq!(hex_hash, expected_hash);
     }
+
+    #[test]
+    fn crunchy_presets_by_name() {
+        let parameters = |k: &dyn SpongeHash| {
+            (
+                k.n_rounds(),
+                k.message_length(),
+                k.output_length(),
+                k.rate_length(),
+                k.state_length(),
+            )
+        };
+        for level in 1..=Keccak::CRUNCHY_LEVELS {
+            let name = format!("keccak-crunchy-{}", level);
+            assert!(supported_sponges().contains(&name.as_str()));
+            let k = build_sponge_by_name(&name, 0, 0, 0, 0, 0).unwrap();
+            assert_eq!(parameters(k.as_ref()), parameters(&Keccak::crunchy(level)));
+        }
+        assert_eq!(parameters(&Keccak::crunchy(3)), (5, 640, 160, 640, 800));
+        assert!(build_sponge_by_name("keccak-crunchy-0", 0, 0, 0, 0, 0).is_none());
+        assert!(build_sponge_by_name("keccak-crunchy-4", 0, 0, 0, 0, 0).is_none());
+        assert!(build_sponge_by_name("keccak-crunchy-x", 0, 0, 0, 0, 0).is_none());
+    }
+
+    #[test]
+    fn preimage_with_initial_state() {
+        let initial_state: Vec<Bit> = (0..200).map(|i| bit!(i % 3 == 0)).collect();
+        let k = Keccak::with_initial_state(1, 40, 40, 40, 160, initial_state.clone());
+        let message = bit::bits_from_binary_string_unchecked("0110100111010010110001101011100100000001");
+        let image = k.hash(message.clone());
+        assert_ne!(image, Keccak::new(1, 40, 40, 40, 160).hash(message.clone()));
+        // the last 8 bits before the padding are unknown
+        let known_bits: Vec<usize> = (0..31).chain(39..40).collect();
+        let (output, mut system) = build_system_sponge(&k);
+        fix_system_values_sponge_with_partial_preimage(
+            &k,
+            &mut system,
+            &image,
+            &output,
+            (message, known_bits),
+        )
+        .unwrap();
+        let forbid_dropping: Vec<usize> = (0..k.message_length()).collect();
+        let sols = execute_strategy_by_name(
+            "drop",
+            &mut system,
+            Some(&forbid_dropping),
+            LogLevel::Quiet,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(!sols.is_empty());
+        for sol in sols.iter() {
+            let preimage: Vec<Bit> = sol[..k.message_length()]
+                .iter()
+                .map(|b| bit!(b.expect("message bits should be determined")))
+                .collect();
+            assert_eq!(image, k.hash(preimage));
+        }
+    }
+
+    #[test]
+    fn preimage_of_half_the_output() {
+        let k = Keccak::new(1, 40, 40, 40, 160);
+        let message = bit::bits_from_binary_string_unchecked("0110100111010010110001101011100100000001");
+        let image = k.hash(message.clone());
+        let known_output: Vec<(usize, bool)> =
+            (0..20).map(|i| (i, image[i].constant())).collect();
+        let (output, mut system) = build_system_sponge(&k);
+        fix_system_values_sponge_partial(&k, &mut system, &known_output, &output).unwrap();
+        // the first 20 bits of the message are unknown, which leaves few enough preimages for
+        // get_all_valid_path to return all of them
+        for (i, bit) in message.iter().enumerate().take(39).skip(20) {
+            system.fix(vec![i], bit.constant()).unwrap();
+        }
+        let forbid_dropping: Vec<usize> = (0..k.message_length()).collect();
+        let sols = execute_strategy_by_name(
+            "drop",
+            &mut system,
+            Some(&forbid_dropping),
+            LogLevel::Quiet,
+        )
+        .unwrap()
+        .unwrap();
+        let images: Vec<Vec<Bit>> = sols
+            .iter()
+            .map(|sol| {
+                let preimage: Vec<Bit> = sol[..k.message_length()]
+                    .iter()
+                    .map(|b| bit!(b.expect("message bits should be determined")))
+                    .collect();
+                k.hash(preimage)
+            })
+            .collect();
+        assert!(images.contains(&image));
+        for found in images.iter() {
+            assert_eq!(found[..20], image[..20]);
+        }
+        assert!(images.iter().any(|found| found[20..] != image[20..]));
+    }
 }
@@ -0,0 +1,144 @@
+//! A cipher whose S-Box is the identity and whose linear layer is an invertible GF(2) matrix,
+//! so the whole encryption is affine in the message and the key.
+//!
+//! It is not meant to be secure: solving it only needs linear algebra, which makes it a
+//! baseline to check the solver on an idealised structure (ex: a distinguisher experiment).
+
+use crate::rand::{rngs::StdRng, Rng, SeedableRng};
+use crate::sbox::Sbox;
+use crate::targets::Cipher;
+use crate::{bit, bit::Bit};
+use crush::algebra::{self, Matrix};
+use vob::Vob;
+
+pub struct LinearCipher {
+    n_rounds: usize,
+    message_length: usize,
+    matrix: Matrix,
+    sbox: Sbox,
+}
+
+impl LinearCipher {
+    /// Build a cipher of `n_rounds` rounds whose linear layer multiplies the state by `matrix`,
+    /// the message length being the size of the matrix (a multiple of 4, the size of the
+    /// identity S-Box).
+    ///
+    /// Panics if `matrix` is not invertible or if its size is not a multiple of 4.
+    pub fn new(matrix: Matrix, n_rounds: usize) -> Self {
+        assert!(algebra::is_invertible(&matrix), "the linear layer should be invertible");
+        let message_length = matrix.row_size();
+        assert_eq!(
+            message_length % 4,
+            0,
+            "the message length {} is not a multiple of 4",
+            message_length
+        );
+        LinearCipher {
+            n_rounds,
+            message_length,
+            matrix,
+            sbox: Sbox::new(4, 4, (0..16).collect(), 2 * message_length),
+        }
+    }
+
+    /// Build a cipher of `n_rounds` rounds on `message_length` bits whose linear layer is an
+    /// invertible matrix drawn at random from `seed`.
+    pub fn random(message_length: usize, n_rounds: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        loop {
+            let rows = (0..message_length)
+                .map(|_| (0..message_length).map(|_| rng.gen()).collect::<Vob>())
+                .collect();
+            let matrix = Matrix::from_rows(rows);
+            if algebra::is_invertible(&matrix) {
+                return Self::new(matrix, n_rounds);
+            }
+        }
+    }
+
+    fn linear_layer(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
+        assert_eq!(in_bits.len(), self.message_length);
+        self.matrix
+            .iter_rows()
+            .map(|row| {
+                row.iter_set_bits(..)
+                    .fold(bit!(false), |sum, j| sum ^ in_bits[j].clone())
+            })
+            .collect()
+    }
+}
+
+impl Cipher for LinearCipher {
+    /// The key is only added to the message before the first round, so that
+    /// `ciphertext = matrix^n_rounds * (plaintext + key)` always gives a single key.
+    fn encrypt(&self, in_bits: Vec<Bit>, key_bits: Vec<Bit>) -> Vec<Bit> {
+        assert_eq!(key_bits.len(), self.message_length);
+        let mut out_bits = bit::bit_vector_xoring(in_bits, key_bits);
+        for _ in 0..self.n_rounds {
+            out_bits = self.linear_layer(self.sbox.apply_layer(&out_bits));
+        }
+        out_bits
+    }
+
+    fn message_length(&self) -> usize {
+        self.message_length
+    }
+
+    fn key_length(&self) -> usize {
+        self.message_length
+    }
+
+    fn n_rounds(&self) -> usize {
+        self.n_rounds
+    }
+
+    /// There is no specification, an instance has as many rounds as it was built with.
+    fn full_rounds(&self) -> usize {
+        self.n_rounds
+    }
+
+    fn sbox(&self) -> Sbox {
+        self.sbox.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bit;
+    use crate::strategy::{absorb_only, execute_strategy_by_name, LogLevel};
+    use crate::targets::{
+        build_system_cipher, fix_system_values_cipher, linear_cipher::LinearCipher, Cipher,
+    };
+    use crush::algebra::Matrix;
+
+    #[test]
+    #[should_panic]
+    fn singular_matrix_is_rejected() {
+        LinearCipher::new(Matrix::new(8, 8), 1);
+    }
+
+    #[test]
+    fn key_is_recovered_by_linear_algebra_only() {
+        let cipher = LinearCipher::random(16, 3, 1);
+        let key = bit::bits_from_hex_string_unchecked("c9bd");
+        let plaintext = bit::bits_from_hex_string_unchecked("f721");
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        let (layout, mut system) = build_system_cipher(&cipher);
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        // the relations of the identity S-Boxes are linear, absorbing them leaves no Bdd and
+        // a LinBank determining every variable
+        let report = absorb_only(&mut system).unwrap();
+        assert_eq!(report.bdds, 0);
+        assert_eq!(report.lin_eqs, system.get_nvar());
+        let solutions = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        assert_eq!(solutions.len(), 1);
+        let recovered: Vec<bool> = solutions[0][layout.key_vars.clone()]
+            .iter()
+            .map(|var| var.expect("the key should be determined"))
+            .collect();
+        let expected: Vec<bool> = key.iter().map(|bit| bit.constant()).collect();
+        assert_eq!(recovered, expected);
+    }
+}
@@ -1,8 +1,7 @@
 use crate::crush::algebra::{Matrix, *};
 
 use crate::sbox::Sbox;
-use crate::targets::Cipher;
-use crate::vob::Vob;
+use crate::targets::{Cipher, RoundProfile};
 use crate::{bit, bit::Bit, bit::*};
 use std::cmp;
 use std::collections::VecDeque;
@@ -24,7 +23,24 @@ struct LowMCParams {
 }
 
 impl LowMC {
+    /// Build a LowMC instance whose S-Box layer applies `n_sbox` S-Boxes to the last
+    /// `3 * n_sbox` bits of the state (a partial layer if it doesn't cover the whole state).
+    ///
+    /// Panics if the message or the key is empty, or if the S-Boxes don't fit in the state.
     pub fn new(n_rounds: usize, message_length: usize, key_length: usize, n_sbox: usize) -> Self {
+        assert!(
+            message_length > 0 && key_length > 0,
+            "LowMC needs a non empty message and key, got {} and {} bits",
+            message_length,
+            key_length
+        );
+        assert!(
+            3 * n_sbox <= message_length,
+            "LowMC with a {}-bit message has at most {} S-Boxes, got {}",
+            message_length,
+            message_length / 3,
+            n_sbox
+        );
         let table = vec![0x00, 0x01, 0x03, 0x06, 0x07, 0x04, 0x05, 0x02];
 
         let mut lowmc = LowMC {
@@ -39,6 +55,11 @@ impl LowMC {
         lowmc
     }
 
+    /// The number of S-Boxes of the S-Box layer.
+    pub fn sbox_count(&self) -> usize {
+        self.n_sbox
+    }
+
     fn key_addition(&self, in_bits: Vec<Bit>, round_key: Vec<Bit>) -> Vec<Bit> {
         assert_eq!(in_bits.len(), self.message_length);
         assert_eq!(in_bits.len(), round_key.len());
@@ -166,26 +187,8 @@ fn extract(lfsr: &mut VecDeque<bool>, t: usize) -> Vec<bool> {
     out
 }
 
-fn matrix_from_vec_bool(matrix: &[bool], n_rows: usize, n_columns: usize) -> Matrix {
-    assert_eq!(matrix.len(), n_rows * n_columns);
-    let mut rows = Vec::with_capacity(n_rows);
-    for row_index in 0..matrix.len() / n_columns {
-        let mut row = Vob::new();
-        let row_bits = &matrix
-            .iter()
-            .skip(row_index * n_columns)
-            .take(n_columns)
-            .collect::<Vec<&bool>>();
-        for bit in row_bits.iter() {
-            row.push(**bit);
-        }
-        rows.push(row);
-    }
-    matrix![rows]
-}
-
 fn matrix_rank(matrix: &[bool], n_rows: usize, n_columns: usize) -> usize {
-    let mut m = matrix_from_vec_bool(matrix, n_rows, n_columns);
+    let mut m = Matrix::from_flat(matrix, n_rows, n_columns);
     let rank = if n_rows > n_columns {
         m = transpose(&m);
         n_columns
@@ -237,6 +240,10 @@ impl Cipher for LowMC {
         state
     }
 
+    fn round_keys(&self, key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        Some(self.make_round_keys(key))
+    }
+
     fn message_length(&self) -> usize {
         self.message_length
     }
@@ -249,22 +256,51 @@ impl Cipher for LowMC {
         self.n_rounds
     }
 
+    /// The constants of LowMC are generated for any number of rounds, every instance is a full
+    /// round instance.
+    fn full_rounds(&self) -> usize {
+        self.n_rounds
+    }
+
     fn sbox(&self) -> Sbox {
         self.sbox.clone()
     }
+
+    /// The S-Box layer of LowMC may only cover part of the state.
+    fn equation_profile(&self) -> Vec<RoundProfile> {
+        let profile = RoundProfile {
+            sbox_relations: self.n_sbox,
+            linear_equations: self.message_length,
+        };
+        vec![profile; self.n_rounds]
+    }
+
+    /// The matrix of the linear layer of the first round, on single bits since the S-Box layer
+    /// may not cover the state. Every round has its own matrix.
+    fn linear_layer_matrix(&self) -> Option<(Matrix, usize)> {
+        // `multiply_with_gf2_matrix` reads the rows and the columns from the end
+        let matrix = self.init_params.lin_matrices.first()?;
+        let reversed: Vec<bool> = matrix.iter().cloned().rev().collect();
+        Some((
+            Matrix::from_flat(&reversed, self.message_length, self.message_length),
+            1,
+        ))
+    }
 }
 
-// from https://github.com/LowMC/lowmc
 #[cfg(test)]
-#[cfg(not(debug_assertions))]
 mod test {
-    use crate::bit;
+    use crate::bit::{self, Bit};
     use crate::targets::{lowmc::LowMC, Cipher};
+    use crush::algebra::{self, Matrix};
+
+    // from https://github.com/LowMC/lowmc
     #[test]
+    #[cfg(not(debug_assertions))]
     fn validate_encrypt() {
         let lowmc = LowMC::new(12, 256, 80, 49);
-        let plaintext = bit::bits_from_binary_string("0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001111111111010101");
-        let key = bit::bits_from_binary_string(
+        let plaintext = bit::bits_from_binary_string_unchecked("0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001111111111010101");
+        let key = bit::bits_from_binary_string_unchecked(
             "00000000000000000000000000000000000000000000000000000000000000000000000000000001",
         );
         assert_eq!(
@@ -273,10 +309,10 @@ mod test {
     );
 
         let lowmc = LowMC::new(164, 64, 80, 1);
-        let plaintext = bit::bits_from_binary_string(
+        let plaintext = bit::bits_from_binary_string_unchecked(
             "0000000000000000000000000000000000000000000000001111111111010101",
         );
-        let key = bit::bits_from_binary_string(
+        let key = bit::bits_from_binary_string_unchecked(
             "00000000000000000000000000000000000000000000000000000000000000000000000000000001",
         );
         assert_eq!(
@@ -284,4 +320,38 @@ mod test {
             bit::bits_to_binary_string(lowmc.encrypt(plaintext, key))
         );
     }
+
+    #[test]
+    fn matrices_are_built_row_by_row() {
+        let lowmc = LowMC::new(2, 64, 80, 1);
+        let params = &lowmc.init_params;
+        for matrix in params.lin_matrices.iter() {
+            let m = Matrix::from_flat(matrix, 64, 64);
+            for (row, bits) in m.iter_rows().zip(matrix.chunks(64)) {
+                assert!(row.iter().eq(bits.iter().cloned()));
+            }
+            assert!(algebra::is_invertible(&m));
+        }
+        for matrix in params.key_matrices.iter() {
+            assert_eq!(super::matrix_rank(matrix, 64, 80), 64);
+        }
+    }
+
+    #[test]
+    fn partial_sbox_layer() {
+        let lowmc = LowMC::new(1, 64, 80, 1);
+        assert_eq!(lowmc.sbox_count(), 1);
+        let in_bits: Vec<Bit> = (0..64).map(Bit::from_variable_id).collect();
+        let out_bits = lowmc.sbox_layer(in_bits.clone());
+        // only the last 3 bits go through the S-Box
+        assert_eq!(out_bits[..61], in_bits[..61]);
+        assert!(out_bits[61..].iter().zip(in_bits[61..].iter()).all(|(out, bit)| out != bit));
+        assert_eq!(lowmc.encrypt(bit::random_bits(64), bit::random_bits(80)).len(), 64);
+    }
+
+    #[test]
+    #[should_panic(expected = "LowMC with a 64-bit message has at most 21 S-Boxes, got 22")]
+    fn too_many_sboxes() {
+        LowMC::new(1, 64, 80, 22);
+    }
 }
@@ -10,7 +10,16 @@ pub struct MiniAES2x2 {
 }
 
 impl MiniAES2x2 {
+    /// The number of rounds of the specification, and the most this implementation supports.
+    pub const MAX_ROUNDS: usize = 10;
+
     pub fn new(n_rounds: usize) -> Self {
+        assert!(
+            n_rounds <= Self::MAX_ROUNDS,
+            "miniaes2x2 has at most {} rounds, got {}",
+            Self::MAX_ROUNDS,
+            n_rounds
+        );
         let table = vec![
             0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7,
             0xab, 0x76, 0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf,
@@ -111,16 +120,16 @@ impl MiniAES2x2 {
         assert_eq!(key.len(), self.key_length);
         let mut round_keys = Vec::with_capacity(self.n_rounds);
         let round_constants = vec![
-            bit::bits_from_hex_string("0100"),
-            bit::bits_from_hex_string("0200"),
-            bit::bits_from_hex_string("0400"),
-            bit::bits_from_hex_string("0800"),
-            bit::bits_from_hex_string("1000"),
-            bit::bits_from_hex_string("2000"),
-            bit::bits_from_hex_string("4000"),
-            bit::bits_from_hex_string("8000"),
-            bit::bits_from_hex_string("1B00"),
-            bit::bits_from_hex_string("3600"),
+            bit::bits_from_hex_string_unchecked("0100"),
+            bit::bits_from_hex_string_unchecked("0200"),
+            bit::bits_from_hex_string_unchecked("0400"),
+            bit::bits_from_hex_string_unchecked("0800"),
+            bit::bits_from_hex_string_unchecked("1000"),
+            bit::bits_from_hex_string_unchecked("2000"),
+            bit::bits_from_hex_string_unchecked("4000"),
+            bit::bits_from_hex_string_unchecked("8000"),
+            bit::bits_from_hex_string_unchecked("1B00"),
+            bit::bits_from_hex_string_unchecked("3600"),
         ];
         let mut k0 = key[0..8].to_vec();
         k0.append(&mut key[16..24].to_vec());
@@ -165,6 +174,20 @@ impl Cipher for MiniAES2x2 {
         )
     }
 
+    /// The key schedule applies the S-Box twice per round before the encryption does, the
+    /// variables of the S-Box layers of the rounds come right after.
+    fn state_variable(&self, round: usize, pos: usize) -> Option<usize> {
+        if round >= self.n_rounds || pos >= self.message_length {
+            return None;
+        }
+        let base = self.message_length + self.key_length + 2 * 8 * self.n_rounds;
+        Some(base + round * self.message_length + pos)
+    }
+
+    fn round_keys(&self, key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        Some(self.make_round_keys(key))
+    }
+
     fn message_length(&self) -> usize {
         self.message_length
     }
@@ -177,6 +200,10 @@ impl Cipher for MiniAES2x2 {
         self.n_rounds
     }
 
+    fn full_rounds(&self) -> usize {
+        Self::MAX_ROUNDS
+    }
+
     fn sbox(&self) -> Sbox {
         self.sbox.clone()
     }
@@ -186,12 +213,24 @@ impl Cipher for MiniAES2x2 {
 #[cfg(test)]
 mod test {
     use crate::bit;
+    use crate::gf2n;
     use crate::targets::{miniaes2x2::MiniAES2x2, Cipher};
 
-    
+
+    #[test]
+    fn time_x_is_mul_const_in_the_aes_field() {
+        for value in 0..=255u8 {
+            let bits = bit::bits_from_hex_string_unchecked(&format!("{:02x}", value));
+            assert_eq!(
+                bit::bits_to_hex_string(MiniAES2x2::time_x(bits.clone())),
+                bit::bits_to_hex_string(gf2n::mul_const(&bits, 2, 0x11b))
+            );
+        }
+    }
+
     #[test]
     fn validate_key_schedule() {
-        let key = bit::bits_from_hex_string("c9bd6550");
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
         let cipher = MiniAES2x2::new(10);
         let round_keys = cipher.make_round_keys(key);
         let expected_keys = vec![
@@ -209,7 +248,7 @@ mod test {
 
     #[test]
     fn validate_mix_column() {
-        let state = bit::bits_from_hex_string("9c6904e1");
+        let state = bit::bits_from_hex_string_unchecked("9c6904e1");
         let cipher = MiniAES2x2::new(10);
         let expected_state = "b7622fea";
         assert_eq!(
@@ -217,7 +256,7 @@ mod test {
             bit::bits_to_hex_string(cipher.mix_columns(state))
         );
 
-        let state = bit::bits_from_hex_string("b9bAc044");
+        let state = bit::bits_from_hex_string_unchecked("b9bAc044");
         let cipher = MiniAES2x2::new(10);
         let expected_state = "4b5d32a3";
         assert_eq!(
@@ -228,7 +267,7 @@ mod test {
 
     #[test]
     fn validate_shift_rows() {
-        let state = bit::bits_from_hex_string("a6767ea0");
+        let state = bit::bits_from_hex_string_unchecked("a6767ea0");
         let cipher = MiniAES2x2::new(10);
         let expected_state = "a676a07e";
         assert_eq!(
@@ -236,7 +275,7 @@ mod test {
             bit::bits_to_hex_string(cipher.shift_rows(state))
         );
 
-        let state = bit::bits_from_hex_string("8fbcc647");
+        let state = bit::bits_from_hex_string_unchecked("8fbcc647");
         let cipher = MiniAES2x2::new(10);
         let expected_state = "8fbc47c6";
         assert_eq!(
@@ -247,7 +286,7 @@ mod test {
 
     #[test]
     fn validate_sub_bytes() {
-        let state = bit::bits_from_hex_string("16c59e64");
+        let state = bit::bits_from_hex_string_unchecked("16c59e64");
         let cipher = MiniAES2x2::new(10);
         let expected_state = "47a60b43";
         assert_eq!(
@@ -255,7 +294,7 @@ mod test {
             bit::bits_to_hex_string(cipher.sub_bytes(state))
         );
 
-        let state = bit::bits_from_hex_string("06abb7f1");
+        let state = bit::bits_from_hex_string_unchecked("06abb7f1");
         let cipher = MiniAES2x2::new(10);
         let expected_state = "6f62a9a1";
         assert_eq!(
@@ -267,8 +306,8 @@ mod test {
     #[test]
     fn validate_encrypt() {
         let cipher = MiniAES2x2::new(10);
-        let key = bit::bits_from_hex_string("dc16b351");
-        let plaintext = bit::bits_from_hex_string("0d2729ac");
+        let key = bit::bits_from_hex_string_unchecked("dc16b351");
+        let plaintext = bit::bits_from_hex_string_unchecked("0d2729ac");
         let expected_ciphertext = "56737333";
         assert_eq!(
             expected_ciphertext,
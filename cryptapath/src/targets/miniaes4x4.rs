@@ -1,3 +1,4 @@
+use crate::gf2n;
 use crate::sbox::Sbox;
 use crate::targets::Cipher;
 use crate::{bit, bit::Bit, bit::*};
@@ -10,7 +11,23 @@ pub struct MiniAES4x4 {
 }
 
 impl MiniAES4x4 {
+    /// The number of rounds of the specification, and the most this implementation supports.
+    pub const MAX_ROUNDS: usize = 10;
+
+    /// The field polynomial x^4 + x + 1 of the cells.
+    const FIELD_MODULUS: u16 = 0x13;
+
+    /// The matrix multiplying each column in MixColumns.
+    const MIX_COLUMNS_MATRIX: [[u16; 4]; 4] =
+        [[2, 3, 1, 1], [1, 2, 3, 1], [1, 1, 2, 3], [3, 1, 1, 2]];
+
     pub fn new(n_rounds: usize) -> Self {
+        assert!(
+            n_rounds <= Self::MAX_ROUNDS,
+            "miniaes4x4 has at most {} rounds, got {}",
+            Self::MAX_ROUNDS,
+            n_rounds
+        );
         let table = vec![
             0x06, 0x0b, 0x05, 0x04, 0x02, 0x0e, 0x07, 0x0a, 0x09, 0x0d, 0x0f, 0x0c, 0x03, 0x01,
             0x00, 0x08,
@@ -25,11 +42,7 @@ impl MiniAES4x4 {
 
     fn sub_bytes(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
         assert!(in_bits.len() == self.message_length);
-        let mut out_bits = Vec::with_capacity(self.message_length);
-        for i in 0..16 {
-            out_bits.append(&mut self.sbox.apply(in_bits[i * 4..(i + 1) * 4].to_vec()));
-        }
-        out_bits
+        self.sbox.apply_layer(&in_bits)
     }
 
     fn shift_rows(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
@@ -49,76 +62,23 @@ impl MiniAES4x4 {
         assert_eq!(in_bits.len(), self.message_length);
         let mut out_bits = vec![bit!(false); in_bits.len()];
         for column in 0..4 {
-            let a = in_bits
-                .iter()
-                .cloned()
-                .skip(column * 4)
-                .take(4)
-                .collect::<Vec<Bit>>();
-            let b = in_bits
-                .iter()
-                .cloned()
-                .skip(16 + column * 4)
-                .take(4)
-                .collect::<Vec<Bit>>();
-            let c = in_bits
-                .iter()
-                .cloned()
-                .skip(32 + column * 4)
-                .take(4)
-                .collect::<Vec<Bit>>();
-            let d = in_bits
-                .iter()
-                .cloned()
-                .skip(48 + column * 4)
-                .take(4)
-                .collect::<Vec<Bit>>();
-            let a_x = Self::time_x(a.clone());
-            let b_x = Self::time_x(b.clone());
-            let c_x = Self::time_x(c.clone());
-            let d_x = Self::time_x(d.clone());
-            let out_a = {
-                let b_x_1 = bit_vector_xoring(b_x.clone(), b.clone());
-                let mut tmp = bit_vector_xoring(a_x.clone(), b_x_1);
-                tmp = bit_vector_xoring(tmp, c.clone());
-                bit_vector_xoring(tmp, d.clone())
-            };
-            let out_b = {
-                let c_x_1 = bit_vector_xoring(c_x.clone(), c.clone());
-                let mut tmp = bit_vector_xoring(a.clone(), b_x);
-                tmp = bit_vector_xoring(tmp, c_x_1);
-                bit_vector_xoring(tmp, d.clone())
-            };
-            let out_c = {
-                let d_x_1 = bit_vector_xoring(d_x.clone(), d);
-                let mut tmp = bit_vector_xoring(a.clone(), b.clone());
-                tmp = bit_vector_xoring(tmp, c_x);
-                bit_vector_xoring(tmp, d_x_1)
-            };
-            let out_d = {
-                let a_x_1 = bit_vector_xoring(a_x, a);
-                let mut tmp = bit_vector_xoring(a_x_1, b);
-                tmp = bit_vector_xoring(tmp, c);
-                bit_vector_xoring(tmp, d_x)
-            };
-            for bit in 0..4 {
-                out_bits[bit + column * 4] = out_a[bit].clone();
-                out_bits[bit + column * 4 + 16] = out_b[bit].clone();
-                out_bits[bit + column * 4 + 32] = out_c[bit].clone();
-                out_bits[bit + column * 4 + 48] = out_d[bit].clone();
+            let cells = (0..4)
+                .map(|row| in_bits[row * 16 + column * 4..row * 16 + column * 4 + 4].to_vec())
+                .collect::<Vec<Vec<Bit>>>();
+            for (row, coefficients) in Self::MIX_COLUMNS_MATRIX.iter().enumerate() {
+                let out_cell = cells
+                    .iter()
+                    .zip(coefficients.iter())
+                    .map(|(cell, c)| gf2n::mul_const(cell, *c, Self::FIELD_MODULUS))
+                    .fold(vec![bit!(false); 4], bit_vector_xoring);
+                for (bit, out_bit) in out_cell.into_iter().enumerate() {
+                    out_bits[bit + column * 4 + row * 16] = out_bit;
+                }
             }
         }
         out_bits
     }
 
-    fn time_x(in_bits: Vec<Bit>) -> Vec<Bit> {
-        assert_eq!(in_bits.len(), 4);
-        let mut time_x = in_bits[1..4].to_vec();
-        time_x.push(in_bits[0].clone());
-        time_x[2] ^= in_bits[0].clone();
-        time_x
-    }
-
     fn add_round_key(&self, in_bits: Vec<Bit>, round_key: Vec<Bit>) -> Vec<Bit> {
         assert!(in_bits.len() == self.message_length);
         assert!(round_key.len() == self.message_length);
@@ -129,16 +89,16 @@ impl MiniAES4x4 {
         assert_eq!(key.len(), self.key_length);
         let mut round_keys = Vec::with_capacity(self.n_rounds);
         let round_constants = vec![
-            bit::bits_from_hex_string("1000"),
-            bit::bits_from_hex_string("2000"),
-            bit::bits_from_hex_string("4000"),
-            bit::bits_from_hex_string("8000"),
-            bit::bits_from_hex_string("3000"),
-            bit::bits_from_hex_string("6000"),
-            bit::bits_from_hex_string("c000"),
-            bit::bits_from_hex_string("b000"),
-            bit::bits_from_hex_string("5000"),
-            bit::bits_from_hex_string("a000"),
+            bit::bits_from_hex_string_unchecked("1000"),
+            bit::bits_from_hex_string_unchecked("2000"),
+            bit::bits_from_hex_string_unchecked("4000"),
+            bit::bits_from_hex_string_unchecked("8000"),
+            bit::bits_from_hex_string_unchecked("3000"),
+            bit::bits_from_hex_string_unchecked("6000"),
+            bit::bits_from_hex_string_unchecked("c000"),
+            bit::bits_from_hex_string_unchecked("b000"),
+            bit::bits_from_hex_string_unchecked("5000"),
+            bit::bits_from_hex_string_unchecked("a000"),
         ];
         let (mut k0, mut k1, mut k2, mut k3) = (
             key[0..4].to_vec(),
@@ -201,6 +161,10 @@ impl Cipher for MiniAES4x4 {
         )
     }
 
+    fn round_keys(&self, key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        Some(self.make_round_keys(key))
+    }
+
     fn message_length(&self) -> usize {
         self.message_length
     }
@@ -213,6 +177,10 @@ impl Cipher for MiniAES4x4 {
         self.n_rounds
     }
 
+    fn full_rounds(&self) -> usize {
+        Self::MAX_ROUNDS
+    }
+
     fn sbox(&self) -> Sbox {
         self.sbox.clone()
     }
@@ -220,7 +188,7 @@ impl Cipher for MiniAES4x4 {
 
 #[test]
 fn validate_key_schedule() {
-    let key = bit::bits_from_hex_string("c4de2cadef240c95");
+    let key = bit::bits_from_hex_string_unchecked("c4de2cadef240c95");
     let cipher = MiniAES4x4::new(10);
     let round_keys = cipher.make_round_keys(key);
     let expected_keys = vec![
@@ -250,11 +218,28 @@ fn validate_key_schedule() {
 #[cfg(test)]
 mod test {
     use crate::bit;
-    use crate::targets::{miniaes4x4::MiniAES4x4, Cipher};
+    use crate::targets::{build_system_cipher, miniaes4x4::MiniAES4x4, Cipher};
+
+    #[test]
+    fn equation_profile() {
+        let cipher = MiniAES4x4::new(3);
+        let profile = cipher.equation_profile();
+        assert_eq!(profile.len(), 3);
+        // ShiftRows, MixColumns and AddRoundKey (no MixColumns in the last round) compute
+        // the 64 bits of the state
+        for round in profile.iter() {
+            assert_eq!(round.sbox_relations, 16);
+            assert_eq!(round.linear_equations, 64);
+        }
+        // one bdd per S-Box, the key schedule has 4 more S-Boxes per round
+        let (_, system) = build_system_cipher(&cipher);
+        let sbox_relations: usize = profile.iter().map(|round| round.sbox_relations).sum();
+        assert_eq!(system.iter_bdds().len(), sbox_relations + 4 * 3);
+    }
 
     #[test]
     fn validate_mix_column() {
-        let state = bit::bits_from_hex_string("b316c65d45d0e76d");
+        let state = bit::bits_from_hex_string_unchecked("b316c65d45d0e76d");
         let cipher = MiniAES4x4::new(10);
         let expected_state = "8e652792e67f987e";
         assert_eq!(
@@ -262,7 +247,7 @@ mod test {
             bit::bits_to_hex_string(cipher.mix_columns(state))
         );
 
-        let state = bit::bits_from_hex_string("7865690c18f0f9ad");
+        let state = bit::bits_from_hex_string_unchecked("7865690c18f0f9ad");
         let expected_state = "aa907be31a6d3b2a";
         assert_eq!(
             expected_state,
@@ -272,7 +257,7 @@ mod test {
 
     #[test]
     fn validate_shift_rows() {
-        let state = bit::bits_from_hex_string("b5da6ba15149d885");
+        let state = bit::bits_from_hex_string_unchecked("b5da6ba15149d885");
         let cipher = MiniAES4x4::new(10);
         let expected_state = "b5daba1649515d88";
         assert_eq!(
@@ -280,7 +265,7 @@ mod test {
             bit::bits_to_hex_string(cipher.shift_rows(state))
         );
 
-        let state = bit::bits_from_hex_string("8746923890145362");
+        let state = bit::bits_from_hex_string_unchecked("8746923890145362");
         let expected_state = "8746238914902536";
         assert_eq!(
             expected_state,
@@ -290,7 +275,7 @@ mod test {
 
     #[test]
     fn validate_sub_bytes() {
-        let state = bit::bits_from_hex_string("fc7979b7f955da1d");
+        let state = bit::bits_from_hex_string_unchecked("fc7979b7f955da1d");
         let cipher = MiniAES4x4::new(10);
         let expected_state = "83adadca8dee1fb1";
         assert_eq!(
@@ -298,7 +283,7 @@ mod test {
             bit::bits_to_hex_string(cipher.sub_bytes(state))
         );
 
-        let state = bit::bits_from_hex_string("736e20605aa1064d");
+        let state = bit::bits_from_hex_string_unchecked("736e20605aa1064d");
         let expected_state = "a4705676effb6721";
         assert_eq!(
             expected_state,
@@ -309,8 +294,8 @@ mod test {
     #[test]
     fn validate_encrypt() {
         let cipher = MiniAES4x4::new(10);
-        let key = bit::bits_from_hex_string("07f5167304421207");
-        let plaintext = bit::bits_from_hex_string("05f6a0b7035625dd");
+        let key = bit::bits_from_hex_string_unchecked("07f5167304421207");
+        let plaintext = bit::bits_from_hex_string_unchecked("05f6a0b7035625dd");
         let expected_ciphertext = "336dc64ef859c8c4";
         assert_eq!(
             expected_ciphertext,
@@ -1,5 +1,7 @@
 pub mod des;
+pub mod katan;
 pub mod keccak;
+pub mod linear_cipher;
 pub mod lowmc;
 pub mod miniaes2x2;
 pub mod miniaes4x4;
@@ -9,6 +11,7 @@ pub mod skinny128;
 pub mod skinny64;
 
 use des::DES;
+use katan::Katan32;
 use keccak::Keccak;
 use lowmc::LowMC;
 use miniaes2x2::MiniAES2x2;
@@ -20,10 +23,19 @@ use skinny64::Skinny64;
 
 use crate::bit::{self, Bit, *};
 use crate::sbox::Sbox;
+use crush::algebra::Matrix;
+use crush::error::CrushError;
 use crush::soc::{
     system::System,
     utils::{SystemSpec, *},
+    Id,
 };
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{Error, ErrorKind};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 pub trait SpongeHash {
     fn hash(&self, in_bits: Vec<Bit>) -> Vec<Bit>;
@@ -33,6 +45,48 @@ pub trait SpongeHash {
     fn output_length(&self) -> usize;
     fn n_rounds(&self) -> usize;
     fn sbox(&self) -> Sbox;
+
+    /// The constant bits the state starts from before absorbing the message, all zeros
+    /// by default.
+    fn initial_state(&self) -> Vec<Bit> {
+        vec![bit!(false); self.state_length()]
+    }
+
+    /// Check the round constants generated by the hash against published values.
+    ///
+    /// The default implementation is for the hashes without a reference to compare to and
+    /// always returns true.
+    fn verify_round_constants(&self) -> bool {
+        true
+    }
+
+    /// The order of the bits in each byte of the hexadecimal values of the specification of
+    /// the hash (see `bit::bits_from_hex_string_with_order`), most significant bit first by
+    /// default.
+    fn hex_bit_order(&self) -> BitOrder {
+        BitOrder::Msb0
+    }
+}
+
+/// The number of equations a round of a cipher adds to its system.
+///
+/// Each S-Box of the round relates its input bits to its output bits by a nonlinear relation,
+/// and each bit of the state computed by the linear layers following the S-Box layer (ex:
+/// ShiftRows, MixColumns and AddRoundKey) is a linear equation on the outputs of the S-Boxes
+/// and the key. The S-Boxes of a key schedule are not counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoundProfile {
+    pub sbox_relations: usize,
+    pub linear_equations: usize,
+}
+
+/// Role of a tweakey word of a tweakable cipher (TK1, TK2, TK3 in the Skinny spec).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TweakeyWord {
+    /// The word holds (part of) the public tweak.
+    Tweak,
+    /// The word holds (part of) the secret key.
+    Key,
 }
 
 pub trait Cipher {
@@ -41,9 +95,102 @@ pub trait Cipher {
     fn n_rounds(&self) -> usize;
     fn key_length(&self) -> usize;
     fn sbox(&self) -> Sbox;
+
+    /// The number of rounds of the specification of the cipher, the most rounds an instance
+    /// can have.
+    fn full_rounds(&self) -> usize;
+
+    /// Length of the public tweak of a tweakable cipher, 0 for the others.
+    fn tweak_length(&self) -> usize {
+        0
+    }
+
+    /// Encrypt `in_bits` under `key_bits` and the tweak `tweak_bits`.
+    ///
+    /// The default implementation is for ciphers without a tweak and only accepts an empty tweak.
+    fn encrypt_with_tweak(
+        &self,
+        in_bits: Vec<Bit>,
+        key_bits: Vec<Bit>,
+        tweak_bits: Vec<Bit>,
+    ) -> Vec<Bit> {
+        assert!(tweak_bits.is_empty(), "this cipher does not take a tweak");
+        self.encrypt(in_bits, key_bits)
+    }
+
+    /// Return the round keys derived from `key` by the key schedule, for debugging purposes.
+    ///
+    /// Only the ciphers with a separate key schedule expose it, the default implementation
+    /// returns `None`.
+    fn round_keys(&self, _key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        None
+    }
+
+    /// Return the id of the variable holding the bit `pos` of the state right after the S-Box
+    /// layer of the round `round` (starting from 0), in a system built by `build_system_cipher`.
+    ///
+    /// Only some ciphers expose this mapping, the default implementation returns `None`.
+    fn state_variable(&self, _round: usize, _pos: usize) -> Option<usize> {
+        None
+    }
+
+    /// Decrypt the constant bits `in_bits` under the constant bits `key_bits`.
+    ///
+    /// Only some ciphers implement decryption, the default implementation returns `None`.
+    fn decrypt(&self, _in_bits: Vec<Bit>, _key_bits: Vec<Bit>) -> Option<Vec<Bit>> {
+        None
+    }
+
+    /// The order of the bits in each byte of the hexadecimal plaintexts, ciphertexts and keys
+    /// of the specification of the cipher (see `bit::bits_from_hex_string_with_order`), most
+    /// significant bit first by default.
+    fn hex_bit_order(&self) -> BitOrder {
+        BitOrder::Msb0
+    }
+
+    /// Return the matrix of the linear layer of the cipher and the size of the words it mixes
+    /// (see `crush::algebra::branch_number`).
+    ///
+    /// Only the ciphers whose linear layer is given as a matrix expose it, the default
+    /// implementation returns `None`.
+    fn linear_layer_matrix(&self) -> Option<(Matrix, usize)> {
+        None
+    }
+
+    /// Return the number of equations of each round (see `RoundProfile`).
+    ///
+    /// The default implementation is for ciphers whose S-Box layer covers the whole state and
+    /// whose linear layers compute the whole state at each round.
+    fn equation_profile(&self) -> Vec<RoundProfile> {
+        let profile = RoundProfile {
+            sbox_relations: self.message_length() / self.sbox().in_size(),
+            linear_equations: self.message_length(),
+        };
+        vec![profile; self.n_rounds()]
+    }
+
+    /// Check the round constants generated by the cipher against published values, for the
+    /// rounds covered by the published values.
+    ///
+    /// The default implementation is for the ciphers without a reference to compare to and
+    /// always returns true.
+    fn verify_round_constants(&self) -> bool {
+        true
+    }
 }
 
+/// Build the system of the sponge `hash` and return it along with its output bits.
+///
+/// The state starts from `SpongeHash::initial_state`, whose constant bits (ex: an IV in the
+/// capacity) are folded into the bits produced by the hashing, so that no variable has to be
+/// fixed for them.
 pub fn build_system_sponge(hash: &dyn SpongeHash) -> (Vec<Bit>, System) {
+    assert!(
+        hash.initial_state()
+            .iter()
+            .all(|bit| bit.is_constant().is_some()),
+        "the initial state of the sponge should be made of constant bits"
+    );
     let mut message_bits = Vec::with_capacity(hash.message_length());
     for i in 0..hash.message_length() {
         message_bits.push(Bit::from_variable_id(i));
@@ -66,20 +213,237 @@ pub fn build_system_sponge(hash: &dyn SpongeHash) -> (Vec<Bit>, System) {
     (output, build_system_from_spec(system_spec))
 }
 
-pub fn build_system_cipher(cipher: &dyn Cipher) -> (Vec<Bit>, Vec<Bit>, System) {
-    let mut message_bits = Vec::with_capacity(cipher.message_length());
-    let mut key_bits = Vec::with_capacity(cipher.key_length());
-    for i in 0..cipher.key_length() {
-        key_bits.push(Bit::from_variable_id(i));
+/// Where the key, the tweak, the message and the output of a cipher are in the system
+/// built by `build_system_cipher`.
+///
+/// The variables of the key come first, followed by the ones of the tweak (empty if the
+/// cipher has none) and the ones of the message. The output bits are linear combinations
+/// of the variables of the system.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SystemLayout {
+    pub key_vars: Range<usize>,
+    pub tweak_vars: Range<usize>,
+    pub message_vars: Range<usize>,
+    pub output_bits: Vec<Bit>,
+}
+
+impl SystemLayout {
+    fn with_output_bits(cipher: &dyn Cipher, output_bits: Vec<Bit>) -> SystemLayout {
+        let tweak_end = cipher.key_length() + cipher.tweak_length();
+        SystemLayout {
+            key_vars: 0..cipher.key_length(),
+            tweak_vars: cipher.key_length()..tweak_end,
+            message_vars: tweak_end..tweak_end + cipher.message_length(),
+            output_bits,
+        }
     }
-    for i in cipher.key_length()..cipher.message_length() + cipher.key_length() {
-        message_bits.push(Bit::from_variable_id(i));
+
+    /// The bits of the key.
+    pub fn key_bits(&self) -> Vec<Bit> {
+        self.key_vars.clone().map(Bit::from_variable_id).collect()
+    }
+
+    /// The bits of the tweak.
+    pub fn tweak_bits(&self) -> Vec<Bit> {
+        self.tweak_vars.clone().map(Bit::from_variable_id).collect()
+    }
+
+    /// The bits of the message.
+    pub fn message_bits(&self) -> Vec<Bit> {
+        self.message_vars.clone().map(Bit::from_variable_id).collect()
+    }
+
+    /// Split a solution of the system in its labelled parts: the key, the tweak (if the cipher
+    /// has one), the message and the intermediate variables (the outputs of the S-Boxes).
+    pub fn label_solution<'a>(
+        &self,
+        solution: &'a [Option<bool>],
+    ) -> Vec<(&'static str, &'a [Option<bool>])> {
+        let mut parts = vec![("key", &solution[self.key_vars.clone()])];
+        if !self.tweak_vars.is_empty() {
+            parts.push(("tweak", &solution[self.tweak_vars.clone()]));
+        }
+        parts.push(("message", &solution[self.message_vars.clone()]));
+        parts.push(("intermediate", &solution[self.message_vars.end..]));
+        parts
     }
-    let output = cipher.encrypt(message_bits.clone(), key_bits);
+}
+
+/// Build the system of a cipher and return it along with its layout.
+pub fn build_system_cipher(cipher: &dyn Cipher) -> (SystemLayout, System) {
+    let mut layout = SystemLayout::with_output_bits(cipher, Vec::new());
+    layout.output_bits =
+        cipher.encrypt_with_tweak(layout.message_bits(), layout.key_bits(), layout.tweak_bits());
     let mut sbox = cipher.sbox();
     let bdds = sbox.bdds();
     let system_spec = SystemSpec::new(sbox.next_var_id(), bdds);
-    (message_bits, output, build_system_from_spec(system_spec))
+    (layout, build_system_from_spec(system_spec))
+}
+
+/// Build a `System` of `nvar` variables with one `Bdd` per linear relation of `relations`, a
+/// relation `(bits, rhs)` meaning that the XOR of the `bits` is equal to `rhs`.
+///
+/// Each `Bdd` has a single level, whose lhs is the XOR of the bits, above the sink. Together with
+/// the `Bdd`s of an S-Box (see `Sbox::bdds`) this is all that is needed to build the system of
+/// a cipher from its `Bit`s. The relations which always hold are skipped.
+///
+/// Panics if a relation never holds (the bits cancel out to a constant different from `rhs`)
+/// or if a bit has a variable out of the `nvar` variables.
+pub fn build_system_from_linear_relations(nvar: usize, relations: &[(Vec<Bit>, bool)]) -> System {
+    let mut bdds = Vec::with_capacity(relations.len());
+    for (index, (bits, rhs)) in relations.iter().enumerate() {
+        let sum = bits.iter().fold(bit!(false), |sum, bit| sum ^ bit.clone());
+        if let Some(constant) = sum.is_constant() {
+            assert_eq!(constant, *rhs, "the linear relation {} never holds", index);
+            continue;
+        }
+        let mut lhs: Vec<i64> = sum
+            .vars()
+            .map(|var| {
+                assert!(var.id() < nvar, "variable {} out of the {} variables", var.id(), nvar);
+                var.id() as i64
+            })
+            .collect();
+        if sum.constant() {
+            lhs.push(-1i64);
+        }
+        let (e0, e1) = if *rhs { (0, 2) } else { (2, 0) };
+        let levels = vec![
+            LevelSpec::new(lhs, vec![NodeSpec::new(Id::new(1), Id::new(e0), Id::new(e1))]),
+            LevelSpec::new(vec![], vec![NodeSpec::new(Id::new(2), Id::new(0), Id::new(0))]),
+        ];
+        bdds.push(BddSpec::new(Id::new(bdds.len()), levels));
+    }
+    build_system_from_spec(SystemSpec::new(nvar, bdds))
+}
+
+/// Same as `build_system_cipher`, but the output bits and the system are cached in `cache_dir`
+/// so that later builds of the same cipher can skip the encryption.
+///
+/// The cache is made of a `.bits` file of the output (see `bit::bits_to_bytes`) and a `.bdd`
+/// file of the system, named after `name`, the number of rounds and a hash of every input of
+/// the build: the version of cryptapath, the sizes of the cipher, its S-Box and the first
+/// variable free for the S-Boxes. A cache which can't be loaded is rebuilt. When the cache is
+/// used the cipher is not run, so its S-Box doesn't allocate any variable.
+pub fn build_system_cipher_cached(
+    cipher: &dyn Cipher,
+    name: &str,
+    cache_dir: &Path,
+) -> Result<(SystemLayout, System), Error> {
+    let sbox = cipher.sbox();
+    let mut hasher = DefaultHasher::new();
+    (
+        env!("CARGO_PKG_VERSION"),
+        name,
+        cipher.n_rounds(),
+        cipher.message_length(),
+        cipher.key_length(),
+        cipher.tweak_length(),
+        sbox.in_size(),
+        sbox.table(),
+        sbox.next_var_id(),
+    )
+        .hash(&mut hasher);
+    let stem = format!("{}_{}_{:016x}", name, cipher.n_rounds(), hasher.finish());
+    let bits_path = cache_dir.join(format!("{}.bits", stem));
+    let bdd_path = cache_dir.join(format!("{}.bdd", stem));
+    if let Ok(cached) = load_system_cipher(cipher, &bits_path, &bdd_path) {
+        return Ok(cached);
+    }
+    let (layout, system) = build_system_cipher(cipher);
+    fs::create_dir_all(cache_dir)?;
+    fs::write(&bits_path, bit::bits_to_bytes(&layout.output_bits))?;
+    print_system_to_file(&system, &bdd_path);
+    Ok((layout, system))
+}
+
+/// Load the output bits and the system cached by `build_system_cipher_cached`.
+fn load_system_cipher(
+    cipher: &dyn Cipher,
+    bits_path: &Path,
+    bdd_path: &Path,
+) -> Result<(SystemLayout, System), Error> {
+    let output = bit::bits_from_bytes(&fs::read(bits_path)?)?;
+    if output.len() != cipher.message_length() {
+        return Err(Error::new(ErrorKind::InvalidData, "the output has the wrong size"));
+    }
+    let spec = parse_system_spec(&fs::read_to_string(bdd_path)?)?;
+    Ok((SystemLayout::with_output_bits(cipher, output), build_system_from_spec(spec)))
+}
+
+/// The bits of the message and of the output of an encryption.
+pub type EncryptionBits = (Vec<Bit>, Vec<Bit>);
+
+/// Build the system of two encryptions sharing the key (and the tweak if the cipher has one)
+/// but with independent message variables, and return it along with the bits of the message
+/// and of the output of each encryption.
+///
+/// The variables are the ones of `build_system_cipher` for the first encryption, the message
+/// variables of the second encryption come last. Use `fix_input_difference` and
+/// `fix_output_difference` to model a differential.
+/// The cipher should not have been used to build a system before.
+pub fn build_system_cipher_pair(cipher: &dyn Cipher) -> (EncryptionBits, EncryptionBits, System) {
+    let key_bits: Vec<Bit> = (0..cipher.key_length()).map(Bit::from_variable_id).collect();
+    let tweak_end = cipher.key_length() + cipher.tweak_length();
+    let tweak_bits: Vec<Bit> = (cipher.key_length()..tweak_end)
+        .map(Bit::from_variable_id)
+        .collect();
+    let message_bits_a: Vec<Bit> = (tweak_end..tweak_end + cipher.message_length())
+        .map(Bit::from_variable_id)
+        .collect();
+    let start = cipher.sbox().next_var_id();
+    let output_a =
+        cipher.encrypt_with_tweak(message_bits_a.clone(), key_bits.clone(), tweak_bits.clone());
+    // the second encryption makes as many variables as the first one, its message
+    // variables are placed after them
+    let second_start = cipher.sbox().next_var_id();
+    let message_start = second_start + second_start - start;
+    let message_bits_b: Vec<Bit> = (message_start..message_start + cipher.message_length())
+        .map(Bit::from_variable_id)
+        .collect();
+    let output_b = cipher.encrypt_with_tweak(message_bits_b.clone(), key_bits, tweak_bits);
+    let mut sbox = cipher.sbox();
+    assert_eq!(sbox.next_var_id(), message_start);
+    let bdds = sbox.bdds();
+    let system_spec = SystemSpec::new(message_start + cipher.message_length(), bdds);
+    (
+        (message_bits_a, output_a),
+        (message_bits_b, output_b),
+        build_system_from_spec(system_spec),
+    )
+}
+
+/// Fix the XOR of the messages of the two encryptions of `build_system_cipher_pair` to `delta`.
+pub fn fix_input_difference(
+    system: &mut System,
+    delta: &[Bit],
+    input_bits_a: &[Bit],
+    input_bits_b: &[Bit],
+) {
+    fix_difference(system, delta, input_bits_a, input_bits_b);
+}
+
+/// Fix the XOR of the outputs of the two encryptions of `build_system_cipher_pair` to `delta`.
+pub fn fix_output_difference(
+    system: &mut System,
+    delta: &[Bit],
+    output_bits_a: &[Bit],
+    output_bits_b: &[Bit],
+) {
+    fix_difference(system, delta, output_bits_a, output_bits_b);
+}
+
+fn fix_difference(system: &mut System, delta: &[Bit], bits_a: &[Bit], bits_b: &[Bit]) {
+    assert_eq!(bits_a.len(), delta.len(), "the difference should have the size of the bits");
+    for ((bit_a, bit_b), delta_bit) in bits_a.iter().zip(bits_b).zip(delta) {
+        let difference = bit_a.clone() ^ bit_b.clone();
+        system
+            .fix(
+                difference.vars().map(|var| var.id()).collect(),
+                difference.constant() ^ delta_bit.constant(),
+            )
+            .unwrap();
+    }
 }
 
 pub fn get_random_sponge_output(hash: &dyn SpongeHash) -> (Vec<Bit>) {
@@ -147,12 +511,33 @@ pub fn get_sponge_output_with_partial_preimage(
     hash.hash(partial_preimage)
 }
 
+/// Fix the padding of the message of `hash` and its output to `hash_value`.
+///
+/// Returns an `Error` if the values leave the system without solution, there is then no
+/// preimage of this length.
 pub fn fix_system_values_sponge(
     hash: &dyn SpongeHash,
     system: &mut System,
     hash_value: &[Bit],
     output_bits: &[Bit],
-) {
+) -> Result<(), CrushError> {
+    let known_output: Vec<(usize, bool)> = hash_value
+        .iter()
+        .enumerate()
+        .map(|(i, expected_bit)| (i, expected_bit.constant()))
+        .collect();
+    fix_system_values_sponge_partial(hash, system, &known_output, output_bits)
+}
+
+/// Same as `fix_system_values_sponge`, but only the output bits at the positions of
+/// `known_output` are fixed (to the value paired with each position), the other output
+/// bits are left free. Useful for truncated hashes or partial image preimages.
+pub fn fix_system_values_sponge_partial(
+    hash: &dyn SpongeHash,
+    system: &mut System,
+    known_output: &[(usize, bool)],
+    output_bits: &[Bit],
+) -> Result<(), CrushError> {
     let padding_bit = {
         if hash.message_length() <= hash.rate_length() {
             hash.rate_length() - 1
@@ -161,16 +546,16 @@ pub fn fix_system_values_sponge(
         }
     };
     //fixing padding (every padding end with a one regardless of the message_length)
-    system.fix(vec![padding_bit], true).unwrap();
-    //fixing the value of the output
-    for (output_bit, expected_bit) in output_bits.iter().zip(hash_value) {
-        system
-            .fix(
-                output_bit.vars.iter().map(|var| var.id()).collect(),
-                output_bit.constant() ^ expected_bit.constant(),
-            )
-            .unwrap();
+    system.fix(vec![padding_bit], true)?;
+    //fixing the known bits of the output
+    for (position, expected_bit) in known_output.iter() {
+        let output_bit = &output_bits[*position];
+        system.fix(
+            output_bit.vars().map(|var| var.id()).collect(),
+            output_bit.constant() ^ expected_bit,
+        )?;
     }
+    Ok(())
 }
 
 pub fn fix_system_values_sponge_with_partial_preimage(
@@ -179,8 +564,8 @@ pub fn fix_system_values_sponge_with_partial_preimage(
     hash_value: &[Bit],
     output_bits: &[Bit],
     mut partial_preimage: (Vec<Bit>,Vec<usize>)
-) {
-    fix_system_values_sponge(hash, system, hash_value, output_bits);
+) -> Result<(), CrushError> {
+    fix_system_values_sponge(hash, system, hash_value, output_bits)?;
     let padding_bit = {
         if hash.message_length() <= hash.rate_length() {
             hash.rate_length() - 1
@@ -197,56 +582,342 @@ pub fn fix_system_values_sponge_with_partial_preimage(
     }
     //fixing the known bits of the preimage
     for known_bit in partial_preimage.1.iter() {
-        system
-            .fix(vec![*known_bit], partial_preimage.0[*known_bit].constant())
-            .unwrap();
+        system.fix(vec![*known_bit], partial_preimage.0[*known_bit].constant())?;
     }
+    Ok(())
 }
 
+/// Fix the message of `layout` to `plaintext` and its output to `ciphertext`.
+///
+/// An output bit may be implied by the message (ex: after too few rounds of KATAN32, some bits
+/// of the ciphertext still are bits of the plaintext), its equation is then skipped.
+///
+/// Panics if the values contradict the system, `ciphertext` not being the encryption of
+/// `plaintext` under any key.
 pub fn fix_system_values_cipher(
     system: &mut System,
+    layout: &SystemLayout,
     plaintext: &[Bit],
     ciphertext: &[Bit],
-    input_bits: &[Bit],
-    output_bits: &[Bit],
 ) {
-    for (plaintext_vars, plaintext_bits) in input_bits.iter().zip(plaintext) {
-        system
-            .fix(
-                plaintext_vars.vars.iter().map(|var| var.id()).collect(),
-                plaintext_vars.constant() ^ plaintext_bits.constant(),
+    let message_bits = layout.message_bits();
+    let eqs: Vec<(Vec<usize>, bool)> = message_bits
+        .iter()
+        .zip(plaintext)
+        .chain(layout.output_bits.iter().zip(ciphertext))
+        .map(|(vars, expected_bit)| {
+            (
+                vars.vars().map(|var| var.id()).collect(),
+                vars.constant() ^ expected_bit.constant(),
             )
-            .unwrap();
-    }
-    for (ciphertext_vars, expected_bit) in output_bits.iter().zip(ciphertext) {
-        system
-            .fix(
-                ciphertext_vars.vars.iter().map(|var| var.id()).collect(),
-                ciphertext_vars.constant() ^ expected_bit.constant(),
+        })
+        .collect();
+    system
+        .fix_many(&eqs)
+        .expect("the values contradict the equations of the system");
+}
+
+/// Fix the key of `layout` to `key` and its output to `ciphertext`, leaving the message (and the
+/// tweak if the cipher has one) unknown, to recover the plaintext of `ciphertext`.
+///
+/// As in `fix_system_values_cipher`, redundant equations are skipped and a contradiction
+/// panics.
+pub fn fix_system_values_cipher_with_key(
+    system: &mut System,
+    layout: &SystemLayout,
+    key: &[Bit],
+    ciphertext: &[Bit],
+) {
+    let key_bits = layout.key_bits();
+    let eqs: Vec<(Vec<usize>, bool)> = key_bits
+        .iter()
+        .zip(key)
+        .chain(layout.output_bits.iter().zip(ciphertext))
+        .map(|(vars, expected_bit)| {
+            (
+                vars.vars().map(|var| var.id()).collect(),
+                vars.constant() ^ expected_bit.constant(),
             )
-            .unwrap();
+        })
+        .collect();
+    system
+        .fix_many(&eqs)
+        .expect("the values contradict the equations of the system");
+}
+
+/// Fix the bits `bit_positions` of the state right after the S-Box layer of the round `round`
+/// to `values` (a partial state leakage), see `Cipher::state_variable`.
+///
+/// Returns an `Error` if the cipher doesn't expose the variables of this state, or if the values
+/// contradict the values already fixed in the system.
+pub fn fix_intermediate(
+    cipher: &dyn Cipher,
+    system: &mut System,
+    round: usize,
+    bit_positions: &[usize],
+    values: &[Bit],
+) -> Result<(), Error> {
+    assert_eq!(bit_positions.len(), values.len());
+    let mut equations = Vec::with_capacity(bit_positions.len());
+    for (pos, value) in bit_positions.iter().zip(values.iter()) {
+        match cipher.state_variable(round, *pos) {
+            Some(var) => equations.push((vec![var], value.constant())),
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("the state bit {} of round {} is not exposed", pos, round),
+                ))
+            }
+        }
     }
+    system.add_linear_constraints(&equations)?;
+    Ok(())
 }
 
+/// Same as `fix_system_values_cipher`, but also fix the key bits at the positions `partial_key.1`
+/// to their value in `partial_key.0` (see `fill_partial_value`).
 pub fn fix_system_values_cipher_with_partial_key(
     system: &mut System,
+    layout: &SystemLayout,
     plaintext: &[Bit],
     ciphertext: &[Bit],
     partial_key: (Vec<Bit>, Vec<usize>),
-    input_bits: &[Bit],
-    output_bits: &[Bit],
 ) {
-    // This assumes that the key variables are always the n first (from 1 to key_length)
-    // In pratice this is safe because we use this assumption everywhere but in case
-    // someone would like to tinker with the library this has to be taken into account.
     for known_bit in partial_key.1.iter() {
         system
-            .fix(vec![*known_bit], partial_key.0[*known_bit].constant())
+            .fix(
+                vec![layout.key_vars.start + *known_bit],
+                partial_key.0[*known_bit].constant(),
+            )
             .unwrap();
     }
-    fix_system_values_cipher(system, plaintext, ciphertext, input_bits, output_bits);
+    fix_system_values_cipher(system, layout, plaintext, ciphertext);
 }
 
+/// Load linear relations between the bits of a key of `key_length` bits from the file at `path`,
+/// in the format of `utils::parse_linear_constraints` with the positions of the key bits in place
+/// of the variables (ex: `0+1 = 0` for the first two bits being equal).
+///
+/// Returns an `Error` if the file is malformed or a position is out of the key.
+pub fn load_key_relations(
+    path: &PathBuf,
+    key_length: usize,
+) -> Result<Vec<(Vec<usize>, bool)>, Error> {
+    let relations = load_linear_constraints(path)?;
+    if let Some(pos) = relations
+        .iter()
+        .flat_map(|(bits, _)| bits.iter())
+        .find(|pos| **pos >= key_length)
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("key bit {} out of a key of {} bits", pos, key_length),
+        ));
+    }
+    Ok(relations)
+}
+
+/// Impose the linear `relations` between the bits of the key (see `load_key_relations`) on the
+/// system of a cipher, restricting the keys it can recover to an affine subspace.
+///
+/// Returns an `Error` without fixing anything if the relations contradict each other or the
+/// values already fixed in the system.
+pub fn fix_key_relations(
+    system: &mut System,
+    layout: &SystemLayout,
+    relations: &[(Vec<usize>, bool)],
+) -> Result<(), Error> {
+    let constraints: Vec<(Vec<usize>, bool)> = relations
+        .iter()
+        .map(|(bits, rhs)| {
+            let vars = bits.iter().map(|pos| layout.key_vars.start + pos).collect();
+            (vars, *rhs)
+        })
+        .collect();
+    system.add_linear_constraints(&constraints)?;
+    Ok(())
+}
+
+/// Return a random key satisfying the linear `relations` between its bits (see
+/// `load_key_relations`) and keeping the known bits of `partial_key` (see `fill_partial_value`),
+/// or `None` if no key satisfies them.
+///
+/// The relations and the known bits are reduced to a row echelon form: the bits which are not
+/// the pivot of a row are drawn at random and the pivots computed from them.
+pub fn random_key_with_relations(
+    partial_key: &(Vec<Bit>, Vec<usize>),
+    relations: &[(Vec<usize>, bool)],
+) -> Option<Vec<Bit>> {
+    let key_length = partial_key.0.len();
+    let mut rows: Vec<(Vec<bool>, bool)> = Vec::with_capacity(relations.len());
+    for (bits, rhs) in relations.iter() {
+        let mut row = vec![false; key_length];
+        bits.iter().for_each(|pos| row[*pos] ^= true);
+        rows.push((row, *rhs));
+    }
+    for pos in partial_key.1.iter() {
+        let mut row = vec![false; key_length];
+        row[*pos] = true;
+        rows.push((row, partial_key.0[*pos].constant()));
+    }
+    let mut pivots = Vec::new();
+    for col in 0..key_length {
+        let rank = pivots.len();
+        let pivot_row = match (rank..rows.len()).find(|i| rows[*i].0[col]) {
+            Some(i) => i,
+            None => continue,
+        };
+        rows.swap(rank, pivot_row);
+        let pivot = rows[rank].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i != rank && row.0[col] {
+                row.0.iter_mut().zip(pivot.0.iter()).for_each(|(a, b)| *a ^= b);
+                row.1 ^= pivot.1;
+            }
+        }
+        pivots.push(col);
+    }
+    if rows[pivots.len()..].iter().any(|(_, rhs)| *rhs) {
+        return None;
+    }
+    let mut key: Vec<bool> = bit::random_bits(key_length)
+        .iter()
+        .map(Bit::constant)
+        .collect();
+    // every pivot only appears in its row, so it only depends on the random bits
+    for ((row, rhs), pivot) in rows.iter().zip(pivots.iter()) {
+        key[*pivot] = row
+            .iter()
+            .zip(key.iter())
+            .enumerate()
+            .filter(|(pos, (set, _))| **set && pos != pivot)
+            .fold(*rhs, |value, (_, (_, bit))| value ^ bit);
+    }
+    Some(key.into_iter().map(|value| bit!(value)).collect())
+}
+
+/// Fix the tweak of a tweakable cipher along with the plaintext and ciphertext.
+pub fn fix_system_values_cipher_with_tweak(
+    system: &mut System,
+    layout: &SystemLayout,
+    plaintext: &[Bit],
+    ciphertext: &[Bit],
+    tweak: &[Bit],
+) {
+    assert_eq!(
+        layout.tweak_vars.len(),
+        tweak.len(),
+        "the provided tweak has a size different from the tweak expected by the chosen cipher"
+    );
+    for (var, tweak_bit) in layout.tweak_vars.clone().zip(tweak) {
+        system.fix(vec![var], tweak_bit.constant()).unwrap();
+    }
+    fix_system_values_cipher(system, layout, plaintext, ciphertext);
+}
+
+/// The names accepted by `build_cipher_by_name`.
+pub fn supported_ciphers() -> &'static [&'static str] {
+    &[
+        "skinny64128",
+        "skinny128128",
+        "lowmc64",
+        "lowmc128",
+        "lowmc256",
+        "miniaes2x2",
+        "miniaes4x4",
+        "present80",
+        "prince",
+        "prince-core",
+        "des",
+        "katan32",
+    ]
+}
+
+/// The names accepted by `build_sponge_by_name`.
+pub fn supported_sponges() -> &'static [&'static str] {
+    &[
+        "keccak",
+        "keccak-crunchy-1",
+        "keccak-crunchy-2",
+        "keccak-crunchy-3",
+    ]
+}
+
+/// A one-line description of the cipher `name`, `None` if it is not supported (see
+/// `supported_ciphers`).
+pub fn cipher_description(name: &str) -> Option<&'static str> {
+    match name {
+        "skinny64128" => Some("SKINNY-64-128, 64-bit block with a 128-bit tweakey"),
+        "skinny128128" => Some("SKINNY-128-128, 128-bit block with a 128-bit tweakey"),
+        "lowmc64" => Some("LowMC with 64-bit block, 80-bit key and a single S-Box per round"),
+        "lowmc128" => Some("LowMC with 128-bit block, 80-bit key and 31 S-Boxes per round"),
+        "lowmc256" => Some("LowMC with 256-bit block, 256-bit key and a single S-Box per round"),
+        "miniaes2x2" => Some("Mini-AES on a 2x2 state of 8-bit cells"),
+        "miniaes4x4" => Some("Mini-AES on a 4x4 state of 4-bit cells"),
+        "present80" => Some("PRESENT with an 80-bit key"),
+        "prince" => Some("PRINCE with its whitening keys"),
+        "prince-core" => Some("PRINCEcore, PRINCE without the whitening keys"),
+        "des" => Some("DES, the 64 bits of the key including its parity bits"),
+        "katan32" => Some("KATAN32, 32-bit block NLFSR cipher with an 80-bit key"),
+        _ => None,
+    }
+}
+
+/// A one-line description of the sponge `name`, `None` if it is not supported (see
+/// `supported_sponges`).
+pub fn sponge_description(name: &str) -> Option<&'static str> {
+    match name {
+        "keccak" => Some("Keccak with the given rounds, rate, capacity and lengths"),
+        "keccak-crunchy-1" => Some("preimage challenge of the Keccak crunchy contest, 1 round"),
+        "keccak-crunchy-2" => Some("preimage challenge of the Keccak crunchy contest, 2 rounds"),
+        "keccak-crunchy-3" => Some("collision challenge of the Keccak crunchy contest, 5 rounds"),
+        _ => None,
+    }
+}
+
+/// The parameters needed to build a `Cipher` (see `supported_ciphers` for the names).
+#[derive(Clone, Debug)]
+pub struct CipherConfig {
+    pub name: String,
+    pub rounds: usize,
+}
+
+impl CipherConfig {
+    /// Build the `Cipher`, returns `None` if the name is not supported.
+    pub fn build(&self) -> Option<Box<dyn Cipher>> {
+        build_cipher_by_name(&self.name, Some(self.rounds))
+    }
+}
+
+/// The parameters needed to build a `SpongeHash` (see `supported_sponges` for the names).
+#[derive(Clone, Debug)]
+pub struct SpongeConfig {
+    pub name: String,
+    pub rounds: usize,
+    pub message_length: usize,
+    pub output_length: usize,
+    pub rate: usize,
+    pub capacity: usize,
+}
+
+impl SpongeConfig {
+    /// Build the `SpongeHash`, returns `None` if the name is not supported.
+    pub fn build(&self) -> Option<Box<dyn SpongeHash>> {
+        build_sponge_by_name(
+            &self.name,
+            self.rounds,
+            self.message_length,
+            self.output_length,
+            self.rate,
+            self.capacity,
+        )
+    }
+}
+
+/// Build the `SpongeHash` `name` (see `supported_sponges`), returns `None` if the name is not
+/// supported.
+///
+/// The presets "keccak-crunchy-<level>" (see `Keccak::crunchy`) ignore the other arguments.
 pub fn build_sponge_by_name(
     name: &str,
     n_rounds: usize,
@@ -263,11 +934,91 @@ pub fn build_sponge_by_name(
             rate,
             capacity,
         ))),
+        _ => match name.strip_prefix("keccak-crunchy-")?.parse::<usize>() {
+            Ok(level) if (1..=Keccak::CRUNCHY_LEVELS).contains(&level) => {
+                Some(Box::new(Keccak::crunchy(level)))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// The most rounds the cipher `name` supports, given by its specification.
+///
+/// Returns `None` if the cipher is not supported or has no such bound (the lowmc constants are
+/// generated for any number of rounds).
+pub fn max_rounds_by_name(name: &str) -> Option<usize> {
+    match name {
+        "skinny64128" => Some(Skinny64::max_rounds(128)),
+        "skinny128128" => Some(Skinny128::max_rounds(128)),
+        "miniaes2x2" => Some(MiniAES2x2::MAX_ROUNDS),
+        "miniaes4x4" => Some(MiniAES4x4::MAX_ROUNDS),
+        "present80" => Some(Present80::MAX_ROUNDS),
+        "prince" | "prince-core" => Some(Prince::MAX_ROUNDS),
+        "des" => Some(DES::MAX_ROUNDS),
+        "katan32" => Some(Katan32::MAX_ROUNDS),
         _ => None,
     }
 }
 
-pub fn build_cipher_by_name(name: &str, rounds: usize) -> Option<Box<dyn Cipher>> {
+/// Check that the cipher `name` can be built with `rounds` rounds, so that a wrong number of
+/// rounds is reported before `build_cipher_by_name` panics.
+///
+/// Returns an `Error` if `rounds` is above the maximum of the cipher (see `max_rounds_by_name`)
+/// or if prince is given an odd number of rounds. An unsupported cipher is not an error here.
+pub fn check_rounds(name: &str, rounds: usize) -> Result<(), Error> {
+    if let Some(max_rounds) = max_rounds_by_name(name) {
+        if rounds > max_rounds {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} has at most {} rounds, got {}", name, max_rounds, rounds),
+            ));
+        }
+    }
+    if name.starts_with("prince") && rounds % 2 == 1 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} needs an even number of rounds, got {}", name, rounds),
+        ));
+    }
+    Ok(())
+}
+
+/// The number of rounds to build the cipher `name` with: `rounds` if given, the full number of
+/// rounds of the cipher otherwise (see `max_rounds_by_name`).
+///
+/// Returns an `Error` if the rounds are not valid for the cipher (see `check_rounds`), or if
+/// `rounds` is `None` and the cipher is not supported or has no full number of rounds.
+pub fn resolve_rounds(name: &str, rounds: Option<usize>) -> Result<usize, Error> {
+    let rounds = match rounds.or_else(|| max_rounds_by_name(name)) {
+        Some(rounds) => rounds,
+        None if supported_ciphers().contains(&name) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} has no full number of rounds, the rounds have to be given", name),
+            ))
+        }
+        None => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Cipher not supported. Supported ciphers: {}.",
+                    supported_ciphers().join(", ")
+                ),
+            ))
+        }
+    };
+    check_rounds(name, rounds)?;
+    Ok(rounds)
+}
+
+/// Build the cipher `name` (see `supported_ciphers`) with `rounds` rounds, or with its full
+/// number of rounds if `rounds` is `None` (see `max_rounds_by_name`).
+///
+/// Returns `None` if the cipher is not supported, or if `rounds` is `None` and the cipher has no
+/// full number of rounds.
+pub fn build_cipher_by_name(name: &str, rounds: Option<usize>) -> Option<Box<dyn Cipher>> {
+    let rounds = rounds.or_else(|| max_rounds_by_name(name))?;
     match name {
         "skinny64128" => Some(Box::new(Skinny64::new(128, rounds))),
         "skinny128128" => Some(Box::new(Skinny128::new(128, rounds))),
@@ -280,6 +1031,494 @@ pub fn build_cipher_by_name(name: &str, rounds: usize) -> Option<Box<dyn Cipher>
         "prince" => Some(Box::new(Prince::new(rounds, true))),
         "prince-core" => Some(Box::new(Prince::new(rounds, false))),
         "des" => Some(Box::new(DES::new(rounds))),
+        "katan32" => Some(Box::new(Katan32::new(rounds))),
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::bit::{self, Bit, BitOrder};
+    use crate::strategy::{execute_strategy_by_name, LogLevel};
+    use crate::targets::{
+        build_cipher_by_name, build_system_cipher, build_system_cipher_cached,
+        build_system_cipher_pair, build_system_from_linear_relations, check_rounds,
+        fill_partial_value, fix_input_difference, fix_intermediate, fix_key_relations,
+        fix_output_difference, fix_system_values_cipher, fix_system_values_cipher_with_key,
+        max_rounds_by_name,
+        des::DES, keccak, keccak::Keccak, linear_cipher::LinearCipher, miniaes2x2::MiniAES2x2,
+        miniaes4x4::MiniAES4x4, random_key_with_relations, resolve_rounds, supported_ciphers, supported_sponges, Cipher,
+        CipherConfig, SpongeConfig, SpongeHash,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn over_limit_rounds_are_rejected() {
+        for name in supported_ciphers().iter() {
+            let max_rounds = match max_rounds_by_name(name) {
+                Some(max_rounds) => max_rounds,
+                None => continue,
+            };
+            assert!(check_rounds(name, max_rounds).is_ok());
+            let err = check_rounds(name, max_rounds + 2).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("{} has at most {} rounds, got {}", name, max_rounds, max_rounds + 2)
+            );
+            let built = std::panic::catch_unwind(|| build_cipher_by_name(name, Some(max_rounds + 2)));
+            assert!(built.is_err(), "{} was built with too many rounds", name);
+        }
+        assert!(max_rounds_by_name("lowmc64").is_none());
+        assert_eq!(max_rounds_by_name("des"), Some(16));
+        assert!(check_rounds("prince", 3).is_err());
+        assert!(check_rounds("aes", 1000).is_ok());
+    }
+
+    #[test]
+    fn full_rounds_by_default() {
+        assert_eq!(build_cipher_by_name("des", None).unwrap().n_rounds(), 16);
+        assert_eq!(build_cipher_by_name("present80", None).unwrap().n_rounds(), 31);
+        for name in supported_ciphers().iter() {
+            match max_rounds_by_name(name) {
+                Some(max_rounds) => {
+                    let cipher = build_cipher_by_name(name, None).unwrap();
+                    assert_eq!(cipher.n_rounds(), max_rounds);
+                    assert_eq!(cipher.full_rounds(), max_rounds);
+                    assert_eq!(resolve_rounds(name, None).unwrap(), max_rounds);
+                }
+                None => {
+                    assert!(build_cipher_by_name(name, None).is_none());
+                    assert!(resolve_rounds(name, None).is_err());
+                    assert_eq!(build_cipher_by_name(name, Some(3)).unwrap().full_rounds(), 3);
+                }
+            }
+        }
+        assert_eq!(resolve_rounds("des", Some(4)).unwrap(), 4);
+        assert!(resolve_rounds("des", Some(17)).is_err());
+        assert!(resolve_rounds("aes", None).is_err());
+    }
+
+    #[test]
+    fn every_supported_target_builds() {
+        for name in supported_ciphers().iter() {
+            // prince needs an even number of rounds
+            let cipher = build_cipher_by_name(name, Some(2));
+            assert!(cipher.is_some(), "{} is not built", name);
+            assert_eq!(cipher.unwrap().n_rounds(), 2);
+        }
+        assert!(build_cipher_by_name("aes", Some(2)).is_none());
+        let config = CipherConfig {
+            name: "present80".to_string(),
+            rounds: 3,
+        };
+        assert_eq!(config.build().unwrap().n_rounds(), 3);
+        for name in supported_sponges().iter() {
+            let config = SpongeConfig {
+                name: name.to_string(),
+                rounds: 1,
+                message_length: 80,
+                output_length: 80,
+                rate: 640,
+                capacity: 160,
+            };
+            assert!(config.build().is_some(), "{} is not built", name);
+        }
+    }
+
+    #[test]
+    fn known_key_recovers_the_plaintext() {
+        let cipher = MiniAES2x2::new(1);
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        let (layout, mut system) = build_system_cipher(&cipher);
+        fix_system_values_cipher_with_key(&mut system, &layout, &key, &ciphertext);
+        let forbid_dropping: Vec<usize> = layout.message_vars.clone().collect();
+        let solutions =
+            execute_strategy_by_name("drop", &mut system, Some(&forbid_dropping), LogLevel::Quiet)
+                .unwrap()
+                .unwrap();
+        // the cipher is a permutation for a fixed key
+        assert_eq!(solutions.len(), 1);
+        let recovered: Vec<Bit> = solutions[0][layout.message_vars.clone()]
+            .iter()
+            .map(|var| Bit::from_value(var.unwrap()))
+            .collect();
+        assert_eq!(recovered, plaintext);
+        assert_eq!(cipher.encrypt(recovered, key), ciphertext);
+    }
+
+    #[test]
+    fn cipher_pair_recovers_the_keys_compatible_with_the_differential() {
+        let plaintext = bit::bits_from_hex_string_unchecked("0123456789abcdef");
+        let delta_in = bit::bits_from_hex_string_unchecked("000000000000f000");
+        let key = bit::bits_from_hex_string_unchecked("c9bd65504bf0f7f1");
+        // the first 8 bits of the key are unknown
+        let candidate_key = |guess: usize| {
+            let mut candidate = bit::bits_from_binary_string_unchecked(&format!("{:08b}", guess));
+            candidate.extend(key.iter().skip(8).cloned());
+            candidate
+        };
+        let output_difference = |key: Vec<Bit>| {
+            let cipher = MiniAES4x4::new(2);
+            let pair = bit::bit_vector_xoring(plaintext.clone(), delta_in.clone());
+            bit::bit_vector_xoring(
+                cipher.encrypt(plaintext.clone(), key.clone()),
+                cipher.encrypt(pair, key),
+            )
+        };
+        let delta_out = output_difference(key.clone());
+        let compatible: Vec<usize> = (0..256)
+            .filter(|guess| output_difference(candidate_key(*guess)) == delta_out)
+            .collect();
+
+        let cipher = MiniAES4x4::new(2);
+        let ((input_a, output_a), (input_b, output_b), mut system) =
+            build_system_cipher_pair(&cipher);
+        for (i, key_bit) in key.iter().enumerate().skip(8) {
+            system.fix(vec![i], key_bit.constant()).unwrap();
+        }
+        for (input_bit, plaintext_bit) in input_a.iter().zip(plaintext.iter()) {
+            system
+                .fix(
+                    input_bit.vars().map(|var| var.id()).collect(),
+                    plaintext_bit.constant(),
+                )
+                .unwrap();
+        }
+        fix_input_difference(&mut system, &delta_in, &input_a, &input_b);
+        fix_output_difference(&mut system, &delta_out, &output_a, &output_b);
+        let solutions = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        assert_eq!(system.count_solutions().unwrap(), compatible.len() as u128);
+        for solution in solutions.iter() {
+            let guess = solution
+                .iter()
+                .take(8)
+                .fold(0, |guess, bit| guess * 2 + bit.unwrap() as usize);
+            assert!(compatible.contains(&guess));
+        }
+    }
+
+    #[test]
+    fn cached_build_matches_a_fresh_build() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("cryptapath_cache_{}", std::process::id()));
+        let (fresh_layout, fresh_system) =
+            build_system_cipher(build_cipher_by_name("miniaes2x2", Some(1)).unwrap().as_ref());
+        for _ in 0..2 {
+            let cipher = build_cipher_by_name("miniaes2x2", Some(1)).unwrap();
+            let (layout, system) =
+                build_system_cipher_cached(cipher.as_ref(), "miniaes2x2", &cache_dir).unwrap();
+            assert_eq!(layout, fresh_layout);
+            assert!(system == fresh_system);
+        }
+        // a corrupt cache is rebuilt
+        for entry in std::fs::read_dir(&cache_dir).unwrap() {
+            std::fs::write(entry.unwrap().path(), "corrupt").unwrap();
+        }
+        for _ in 0..2 {
+            let cipher = build_cipher_by_name("miniaes2x2", Some(1)).unwrap();
+            let (layout, system) =
+                build_system_cipher_cached(cipher.as_ref(), "miniaes2x2", &cache_dir).unwrap();
+            assert_eq!(layout, fresh_layout);
+            assert!(system == fresh_system);
+        }
+        // another number of rounds doesn't use the same cache
+        let cipher = build_cipher_by_name("miniaes2x2", Some(2)).unwrap();
+        let (layout, system) =
+            build_system_cipher_cached(cipher.as_ref(), "miniaes2x2", &cache_dir).unwrap();
+        let (fresh_layout, fresh_system) =
+            build_system_cipher(build_cipher_by_name("miniaes2x2", Some(2)).unwrap().as_ref());
+        assert_eq!(layout, fresh_layout);
+        assert!(system == fresh_system);
+        assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 4);
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn present80_layout_ranges() {
+        let cipher = build_cipher_by_name("present80", Some(1)).unwrap();
+        let (layout, system) = build_system_cipher(cipher.as_ref());
+        assert_eq!(layout.key_vars, 0..80);
+        assert!(layout.tweak_vars.is_empty());
+        assert_eq!(layout.message_vars, 80..144);
+        assert!(layout.key_vars.end <= layout.message_vars.start);
+        assert_eq!(layout.output_bits.len(), cipher.message_length());
+        // the S-Box variables come after the message ones
+        assert!(layout
+            .output_bits
+            .iter()
+            .flat_map(|bit| bit.vars())
+            .all(|var| var.id() < system.get_nvar()));
+        assert!(layout
+            .output_bits
+            .iter()
+            .flat_map(|bit| bit.vars())
+            .any(|var| var.id() >= layout.message_vars.end));
+    }
+
+    #[test]
+    fn sbox_bdds_share_one_structure() {
+        let cipher = build_cipher_by_name("present80", Some(1)).unwrap();
+        let (_, system) = build_system_cipher(cipher.as_ref());
+        let counts = system.count_structurally_identical();
+        // the 16 S-Boxes of the round and the one of the key schedule
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts.values().next(), Some(&17));
+        // a constant XORed in the input of an S-Box changes the topology of its BDD, here the
+        // round counter of the key schedule
+        let cipher = build_cipher_by_name("present80", Some(2)).unwrap();
+        let (_, system) = build_system_cipher(cipher.as_ref());
+        let mut multiplicities: Vec<usize> =
+            system.count_structurally_identical().values().cloned().collect();
+        multiplicities.sort();
+        assert_eq!(multiplicities, vec![1, 33]);
+    }
+
+    #[test]
+    fn expose_the_round_keys() {
+        let cipher = build_cipher_by_name("miniaes2x2", Some(10)).unwrap();
+        let round_keys = cipher
+            .round_keys(bit::bits_from_hex_string_unchecked("c9bd6550"))
+            .unwrap();
+        assert_eq!(round_keys.len(), 11);
+        assert_eq!(bit::bits_to_hex_string(round_keys[0].clone()), "c9bd6550");
+        assert_eq!(bit::bits_to_hex_string(round_keys[1].clone()), "9b261f4f");
+        let cipher = build_cipher_by_name("miniaes4x4", Some(10)).unwrap();
+        let round_keys = cipher
+            .round_keys(bit::bits_from_hex_string_unchecked("c4de2cadef240c95"))
+            .unwrap();
+        assert_eq!(
+            bit::bits_to_hex_string(round_keys[0].clone()),
+            "c4de2cadef240c95"
+        );
+        // the key of the linear cipher is only added once, it has no key schedule
+        let cipher = LinearCipher::random(16, 1, 1);
+        assert!(cipher.round_keys(vec![bit!(false); cipher.key_length()]).is_none());
+    }
+
+    #[test]
+    fn decrypt_is_only_implemented_by_some_ciphers() {
+        let plaintext = bit::bits_from_hex_string_unchecked("06b4c5e1ff24e6bb");
+        let key = bit::random_bits(128);
+        let cipher = build_cipher_by_name("skinny64128", Some(4)).unwrap();
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        assert_eq!(cipher.decrypt(ciphertext, key), Some(plaintext));
+        let cipher = build_cipher_by_name("miniaes2x2", Some(1)).unwrap();
+        let (plaintext, key) = (bit::random_bits(32), bit::random_bits(32));
+        let ciphertext = cipher.encrypt(plaintext, key.clone());
+        assert!(cipher.decrypt(ciphertext, key).is_none());
+    }
+
+    #[test]
+    fn full_solution_holds_the_intermediate_states() {
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let cipher = MiniAES2x2::new(1);
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        let (layout, mut system) = build_system_cipher(&cipher);
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        let solutions = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        assert!(!solutions.is_empty());
+        for solution in solutions.iter() {
+            let parts = layout.label_solution(solution);
+            let labels: Vec<&str> = parts.iter().map(|(label, _)| *label).collect();
+            assert_eq!(labels, vec!["key", "message", "intermediate"]);
+            let to_bits = |part: &[Option<bool>]| -> Vec<Bit> {
+                part.iter().map(|var| bit!(var.unwrap())).collect()
+            };
+            let solution_key = to_bits(parts[0].1);
+            assert_eq!(to_bits(parts[1].1), plaintext);
+            // re-encrypting the message gives back the state after the S-Box layer
+            let state = MiniAES2x2::new(1)
+                .sbox()
+                .apply_layer(&bit::bit_vector_xoring(plaintext.clone(), solution_key));
+            for (pos, value) in state.iter().enumerate() {
+                let var = cipher.state_variable(0, pos).unwrap();
+                assert!(layout.message_vars.end <= var);
+                assert_eq!(solution[var], value.is_constant());
+            }
+            let outputs: Vec<Option<bool>> = layout
+                .output_bits
+                .iter()
+                .map(|bit| bit.evaluate(solution))
+                .collect();
+            assert_eq!(outputs, ciphertext.iter().map(Bit::is_constant).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn evaluating_the_symbolic_ciphertext_gives_the_ciphertext() {
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        let cipher = MiniAES2x2::new(1);
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        let (layout, mut system) = build_system_cipher(&cipher);
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        // the S-Boxes introduce new variables, their values are taken from a solution
+        let solution = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .find(|solution| {
+                solution[layout.key_vars.clone()]
+                    .iter()
+                    .zip(key.iter())
+                    .all(|(var, bit)| *var == bit.is_constant())
+            })
+            .unwrap();
+        let assignment: HashMap<usize, bool> = solution
+            .iter()
+            .enumerate()
+            .filter_map(|(var, value)| value.map(|value| (var, value)))
+            .collect();
+        let substituted = bit::substitute(&layout.output_bits, &assignment);
+        assert_eq!(substituted, ciphertext);
+        // with the key only, the variables of the S-Boxes are left in the ciphertext
+        let key_assignment: HashMap<usize, bool> = assignment
+            .into_iter()
+            .filter(|(var, _)| layout.key_vars.contains(var))
+            .collect();
+        assert!(bit::substitute(&layout.output_bits, &key_assignment)
+            .iter()
+            .all(|bit| bit.is_constant().is_none()
+                && bit.vars().all(|var| var.id() >= layout.message_vars.end)));
+    }
+
+    #[test]
+    fn system_from_linear_relations() {
+        let x = |id: usize| Bit::from_variable_id(id);
+        let relations = vec![
+            (vec![x(0), x(1)], true),
+            (vec![x(1) ^ bit!(true), x(2)], false),
+            (vec![x(0), x(1), x(2)], false),
+            // x0 + x0 + 1 = 1 always holds
+            (vec![x(0), x(0), bit!(true)], true),
+        ];
+        let mut system = build_system_from_linear_relations(3, &relations);
+        assert_eq!(system.iter_bdds().len(), 3);
+        let solutions = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        assert_eq!(solutions, vec![vec![Some(true), Some(false), Some(true)]]);
+        for (bits, rhs) in relations.iter() {
+            let sum = bits.iter().fold(bit!(false), |sum, bit| sum ^ bit.clone());
+            assert_eq!(sum.evaluate(&solutions[0]), Some(*rhs));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the linear relation 0 never holds")]
+    fn contradicting_linear_relation() {
+        let x = Bit::from_variable_id(0);
+        build_system_from_linear_relations(1, &[(vec![x.clone(), x], true)]);
+    }
+
+    #[test]
+    fn key_relations_restrict_the_recovered_keys() {
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        // k0 = k1, k2 = 0 and k4 = 1 hold for the key
+        let relations = vec![(vec![0, 1], false), (vec![2], false), (vec![4], true)];
+        let cipher = MiniAES2x2::new(1);
+        let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+        let (layout, mut system) = build_system_cipher(&cipher);
+        fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+        fix_key_relations(&mut system, &layout, &relations).unwrap();
+        let solutions = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        let keys: Vec<Vec<Option<bool>>> = solutions
+            .iter()
+            .map(|solution| solution[layout.key_vars.clone()].to_vec())
+            .collect();
+        assert!(keys.contains(&key.iter().map(Bit::is_constant).collect()));
+        for key in keys.iter() {
+            for (bits, rhs) in relations.iter() {
+                let value = bits.iter().fold(false, |value, pos| value ^ key[*pos].unwrap());
+                assert_eq!(value, *rhs);
+            }
+        }
+        // k2 = 1 contradicts the relations
+        assert!(fix_key_relations(&mut system, &layout, &[(vec![2], true)]).is_err());
+    }
+
+    #[test]
+    fn random_key_satisfies_the_relations() {
+        let relations = vec![(vec![0, 1], false), (vec![1, 2, 5], true), (vec![3, 5], true)];
+        for _ in 0..10 {
+            let partial_key = fill_partial_value("xxxx1x0x");
+            let key = random_key_with_relations(&partial_key, &relations).unwrap();
+            assert_eq!(key.len(), 8);
+            assert_eq!(key[4], bit!(true));
+            assert_eq!(key[6], bit!(false));
+            for (bits, rhs) in relations.iter() {
+                let value = bits.iter().fold(false, |value, pos| value ^ key[*pos].constant());
+                assert_eq!(value, *rhs);
+            }
+        }
+        // k0 = k1 and k0 + k1 = 1
+        let contradiction = vec![(vec![0, 1], false), (vec![1, 0], true)];
+        assert!(random_key_with_relations(&fill_partial_value("xx"), &contradiction).is_none());
+        assert!(random_key_with_relations(&fill_partial_value("10"), &relations[..1]).is_none());
+    }
+
+    #[test]
+    fn leaked_state_bits_shrink_the_key_set() {
+        let plaintext = bit::bits_from_hex_string_unchecked("f721f4df");
+        let key = bit::bits_from_hex_string_unchecked("c9bd6550");
+        // the state after the first S-Box layer, the first round key being the key
+        let leaked_state = MiniAES2x2::new(1)
+            .sbox()
+            .apply_layer(&bit::bit_vector_xoring(plaintext.clone(), key.clone()));
+        let recovered_keys = |leaked_bits: usize| {
+            let cipher = MiniAES2x2::new(1);
+            let ciphertext = cipher.encrypt(plaintext.clone(), key.clone());
+            let (layout, mut system) = build_system_cipher(&cipher);
+            fix_system_values_cipher(&mut system, &layout, &plaintext, &ciphertext);
+            let positions: Vec<usize> = (0..leaked_bits).collect();
+            fix_intermediate(
+                &cipher,
+                &mut system,
+                0,
+                &positions,
+                &leaked_state[..leaked_bits],
+            )
+            .unwrap();
+            assert!(fix_intermediate(&cipher, &mut system, 1, &[0], &[bit!(false)]).is_err());
+            let mut keys: Vec<Vec<Option<bool>>> =
+                execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+                    .unwrap()
+                    .unwrap()
+                    .into_iter()
+                    .map(|solution| solution[..cipher.key_length()].to_vec())
+                    .collect();
+            keys.sort();
+            keys.dedup();
+            keys
+        };
+        let expected_key: Vec<Option<bool>> = key.iter().map(|bit| Some(bit.constant())).collect();
+        let without_leak = recovered_keys(0);
+        let with_leak = recovered_keys(8);
+        assert!(without_leak.contains(&expected_key));
+        assert_eq!(with_leak, vec![expected_key]);
+        assert!(with_leak.len() < without_leak.len());
+    }
+
+    #[test]
+    fn published_vectors_parse_in_the_order_of_each_target() {
+        let des = DES::new(16);
+        let parse = |hex| bit::bits_from_hex_string_with_order(hex, des.hex_bit_order()).unwrap();
+        let ciphertext = des.encrypt(parse("95f8a5e5dd31d900"), parse("0101010101010101"));
+        assert_eq!(bit::bits_to_hex_string(ciphertext), "8000000000000000");
+        let keccak = Keccak::crunchy(1);
+        assert_eq!(keccak.hex_bit_order(), BitOrder::Lsb0);
+        let hash = bit::bits_from_hex_string_with_order("e9f57f02a9b0ebd84498", keccak.hex_bit_order())
+            .unwrap();
+        assert_eq!(hash, keccak::bits_from_hex_string_keccak("e9f57f02a9b0ebd84498"));
+    }
+}
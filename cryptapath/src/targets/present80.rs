@@ -11,43 +11,49 @@ pub struct Present80 {
 }
 
 impl Present80 {
+    /// The number of rounds of the specification, and the most this implementation supports.
+    pub const MAX_ROUNDS: usize = 31;
+
     pub fn new(n_rounds: usize) -> Self {
+        assert!(
+            n_rounds <= Self::MAX_ROUNDS,
+            "present80 has at most {} rounds, got {}",
+            Self::MAX_ROUNDS,
+            n_rounds
+        );
         let table = vec![
             0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2,
         ];
         let message_length = 64;
         let key_length = 80;
+        // bit i goes to position p_layer[i], as given in the specification
         let p_layer = vec![
             0, 16, 32, 48, 1, 17, 33, 49, 2, 18, 34, 50, 3, 19, 35, 51, 4, 20, 36, 52, 5, 21, 37,
             53, 6, 22, 38, 54, 7, 23, 39, 55, 8, 24, 40, 56, 9, 25, 41, 57, 10, 26, 42, 58, 11, 27,
             43, 59, 12, 28, 44, 60, 13, 29, 45, 61, 14, 30, 46, 62, 15, 31, 47, 63,
         ];
+        // position i holds bit p_layer_inv[i], as expected by bit::permute
+        let mut p_layer_inv = vec![0; message_length];
+        for (i, perm) in p_layer.iter().enumerate() {
+            p_layer_inv[*perm] = i;
+        }
         Present80 {
             n_rounds,
             message_length,
             key_length,
             sbox: Sbox::new(4, 4, table, message_length + key_length),
-            p_layer,
+            p_layer: p_layer_inv,
         }
     }
 
     fn p_layer(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
         assert_eq!(in_bits.len(), self.message_length);
-        let mut out_bits = vec![bit!(false);64];
-        for (i,in_bit) in in_bits.iter().enumerate() {
-            let perm = self.p_layer[i];
-            out_bits[perm] = in_bit.clone();
-        }
-        out_bits
+        bit::permute(&in_bits, &self.p_layer)
     }
 
     fn sbox_layer(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
         assert_eq!(in_bits.len(), self.message_length);
-        let mut out_bits = Vec::with_capacity(self.message_length);
-        for i in 0..16 {
-            out_bits.append(&mut self.sbox.apply(in_bits[i * 4..(i + 1) * 4].to_vec()));
-        }
-        out_bits
+        self.sbox.apply_layer(&in_bits)
     }
 
     fn add_round_key(&self, in_bits: Vec<Bit>, round_key: Vec<Bit>) -> Vec<Bit> {
@@ -66,7 +72,7 @@ impl Present80 {
             key.append(&mut left_part);
             let box_part = self.sbox.apply(key[0..4].to_vec());
             key[..4].clone_from_slice(&box_part[..4]);
-            let round_counter = bit::bits_from_binary_string(&format!("{:05b}",round));
+            let round_counter = bit::bits_from_binary_string_unchecked(&format!("{:05b}",round));
             for bit in 0..5 {
                 key[60+bit] ^= round_counter[bit].clone();
             }
@@ -88,6 +94,10 @@ impl Cipher for Present80 {
         self.add_round_key(out_bits, round_keys[self.n_rounds].clone())
     }
 
+    fn round_keys(&self, key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        Some(self.make_round_keys(key))
+    }
+
     fn message_length(&self) -> usize {
         self.message_length
     }
@@ -100,6 +110,10 @@ impl Cipher for Present80 {
         self.n_rounds
     }
 
+    fn full_rounds(&self) -> usize {
+        Self::MAX_ROUNDS
+    }
+
     fn sbox(&self) -> Sbox {
         self.sbox.clone()
     }
@@ -115,23 +129,23 @@ mod test {
 #[test]
 fn validate_encrypt() {
     let present = Present80::new(31);
-    let message = bit::bits_from_hex_string("0000000000000000");
-    let key = bit::bits_from_hex_string("00000000000000000000");
+    let message = bit::bits_from_hex_string_unchecked("0000000000000000");
+    let key = bit::bits_from_hex_string_unchecked("00000000000000000000");
     let ciphertext = present.encrypt(message, key);
     assert_eq!("5579c1387b228445", bit::bits_to_hex_string(ciphertext));
 
-    let message = bit::bits_from_hex_string("0000000000000000");
-    let key = bit::bits_from_hex_string("FFFFFFFFFFFFFFFFFFFF");
+    let message = bit::bits_from_hex_string_unchecked("0000000000000000");
+    let key = bit::bits_from_hex_string_unchecked("FFFFFFFFFFFFFFFFFFFF");
     let ciphertext = present.encrypt(message, key);
     assert_eq!("e72c46c0f5945049", bit::bits_to_hex_string(ciphertext));
 
-    let message = bit::bits_from_hex_string("FFFFFFFFFFFFFFFF");
-    let key = bit::bits_from_hex_string("00000000000000000000");
+    let message = bit::bits_from_hex_string_unchecked("FFFFFFFFFFFFFFFF");
+    let key = bit::bits_from_hex_string_unchecked("00000000000000000000");
     let ciphertext = present.encrypt(message, key);
     assert_eq!("a112ffc72f68417b", bit::bits_to_hex_string(ciphertext));
 
-    let message = bit::bits_from_hex_string("FFFFFFFFFFFFFFFF");
-    let key = bit::bits_from_hex_string("FFFFFFFFFFFFFFFFFFFF");
+    let message = bit::bits_from_hex_string_unchecked("FFFFFFFFFFFFFFFF");
+    let key = bit::bits_from_hex_string_unchecked("FFFFFFFFFFFFFFFFFFFF");
     let ciphertext = present.encrypt(message, key);
     assert_eq!("3333dcd3213210d2", bit::bits_to_hex_string(ciphertext));
 }
@@ -1,6 +1,7 @@
 use crate::sbox::Sbox;
 use crate::targets::Cipher;
 use crate::{bit, bit::Bit, bit::*};
+use crush::algebra::Matrix;
 use std::cell::RefCell;
 
 pub struct Prince {
@@ -31,12 +32,20 @@ macro_rules! binary_matrix {
 }
 
 impl Prince {
+    /// The number of rounds of the specification, and the most this implementation supports.
+    pub const MAX_ROUNDS: usize = 12;
+
     pub fn new(n_rounds: usize, whitening: bool) -> Self {
         assert!(
             n_rounds % 2 == 0,
             "to preserve the structure of prince, the number of round should be even"
         );
-        assert!(n_rounds <= 12);
+        assert!(
+            n_rounds <= Self::MAX_ROUNDS,
+            "prince has at most {} rounds, got {}",
+            Self::MAX_ROUNDS,
+            n_rounds
+        );
         let table = vec![
             0xb, 0xf, 0x3, 0x2, 0xa, 0xc, 0x9, 0x1, 0x6, 0x7, 0x8, 0x0, 0xe, 0x5, 0xd, 0x4,
         ];
@@ -46,18 +55,18 @@ impl Prince {
         let message_length = 64;
         let key_length = if whitening { 128 } else { 64 };
         let constants = vec![
-            bit::bits_from_hex_string("0000000000000000"),
-            bit::bits_from_hex_string("13198a2e03707344"),
-            bit::bits_from_hex_string("a4093822299f31d0"),
-            bit::bits_from_hex_string("082efa98ec4e6c89"),
-            bit::bits_from_hex_string("452821e638d01377"),
-            bit::bits_from_hex_string("be5466cf34e90c6c"),
-            bit::bits_from_hex_string("7ef84f78fd955cb1"),
-            bit::bits_from_hex_string("85840851f1ac43aa"),
-            bit::bits_from_hex_string("c882d32f25323c54"),
-            bit::bits_from_hex_string("64a51195e0e3610d"),
-            bit::bits_from_hex_string("d3b5a399ca0c2399"),
-            bit::bits_from_hex_string("c0ac29b7c97c50dd"),
+            bit::bits_from_hex_string_unchecked("0000000000000000"),
+            bit::bits_from_hex_string_unchecked("13198a2e03707344"),
+            bit::bits_from_hex_string_unchecked("a4093822299f31d0"),
+            bit::bits_from_hex_string_unchecked("082efa98ec4e6c89"),
+            bit::bits_from_hex_string_unchecked("452821e638d01377"),
+            bit::bits_from_hex_string_unchecked("be5466cf34e90c6c"),
+            bit::bits_from_hex_string_unchecked("7ef84f78fd955cb1"),
+            bit::bits_from_hex_string_unchecked("85840851f1ac43aa"),
+            bit::bits_from_hex_string_unchecked("c882d32f25323c54"),
+            bit::bits_from_hex_string_unchecked("64a51195e0e3610d"),
+            bit::bits_from_hex_string_unchecked("d3b5a399ca0c2399"),
+            bit::bits_from_hex_string_unchecked("c0ac29b7c97c50dd"),
         ];
         let m_prime = binary_matrix![
         //M0
@@ -214,29 +223,12 @@ impl Prince {
         round_keys.push(k0_prime);
         round_keys
     }
-}
 
-fn multiply_with_gf2_matrix(matrix: &[String], in_bits: &[Bit]) -> Vec<Bit> {
-    let mut out_bits = Vec::with_capacity(in_bits.len());
-    for row in matrix {
-        let r = row.chars().collect::<Vec<char>>();
-        let mut tmp = bit!(false);
-        for column in 0..64 {
-            match r[column] {
-                '1' => tmp ^= in_bits[column].clone(),
-                '0' => (),
-                _ => panic!("non binary character in binary string"),
-            };
-        }
-        out_bits.push(tmp)
-    }
-    out_bits
-}
+    /// Encrypt `in_bits` with the whitening keys `round_keys[0]` and `round_keys[2]` and the
+    /// core key `round_keys[1]`.
+    fn encrypt_with_round_keys(&self, in_bits: Vec<Bit>, round_keys: Vec<Vec<Bit>>) -> Vec<Bit> {
+        let mut out_bits = in_bits;
 
-impl Cipher for Prince {
-    fn encrypt(&self, in_bits: Vec<Bit>, key_bits: Vec<Bit>) -> Vec<Bit> {
-        let round_keys = self.make_round_keys(key_bits);
-        let mut out_bits = in_bits.clone();
         if self.whitening {
             out_bits = self.add_round_key(out_bits, round_keys[0].clone());
         }
@@ -281,6 +273,42 @@ impl Cipher for Prince {
         ));
         out_bits
     }
+}
+
+fn multiply_with_gf2_matrix(matrix: &[String], in_bits: &[Bit]) -> Vec<Bit> {
+    let mut out_bits = Vec::with_capacity(in_bits.len());
+    for row in matrix {
+        let r = row.chars().collect::<Vec<char>>();
+        let mut tmp = bit!(false);
+        for column in 0..64 {
+            match r[column] {
+                '1' => tmp ^= in_bits[column].clone(),
+                '0' => (),
+                _ => panic!("non binary character in binary string"),
+            };
+        }
+        out_bits.push(tmp)
+    }
+    out_bits
+}
+
+impl Cipher for Prince {
+    fn encrypt(&self, in_bits: Vec<Bit>, key_bits: Vec<Bit>) -> Vec<Bit> {
+        self.encrypt_with_round_keys(in_bits, self.make_round_keys(key_bits))
+    }
+
+    /// Prince has the alpha-reflection property: decrypting under (k0, k1, k0') is encrypting
+    /// under (k0', k1 ^ alpha, k0), with alpha the last round constant.
+    fn decrypt(&self, in_bits: Vec<Bit>, key_bits: Vec<Bit>) -> Option<Vec<Bit>> {
+        let mut round_keys = self.make_round_keys(key_bits);
+        round_keys.swap(0, 2);
+        round_keys[1] = bit_vector_xoring(round_keys[1].clone(), self.constants[11].clone());
+        Some(self.encrypt_with_round_keys(in_bits, round_keys))
+    }
+
+    fn round_keys(&self, key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        Some(self.make_round_keys(key))
+    }
 
     fn message_length(&self) -> usize {
         self.message_length
@@ -294,9 +322,24 @@ impl Cipher for Prince {
         self.n_rounds
     }
 
+    fn full_rounds(&self) -> usize {
+        Self::MAX_ROUNDS
+    }
+
     fn sbox(&self) -> Sbox {
         self.sbox.borrow().clone()
     }
+
+    /// The matrix M' mixing the nibbles of each column, the shift of the rows of M doesn't
+    /// change the number of nonzero nibbles.
+    fn linear_layer_matrix(&self) -> Option<(Matrix, usize)> {
+        let rows = self
+            .m_prime
+            .iter()
+            .map(|row| row.chars().map(|c| c == '1').collect())
+            .collect();
+        Some((Matrix::from_rows(rows), 4))
+    }
 }
 
 // from https://eprint.iacr.org/2012/529.pdf
@@ -305,37 +348,82 @@ impl Cipher for Prince {
 mod test {
     use crate::bit;
     use crate::targets::{prince::Prince, Cipher};
+    use crush::algebra;
 
     #[test]
     fn validate_encrypt() {
         let prince = Prince::new(12, true);
-        let message = bit::bits_from_hex_string("0000000000000000");
-        let key = bit::bits_from_hex_string("00000000000000000000000000000000");
+        let message = bit::bits_from_hex_string_unchecked("0000000000000000");
+        let key = bit::bits_from_hex_string_unchecked("00000000000000000000000000000000");
         let ciphertext = prince.encrypt(message, key);
         assert_eq!("818665aa0d02dfda", bit::bits_to_hex_string(ciphertext));
 
         let prince = Prince::new(12, true);
-        let message = bit::bits_from_hex_string("ffffffffffffffff");
-        let key = bit::bits_from_hex_string("00000000000000000000000000000000");
+        let message = bit::bits_from_hex_string_unchecked("ffffffffffffffff");
+        let key = bit::bits_from_hex_string_unchecked("00000000000000000000000000000000");
         let ciphertext = prince.encrypt(message, key);
         assert_eq!("604ae6ca03c20ada", bit::bits_to_hex_string(ciphertext));
 
         let prince = Prince::new(12, true);
-        let message = bit::bits_from_hex_string("0000000000000000");
-        let key = bit::bits_from_hex_string("ffffffffffffffff0000000000000000");
+        let message = bit::bits_from_hex_string_unchecked("0000000000000000");
+        let key = bit::bits_from_hex_string_unchecked("ffffffffffffffff0000000000000000");
         let ciphertext = prince.encrypt(message, key);
         assert_eq!("9fb51935fc3df524", bit::bits_to_hex_string(ciphertext));
 
         let prince = Prince::new(12, true);
-        let message = bit::bits_from_hex_string("0000000000000000");
-        let key = bit::bits_from_hex_string("0000000000000000ffffffffffffffff");
+        let message = bit::bits_from_hex_string_unchecked("0000000000000000");
+        let key = bit::bits_from_hex_string_unchecked("0000000000000000ffffffffffffffff");
         let ciphertext = prince.encrypt(message, key);
         assert_eq!("78a54cbe737bb7ef", bit::bits_to_hex_string(ciphertext));
 
         let prince = Prince::new(12, true);
-        let message = bit::bits_from_hex_string("0123456789abcdef");
-        let key = bit::bits_from_hex_string("0000000000000000fedcba9876543210");
+        let message = bit::bits_from_hex_string_unchecked("0123456789abcdef");
+        let key = bit::bits_from_hex_string_unchecked("0000000000000000fedcba9876543210");
         let ciphertext = prince.encrypt(message, key);
         assert_eq!("ae25ad3ca8fa9ccf", bit::bits_to_hex_string(ciphertext));
     }
+
+    #[test]
+    fn decrypt_inverts_encrypt() {
+        let vectors = [
+            ("0000000000000000", "00000000000000000000000000000000"),
+            ("ffffffffffffffff", "00000000000000000000000000000000"),
+            ("0000000000000000", "ffffffffffffffff0000000000000000"),
+            ("0000000000000000", "0000000000000000ffffffffffffffff"),
+            ("0123456789abcdef", "0000000000000000fedcba9876543210"),
+        ];
+        for (message, key) in vectors.iter() {
+            let prince = Prince::new(12, true);
+            let message = bit::bits_from_hex_string_unchecked(message);
+            let key = bit::bits_from_hex_string_unchecked(key);
+            let ciphertext = prince.encrypt(message.clone(), key.clone());
+            assert_eq!(message, prince.decrypt(ciphertext, key).unwrap());
+        }
+    }
+
+    #[test]
+    fn decrypt_is_encrypt_with_alpha_in_the_key() {
+        let alpha = bit::bits_from_hex_string_unchecked("c0ac29b7c97c50dd");
+        let key = bit::bits_from_hex_string_unchecked("fedcba9876543210");
+        let ciphertext = bit::bits_from_hex_string_unchecked("0123456789abcdef");
+        for n_rounds in [4, 12].iter() {
+            let prince = Prince::new(*n_rounds, false);
+            assert_eq!(
+                prince.decrypt(ciphertext.clone(), key.clone()).unwrap(),
+                prince.encrypt(
+                    ciphertext.clone(),
+                    bit::bit_vector_xoring(key.clone(), alpha.clone())
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn m_prime_has_branch_number_4() {
+        // M' is made of the 16x16 blocks M0 and M1 of the specification, a nonzero nibble in
+        // a column gives a nonzero nibble in three of the columns already
+        let (m_prime, word_size) = Prince::new(12, true).linear_layer_matrix().unwrap();
+        assert_eq!((m_prime.row_size(), m_prime.column_size()), (64, 64));
+        assert_eq!(algebra::branch_number(&m_prime, word_size), 4);
+    }
 }
@@ -1,21 +1,53 @@
 use crate::sbox::Sbox;
-use crate::targets::Cipher;
+use crate::targets::skinny64::{round_constant_value, ROUND_CONSTANTS};
+use crate::targets::{Cipher, TweakeyWord};
 use crate::{bit, bit::Bit, bit::*};
 
 pub struct Skinny128 {
     n_rounds: usize,
     message_length: usize,
     key_length: usize,
+    tweak_length: usize,
     sbox: Sbox,
 }
 
 impl Skinny128 {
+    /// The number of rounds of the specification for a tweakey of `tweakey_length` bits
+    /// (40 for one tweakey word, 8 more for each additional word).
+    pub fn max_rounds(tweakey_length: usize) -> usize {
+        40 + 8 * (tweakey_length / 128 - 1)
+    }
+
     pub fn new(key_length: usize, n_rounds: usize) -> Self {
+        Self::with_tweak(key_length, 0, n_rounds)
+    }
+
+    /// Build a Skinny instance where the first `tweak_length` bits of the tweakey
+    /// are a public tweak and the following `key_length` bits are the secret key.
+    ///
+    /// The tweak occupies the first tweakey words (starting with TK1, as in SKINNY-AEAD),
+    /// see `tweakey_words`.
+    pub fn with_tweak(key_length: usize, tweak_length: usize, n_rounds: usize) -> Self {
         let message_length = 128;
+        let tweakey_length = key_length + tweak_length;
+        assert!(
+            tweakey_length == message_length
+                || tweakey_length == message_length * 2
+                || tweakey_length == message_length * 3
+        );
         assert!(
-            key_length == message_length
-                || key_length == message_length * 2
-                || key_length == message_length * 3
+            tweak_length < tweakey_length
+                && (tweak_length == 0
+                    || tweak_length == message_length
+                    || tweak_length == message_length * 2),
+            "the tweak should be made of whole tweakey words and leave at least one for the key"
+        );
+        assert!(
+            n_rounds <= Self::max_rounds(tweakey_length),
+            "skinny128-{} has at most {} rounds, got {}",
+            tweakey_length,
+            Self::max_rounds(tweakey_length),
+            n_rounds
         );
         let table = vec![
             0x65, 0x4c, 0x6a, 0x42, 0x4b, 0x63, 0x43, 0x6b, 0x55, 0x75, 0x5a, 0x7a, 0x53, 0x73,
@@ -43,17 +75,14 @@ impl Skinny128 {
             n_rounds,
             message_length,
             key_length,
-            sbox: Sbox::new(8, 8, table, message_length + key_length),
+            tweak_length,
+            sbox: Sbox::new(8, 8, table, message_length + tweakey_length),
         }
     }
 
     fn sub_cells(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
         assert!(in_bits.len() == self.message_length);
-        let mut out_bits = Vec::with_capacity(self.message_length);
-        for i in 0..16 {
-            out_bits.append(&mut self.sbox.apply(in_bits[i * 8..(i + 1) * 8].to_vec()));
-        }
-        out_bits
+        self.sbox.apply_layer(&in_bits)
     }
 
     fn add_constants(&self, in_bits: Vec<Bit>, round_index: usize) -> Vec<Bit> {
@@ -108,17 +137,81 @@ impl Skinny128 {
         out_bits
     }
 
+    fn inverse_shift_rows(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
+        assert!(in_bits.len() == self.message_length);
+        let mut out_bits = Vec::with_capacity(self.message_length);
+        for row in 0..4 {
+            for column in 0..4 {
+                for bit in 0..8 {
+                    out_bits.push(in_bits[bit + ((column + row) % 4) * 8 + row * 4 * 8].clone())
+                }
+            }
+        }
+        out_bits
+    }
+
+    fn inverse_mix_columns(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
+        assert!(in_bits.len() == self.message_length);
+        let mut out_bits = vec![bit!(false); self.message_length];
+        for row_bit in 0..4 * 8 {
+            out_bits[row_bit] = in_bits[4 * 8 + row_bit].clone();
+            out_bits[4 * 8 + row_bit] = in_bits[4 * 8 + row_bit].clone()
+                ^ in_bits[8 * 8 + row_bit].clone()
+                ^ in_bits[12 * 8 + row_bit].clone();
+            out_bits[8 * 8 + row_bit] =
+                in_bits[4 * 8 + row_bit].clone() ^ in_bits[12 * 8 + row_bit].clone();
+            out_bits[12 * 8 + row_bit] =
+                in_bits[row_bit].clone() ^ in_bits[12 * 8 + row_bit].clone();
+        }
+        out_bits
+    }
+
     fn add_round_key(&self, in_bits: Vec<Bit>, round_key: Vec<Bit>) -> Vec<Bit> {
         assert!(in_bits.len() == self.message_length);
         assert!(round_key.len() == self.message_length);
         bit_vector_xoring(in_bits, round_key)
     }
 
+    /// Decrypt the constant bits `in_bits` under the constant bits `key_bits` and `tweak_bits`,
+    /// by applying the inverse of each round with the round keys in reverse order.
+    pub fn decrypt_with_tweak(
+        &self,
+        in_bits: Vec<Bit>,
+        key_bits: Vec<Bit>,
+        tweak_bits: Vec<Bit>,
+    ) -> Vec<Bit> {
+        assert!(tweak_bits.len() == self.tweak_length);
+        let mut tweakey = tweak_bits;
+        tweakey.extend(key_bits);
+        let round_keys = self.make_round_keys(tweakey);
+        let inverse_sbox = self.sbox.inverse();
+        let mut out_bits = in_bits;
+        for round_index in (0..self.n_rounds).rev() {
+            out_bits = inverse_sbox.apply_layer(&self.add_constants(
+                self.add_round_key(
+                    self.inverse_shift_rows(self.inverse_mix_columns(out_bits)),
+                    round_keys[round_index].clone(),
+                ),
+                round_index,
+            ));
+        }
+        out_bits
+    }
+
+    /// Return the role of each of the tweakey words, from TK1 to the last one.
+    pub fn tweakey_words(&self) -> Vec<TweakeyWord> {
+        let n_tweak_words = self.tweak_length / self.message_length;
+        let n_key_words = self.key_length / self.message_length;
+        let mut words = vec![TweakeyWord::Tweak; n_tweak_words];
+        words.append(&mut vec![TweakeyWord::Key; n_key_words]);
+        words
+    }
+
     fn make_round_keys(&self, key: Vec<Bit>) -> Vec<Vec<Bit>> {
-        assert!(key.len() == self.key_length);
+        assert!(key.len() == self.key_length + self.tweak_length);
         let permute_table = [9, 15, 8, 13, 10, 14, 12, 11, 0, 1, 2, 3, 4, 5, 6, 7];
         let mut round_keys = vec![vec![bit!(false); self.message_length]; self.n_rounds];
-        for tweakey in 0..(self.key_length / self.message_length) {
+        for tweakey in 0..(key.len() / self.message_length) {
             let mut tweakey_key =
                 key[tweakey * self.message_length..(tweakey + 1) * self.message_length].to_vec();
             for round in 0..self.n_rounds {
@@ -210,8 +303,25 @@ fn add_constants_lfsr(t: usize) -> ([Bit; 8], [Bit; 8]) {
 }
 
 impl Cipher for Skinny128 {
+    /// Encrypt without a tweak, an instance built with a tweak has to use `encrypt_with_tweak`.
     fn encrypt(&self, in_bits: Vec<Bit>, key_bits: Vec<Bit>) -> Vec<Bit> {
-        let round_keys = self.make_round_keys(key_bits);
+        assert!(
+            self.tweak_length == 0,
+            "this instance takes a tweak, use encrypt_with_tweak"
+        );
+        self.encrypt_with_tweak(in_bits, key_bits, Vec::new())
+    }
+
+    fn encrypt_with_tweak(
+        &self,
+        in_bits: Vec<Bit>,
+        key_bits: Vec<Bit>,
+        tweak_bits: Vec<Bit>,
+    ) -> Vec<Bit> {
+        assert!(tweak_bits.len() == self.tweak_length);
+        let mut tweakey = tweak_bits;
+        tweakey.extend(key_bits);
+        let round_keys = self.make_round_keys(tweakey);
         let mut out_bits = in_bits.clone();
         for round_index in 0..self.n_rounds {
             out_bits = self.mix_columns(self.shift_rows(self.add_round_key(
@@ -222,6 +332,20 @@ impl Cipher for Skinny128 {
         out_bits
     }
 
+    /// Decrypt without a tweak, an instance built with a tweak has to use `decrypt_with_tweak`.
+    fn decrypt(&self, in_bits: Vec<Bit>, key_bits: Vec<Bit>) -> Option<Vec<Bit>> {
+        assert!(
+            self.tweak_length == 0,
+            "this instance takes a tweak, use decrypt_with_tweak"
+        );
+        Some(self.decrypt_with_tweak(in_bits, key_bits, Vec::new()))
+    }
+
+    /// `key` is the whole tweakey, the tweak followed by the key.
+    fn round_keys(&self, key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        Some(self.make_round_keys(key))
+    }
+
     fn message_length(&self) -> usize {
         self.message_length
     }
@@ -230,10 +354,25 @@ impl Cipher for Skinny128 {
         self.key_length
     }
 
+    fn tweak_length(&self) -> usize {
+        self.tweak_length
+    }
+
     fn n_rounds(&self) -> usize {
         self.n_rounds
     }
 
+    fn full_rounds(&self) -> usize {
+        Self::max_rounds(self.key_length + self.tweak_length)
+    }
+
+    fn verify_round_constants(&self) -> bool {
+        (0..self.full_rounds()).all(|round_index| {
+            let (c0, c1) = add_constants_lfsr(round_index);
+            round_constant_value(&c0, &c1) == Some(ROUND_CONSTANTS[round_index])
+        })
+    }
+
     fn sbox(&self) -> Sbox {
         self.sbox.clone()
     }
@@ -244,29 +383,98 @@ impl Cipher for Skinny128 {
 #[cfg(test)]
 mod test {
     use crate::bit;
-    use crate::targets::{skinny128::Skinny128, Cipher};
+    use crate::targets::{skinny128::Skinny128, Cipher, TweakeyWord};
 
     #[test]
     fn validate_encrypt() {
         //128-128
-        let key = bit::bits_from_hex_string("4f55cfb0520cac52fd92c15f37073e93");
-        let plaintext = bit::bits_from_hex_string("f20adb0eb08b648a3b2eeed1f0adda14");
-        let expected_ciphertext = bit::bits_from_hex_string("22ff30d498ea62d7e45b476e33675b74");
+        let key = bit::bits_from_hex_string_unchecked("4f55cfb0520cac52fd92c15f37073e93");
+        let plaintext = bit::bits_from_hex_string_unchecked("f20adb0eb08b648a3b2eeed1f0adda14");
+        let expected_ciphertext = bit::bits_from_hex_string_unchecked("22ff30d498ea62d7e45b476e33675b74");
         let skinny = Skinny128::new(128, 40);
         assert_eq!(expected_ciphertext, skinny.encrypt(plaintext, key));
         //128-256
-        let key = bit::bits_from_hex_string(
+        let key = bit::bits_from_hex_string_unchecked(
             "009cec81605d4ac1d2ae9e3085d7a1f31ac123ebfc00fddcf01046ceeddfcab3",
         );
-        let plaintext = bit::bits_from_hex_string("3a0c47767a26a68dd382a695e7022e25");
-        let expected_ciphertext = bit::bits_from_hex_string("b731d98a4bde147a7ed4a6f16b9b587f");
+        let plaintext = bit::bits_from_hex_string_unchecked("3a0c47767a26a68dd382a695e7022e25");
+        let expected_ciphertext = bit::bits_from_hex_string_unchecked("b731d98a4bde147a7ed4a6f16b9b587f");
         let skinny = Skinny128::new(256, 48);
         assert_eq!(expected_ciphertext, skinny.encrypt(plaintext, key));
         //128-384
-        let key = bit::bits_from_hex_string("df889548cfc7ea52d296339301797449ab588a34a47f1ab2dfe9c8293fbea9a5ab1afac2611012cd8cef952618c3ebe8");
-        let plaintext = bit::bits_from_hex_string("a3994b66ad85a3459f44e92b08f550cb");
-        let expected_ciphertext = bit::bits_from_hex_string("94ecf589e2017c601b38c6346a10dcfa");
+        let key = bit::bits_from_hex_string_unchecked("df889548cfc7ea52d296339301797449ab588a34a47f1ab2dfe9c8293fbea9a5ab1afac2611012cd8cef952618c3ebe8");
+        let plaintext = bit::bits_from_hex_string_unchecked("a3994b66ad85a3459f44e92b08f550cb");
+        let expected_ciphertext = bit::bits_from_hex_string_unchecked("94ecf589e2017c601b38c6346a10dcfa");
         let skinny = Skinny128::new(384, 56);
         assert_eq!(expected_ciphertext, skinny.encrypt(plaintext, key));
     }
+
+    #[test]
+    fn round_constants_match_the_specification() {
+        for key_length in [128, 256, 384].iter() {
+            assert!(Skinny128::new(*key_length, 1).verify_round_constants());
+        }
+    }
+
+    #[test]
+    fn validate_decrypt() {
+        //128-128
+        let key = bit::bits_from_hex_string_unchecked("4f55cfb0520cac52fd92c15f37073e93");
+        let ciphertext = bit::bits_from_hex_string_unchecked("22ff30d498ea62d7e45b476e33675b74");
+        let expected_plaintext = bit::bits_from_hex_string_unchecked("f20adb0eb08b648a3b2eeed1f0adda14");
+        let skinny = Skinny128::new(128, 40);
+        assert_eq!(expected_plaintext, skinny.decrypt(ciphertext, key).unwrap());
+        //128-256
+        let key = bit::bits_from_hex_string_unchecked("009cec81605d4ac1d2ae9e3085d7a1f31ac123ebfc00fddcf01046ceeddfcab3");
+        let ciphertext = bit::bits_from_hex_string_unchecked("b731d98a4bde147a7ed4a6f16b9b587f");
+        let expected_plaintext = bit::bits_from_hex_string_unchecked("3a0c47767a26a68dd382a695e7022e25");
+        let skinny = Skinny128::new(256, 48);
+        assert_eq!(expected_plaintext, skinny.decrypt(ciphertext, key).unwrap());
+        //128-384
+        let key = bit::bits_from_hex_string_unchecked("df889548cfc7ea52d296339301797449ab588a34a47f1ab2dfe9c8293fbea9a5ab1afac2611012cd8cef952618c3ebe8");
+        let ciphertext = bit::bits_from_hex_string_unchecked("94ecf589e2017c601b38c6346a10dcfa");
+        let expected_plaintext = bit::bits_from_hex_string_unchecked("a3994b66ad85a3459f44e92b08f550cb");
+        let skinny = Skinny128::new(384, 56);
+        assert_eq!(expected_plaintext, skinny.decrypt(ciphertext, key).unwrap());
+    }
+
+    #[test]
+    fn decrypt_inverts_encrypt() {
+        for key_length in [128, 256, 384].iter() {
+            let skinny = Skinny128::new(*key_length, 10);
+            let key = bit::random_bits(*key_length);
+            let plaintext = bit::random_bits(128);
+            let ciphertext = skinny.encrypt(plaintext.clone(), key.clone());
+            assert_eq!(plaintext, skinny.decrypt(ciphertext, key).unwrap());
+        }
+    }
+
+    #[test]
+    fn tweak_is_first_tweakey_words() {
+        //128-384 test vector, with the first two tweakey words as tweak
+        let tweak = bit::bits_from_hex_string_unchecked(
+            "df889548cfc7ea52d296339301797449ab588a34a47f1ab2dfe9c8293fbea9a5",
+        );
+        let key = bit::bits_from_hex_string_unchecked("ab1afac2611012cd8cef952618c3ebe8");
+        let plaintext = bit::bits_from_hex_string_unchecked("a3994b66ad85a3459f44e92b08f550cb");
+        let expected_ciphertext = bit::bits_from_hex_string_unchecked("94ecf589e2017c601b38c6346a10dcfa");
+        let skinny = Skinny128::with_tweak(128, 256, 56);
+        assert_eq!(
+            skinny.tweakey_words(),
+            vec![TweakeyWord::Tweak, TweakeyWord::Tweak, TweakeyWord::Key]
+        );
+        assert_eq!(skinny.tweak_length(), 256);
+        assert_eq!(skinny.key_length(), 128);
+        assert_eq!(
+            expected_ciphertext,
+            skinny.encrypt_with_tweak(plaintext, key, tweak)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn encrypt_without_tweak_panics() {
+        let skinny = Skinny128::with_tweak(128, 128, 4);
+        skinny.encrypt(bit::random_bits(128), bit::random_bits(128));
+    }
 }
@@ -1,21 +1,52 @@
 use crate::sbox::Sbox;
-use crate::targets::Cipher;
+use crate::targets::{Cipher, TweakeyWord};
 use crate::{bit, bit::Bit, bit::*};
 
 pub struct Skinny64 {
     n_rounds: usize,
     message_length: usize,
     key_length: usize,
+    tweak_length: usize,
     sbox: Sbox,
 }
 
 impl Skinny64 {
+    /// The number of rounds of the specification for a tweakey of `tweakey_length` bits
+    /// (32 for one tweakey word, 4 more for each additional word).
+    pub fn max_rounds(tweakey_length: usize) -> usize {
+        32 + 4 * (tweakey_length / 64 - 1)
+    }
+
     pub fn new(key_length: usize, n_rounds: usize) -> Self {
+        Self::with_tweak(key_length, 0, n_rounds)
+    }
+
+    /// Build a Skinny instance where the first `tweak_length` bits of the tweakey
+    /// are a public tweak and the following `key_length` bits are the secret key.
+    ///
+    /// The tweak occupies the first tweakey words (starting with TK1, as in SKINNY-AEAD),
+    /// see `tweakey_words`.
+    pub fn with_tweak(key_length: usize, tweak_length: usize, n_rounds: usize) -> Self {
         let message_length = 64;
+        let tweakey_length = key_length + tweak_length;
+        assert!(
+            tweakey_length == message_length
+                || tweakey_length == message_length * 2
+                || tweakey_length == message_length * 3
+        );
         assert!(
-            key_length == message_length
-                || key_length == message_length * 2
-                || key_length == message_length * 3
+            tweak_length < tweakey_length
+                && (tweak_length == 0
+                    || tweak_length == message_length
+                    || tweak_length == message_length * 2),
+            "the tweak should be made of whole tweakey words and leave at least one for the key"
+        );
+        assert!(
+            n_rounds <= Self::max_rounds(tweakey_length),
+            "skinny64-{} has at most {} rounds, got {}",
+            tweakey_length,
+            Self::max_rounds(tweakey_length),
+            n_rounds
         );
         let table = vec![
             0xc, 0x6, 0x9, 0x0, 0x1, 0xa, 0x2, 0xb, 0x3, 0x8, 0x5, 0xd, 0x4, 0xe, 0x7, 0xf,
@@ -25,17 +56,14 @@ impl Skinny64 {
             n_rounds,
             message_length,
             key_length,
-            sbox: Sbox::new(4, 4, table, message_length + key_length),
+            tweak_length,
+            sbox: Sbox::new(4, 4, table, message_length + tweakey_length),
         }
     }
 
     fn sub_cells(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
         assert!(in_bits.len() == self.message_length);
-        let mut out_bits = Vec::with_capacity(self.message_length);
-        for i in 0..16 {
-            out_bits.append(&mut self.sbox.apply(in_bits[i * 4..(i + 1) * 4].to_vec()));
-        }
-        out_bits
+        self.sbox.apply_layer(&in_bits)
     }
 
     fn add_constants(&self, in_bits: Vec<Bit>, round_index: usize) -> Vec<Bit> {
@@ -81,17 +109,81 @@ impl Skinny64 {
         out_bits
     }
 
+    fn inverse_shift_rows(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
+        assert!(in_bits.len() == self.message_length);
+        let mut out_bits = Vec::with_capacity(self.message_length);
+        for row in 0..4 {
+            for column in 0..4 {
+                for bit in 0..4 {
+                    out_bits.push(in_bits[bit + ((column + row) % 4) * 4 + row * 4 * 4].clone())
+                }
+            }
+        }
+        out_bits
+    }
+
+    fn inverse_mix_columns(&self, in_bits: Vec<Bit>) -> Vec<Bit> {
+        assert!(in_bits.len() == self.message_length);
+        let mut out_bits = vec![bit!(false); self.message_length];
+        for row_bit in 0..4 * 4 {
+            out_bits[row_bit] = in_bits[4 * 4 + row_bit].clone();
+            out_bits[4 * 4 + row_bit] = in_bits[4 * 4 + row_bit].clone()
+                ^ in_bits[8 * 4 + row_bit].clone()
+                ^ in_bits[12 * 4 + row_bit].clone();
+            out_bits[8 * 4 + row_bit] =
+                in_bits[4 * 4 + row_bit].clone() ^ in_bits[12 * 4 + row_bit].clone();
+            out_bits[12 * 4 + row_bit] =
+                in_bits[row_bit].clone() ^ in_bits[12 * 4 + row_bit].clone();
+        }
+        out_bits
+    }
+
     fn add_round_key(&self, in_bits: Vec<Bit>, round_key: Vec<Bit>) -> Vec<Bit> {
         assert!(in_bits.len() == self.message_length);
         assert!(round_key.len() == self.message_length);
         bit_vector_xoring(in_bits, round_key)
     }
 
+    /// Decrypt the constant bits `in_bits` under the constant bits `key_bits` and `tweak_bits`,
+    /// by applying the inverse of each round with the round keys in reverse order.
+    pub fn decrypt_with_tweak(
+        &self,
+        in_bits: Vec<Bit>,
+        key_bits: Vec<Bit>,
+        tweak_bits: Vec<Bit>,
+    ) -> Vec<Bit> {
+        assert!(tweak_bits.len() == self.tweak_length);
+        let mut tweakey = tweak_bits;
+        tweakey.extend(key_bits);
+        let round_keys = self.make_round_keys(tweakey);
+        let inverse_sbox = self.sbox.inverse();
+        let mut out_bits = in_bits;
+        for round_index in (0..self.n_rounds).rev() {
+            out_bits = inverse_sbox.apply_layer(&self.add_constants(
+                self.add_round_key(
+                    self.inverse_shift_rows(self.inverse_mix_columns(out_bits)),
+                    round_keys[round_index].clone(),
+                ),
+                round_index,
+            ));
+        }
+        out_bits
+    }
+
+    /// Return the role of each of the tweakey words, from TK1 to the last one.
+    pub fn tweakey_words(&self) -> Vec<TweakeyWord> {
+        let n_tweak_words = self.tweak_length / self.message_length;
+        let n_key_words = self.key_length / self.message_length;
+        let mut words = vec![TweakeyWord::Tweak; n_tweak_words];
+        words.append(&mut vec![TweakeyWord::Key; n_key_words]);
+        words
+    }
+
     fn make_round_keys(&self, key: Vec<Bit>) -> Vec<Vec<Bit>> {
-        assert!(key.len() == self.key_length);
+        assert!(key.len() == self.key_length + self.tweak_length);
         let permute_table = [9, 15, 8, 13, 10, 14, 12, 11, 0, 1, 2, 3, 4, 5, 6, 7];
         let mut round_keys = vec![vec![bit!(false); self.message_length]; self.n_rounds];
-        for tweakey in 0..(self.key_length / self.message_length) {
+        for tweakey in 0..(key.len() / self.message_length) {
             let mut tweakey_key =
                 key[tweakey * self.message_length..(tweakey + 1) * self.message_length].to_vec();
             for round in 0..self.n_rounds {
@@ -139,6 +231,26 @@ impl Skinny64 {
     }
 }
 
+/// The 6 bit round constants (rc5 ... rc0) of the 62 first rounds, from the Skinny
+/// specification (shared by Skinny64 and Skinny128).
+pub(crate) const ROUND_CONSTANTS: [u8; 62] = [
+    0x01, 0x03, 0x07, 0x0f, 0x1f, 0x3e, 0x3d, 0x3b, 0x37, 0x2f, 0x1e, 0x3c, 0x39, 0x33, 0x27, 0x0e,
+    0x1d, 0x3a, 0x35, 0x2b, 0x16, 0x2c, 0x18, 0x30, 0x21, 0x02, 0x05, 0x0b, 0x17, 0x2e, 0x1c, 0x38,
+    0x31, 0x23, 0x06, 0x0d, 0x1b, 0x36, 0x2d, 0x1a, 0x34, 0x29, 0x12, 0x24, 0x08, 0x11, 0x22, 0x04,
+    0x09, 0x13, 0x26, 0x0c, 0x19, 0x32, 0x25, 0x0a, 0x15, 0x2a, 0x14, 0x28, 0x10, 0x20,
+];
+
+/// Returns the round constant (rc5 ... rc0) held by the constant cells `c0` (rc3 ... rc0) and
+/// `c1` (rc5, rc4), the bits of a cell being most significant first.
+pub(crate) fn round_constant_value(c0: &[Bit], c1: &[Bit]) -> Option<u8> {
+    let value = |cell: &[Bit]| {
+        cell.iter().try_fold(0u8, |value, bit| {
+            bit.is_constant().map(|bit| (value << 1) | bit as u8)
+        })
+    };
+    Some((value(c1)? << 4) | value(c0)?)
+}
+
 fn add_constants_lfsr(t: usize) -> ([Bit; 4], [Bit; 4]) {
     let mut rc = vec![bit!(false); 6];
     for _ in 0..=t {
@@ -157,8 +269,25 @@ fn add_constants_lfsr(t: usize) -> ([Bit; 4], [Bit; 4]) {
 }
 
 impl Cipher for Skinny64 {
+    /// Encrypt without a tweak, an instance built with a tweak has to use `encrypt_with_tweak`.
     fn encrypt(&self, in_bits: Vec<Bit>, key_bits: Vec<Bit>) -> Vec<Bit> {
-        let round_keys = self.make_round_keys(key_bits);
+        assert!(
+            self.tweak_length == 0,
+            "this instance takes a tweak, use encrypt_with_tweak"
+        );
+        self.encrypt_with_tweak(in_bits, key_bits, Vec::new())
+    }
+
+    fn encrypt_with_tweak(
+        &self,
+        in_bits: Vec<Bit>,
+        key_bits: Vec<Bit>,
+        tweak_bits: Vec<Bit>,
+    ) -> Vec<Bit> {
+        assert!(tweak_bits.len() == self.tweak_length);
+        let mut tweakey = tweak_bits;
+        tweakey.extend(key_bits);
+        let round_keys = self.make_round_keys(tweakey);
         let mut out_bits = in_bits.clone();
         for round_index in 0..self.n_rounds {
             out_bits = self.mix_columns(self.shift_rows(self.add_round_key(
@@ -169,6 +298,20 @@ impl Cipher for Skinny64 {
         out_bits
     }
 
+    /// Decrypt without a tweak, an instance built with a tweak has to use `decrypt_with_tweak`.
+    fn decrypt(&self, in_bits: Vec<Bit>, key_bits: Vec<Bit>) -> Option<Vec<Bit>> {
+        assert!(
+            self.tweak_length == 0,
+            "this instance takes a tweak, use decrypt_with_tweak"
+        );
+        Some(self.decrypt_with_tweak(in_bits, key_bits, Vec::new()))
+    }
+
+    /// `key` is the whole tweakey, the tweak followed by the key.
+    fn round_keys(&self, key: Vec<Bit>) -> Option<Vec<Vec<Bit>>> {
+        Some(self.make_round_keys(key))
+    }
+
     fn message_length(&self) -> usize {
         self.message_length
     }
@@ -177,10 +320,25 @@ impl Cipher for Skinny64 {
         self.key_length
     }
 
+    fn tweak_length(&self) -> usize {
+        self.tweak_length
+    }
+
     fn n_rounds(&self) -> usize {
         self.n_rounds
     }
 
+    fn full_rounds(&self) -> usize {
+        Self::max_rounds(self.key_length + self.tweak_length)
+    }
+
+    fn verify_round_constants(&self) -> bool {
+        (0..self.full_rounds()).all(|round_index| {
+            let (c0, c1) = add_constants_lfsr(round_index);
+            round_constant_value(&c0, &c1) == Some(ROUND_CONSTANTS[round_index])
+        })
+    }
+
     fn sbox(&self) -> Sbox {
         self.sbox.clone()
     }
@@ -191,27 +349,117 @@ impl Cipher for Skinny64 {
 #[cfg(test)]
 mod test {
     use crate::bit;
-    use crate::targets::{skinny64::Skinny64, Cipher};
+    use crate::strategy::{execute_strategy_by_name, LogLevel};
+    use crate::targets::{
+        build_system_cipher, fix_system_values_cipher_with_tweak,
+        skinny64::Skinny64,
+        Cipher, TweakeyWord,
+    };
 
     #[test]
     fn validate_encrypt() {
         //64-64
-        let key = bit::bits_from_hex_string("f5269826fc681238");
-        let plaintext = bit::bits_from_hex_string("06034f957724d19d");
-        let expected_ciphertext = bit::bits_from_hex_string("bb39dfb2429b8ac7");
+        let key = bit::bits_from_hex_string_unchecked("f5269826fc681238");
+        let plaintext = bit::bits_from_hex_string_unchecked("06034f957724d19d");
+        let expected_ciphertext = bit::bits_from_hex_string_unchecked("bb39dfb2429b8ac7");
         let skinny = Skinny64::new(64, 32);
         assert_eq!(expected_ciphertext, skinny.encrypt(plaintext, key));
         //64-128
-        let key = bit::bits_from_hex_string("9eb93640d088da6376a39d1c8bea71e1");
-        let plaintext = bit::bits_from_hex_string("cf16cfe8fd0f98aa");
-        let expected_ciphertext = bit::bits_from_hex_string("6ceda1f43de92b9e");
+        let key = bit::bits_from_hex_string_unchecked("9eb93640d088da6376a39d1c8bea71e1");
+        let plaintext = bit::bits_from_hex_string_unchecked("cf16cfe8fd0f98aa");
+        let expected_ciphertext = bit::bits_from_hex_string_unchecked("6ceda1f43de92b9e");
         let skinny = Skinny64::new(128, 36);
         assert_eq!(expected_ciphertext, skinny.encrypt(plaintext, key));
         //64-192
-        let key = bit::bits_from_hex_string("ed00c85b120d68618753e24bfd908f60b2dbb41b422dfcd0");
-        let plaintext = bit::bits_from_hex_string("530c61d35e8663c3");
-        let expected_ciphertext = bit::bits_from_hex_string("dd2cf1a8f330303c");
+        let key = bit::bits_from_hex_string_unchecked("ed00c85b120d68618753e24bfd908f60b2dbb41b422dfcd0");
+        let plaintext = bit::bits_from_hex_string_unchecked("530c61d35e8663c3");
+        let expected_ciphertext = bit::bits_from_hex_string_unchecked("dd2cf1a8f330303c");
         let skinny = Skinny64::new(192, 40);
         assert_eq!(expected_ciphertext, skinny.encrypt(plaintext, key));
     }
+
+    #[test]
+    fn round_constants_match_the_specification() {
+        for key_length in [64, 128, 192].iter() {
+            assert!(Skinny64::new(*key_length, 1).verify_round_constants());
+        }
+    }
+
+    #[test]
+    fn validate_decrypt() {
+        //64-64
+        let key = bit::bits_from_hex_string_unchecked("f5269826fc681238");
+        let ciphertext = bit::bits_from_hex_string_unchecked("bb39dfb2429b8ac7");
+        let expected_plaintext = bit::bits_from_hex_string_unchecked("06034f957724d19d");
+        let skinny = Skinny64::new(64, 32);
+        assert_eq!(expected_plaintext, skinny.decrypt(ciphertext, key).unwrap());
+        //64-128
+        let key = bit::bits_from_hex_string_unchecked("9eb93640d088da6376a39d1c8bea71e1");
+        let ciphertext = bit::bits_from_hex_string_unchecked("6ceda1f43de92b9e");
+        let expected_plaintext = bit::bits_from_hex_string_unchecked("cf16cfe8fd0f98aa");
+        let skinny = Skinny64::new(128, 36);
+        assert_eq!(expected_plaintext, skinny.decrypt(ciphertext, key).unwrap());
+        //64-192
+        let key = bit::bits_from_hex_string_unchecked("ed00c85b120d68618753e24bfd908f60b2dbb41b422dfcd0");
+        let ciphertext = bit::bits_from_hex_string_unchecked("dd2cf1a8f330303c");
+        let expected_plaintext = bit::bits_from_hex_string_unchecked("530c61d35e8663c3");
+        let skinny = Skinny64::new(192, 40);
+        assert_eq!(expected_plaintext, skinny.decrypt(ciphertext, key).unwrap());
+    }
+
+    #[test]
+    fn decrypt_inverts_encrypt() {
+        for key_length in [64, 128, 192].iter() {
+            let skinny = Skinny64::new(*key_length, 10);
+            let key = bit::random_bits(*key_length);
+            let plaintext = bit::random_bits(64);
+            let ciphertext = skinny.encrypt(plaintext.clone(), key.clone());
+            assert_eq!(plaintext, skinny.decrypt(ciphertext, key).unwrap());
+        }
+    }
+
+    #[test]
+    fn tweak_is_first_tweakey_word() {
+        let key = bit::bits_from_hex_string_unchecked("76a39d1c8bea71e1");
+        let tweak = bit::bits_from_hex_string_unchecked("9eb93640d088da63");
+        let plaintext = bit::bits_from_hex_string_unchecked("cf16cfe8fd0f98aa");
+        let mut tweakey = tweak.clone();
+        tweakey.extend(key.clone());
+        let reference = Skinny64::new(128, 36).encrypt(plaintext.clone(), tweakey);
+        let skinny = Skinny64::with_tweak(64, 64, 36);
+        assert_eq!(
+            skinny.tweakey_words(),
+            vec![TweakeyWord::Tweak, TweakeyWord::Key]
+        );
+        assert_eq!(skinny.tweak_length(), 64);
+        assert_eq!(skinny.key_length(), 64);
+        assert_eq!(reference, skinny.encrypt_with_tweak(plaintext, key, tweak));
+    }
+
+    #[test]
+    fn solve_with_known_tweak() {
+        // with 2 rounds every key bit is used, and the key is already unique
+        let skinny = Skinny64::with_tweak(64, 64, 2);
+        let key = bit::bits_from_hex_string_unchecked("76a39d1c8bea71e1");
+        let tweak = bit::bits_from_hex_string_unchecked("9eb93640d088da63");
+        let plaintext = bit::bits_from_hex_string_unchecked("cf16cfe8fd0f98aa");
+        let ciphertext = skinny.encrypt_with_tweak(plaintext.clone(), key.clone(), tweak.clone());
+        let (layout, mut system) = build_system_cipher(&skinny);
+        fix_system_values_cipher_with_tweak(&mut system, &layout, &plaintext, &ciphertext, &tweak);
+        let sols = execute_strategy_by_name("no_drop", &mut system, None, LogLevel::Quiet)
+            .unwrap()
+            .unwrap();
+        assert!(!sols.is_empty());
+        for sol in sols.iter() {
+            let found_key: Vec<_> = sol[..skinny.key_length()]
+                .iter()
+                .map(|b| bit!(b.expect("key bits should be determined")))
+                .collect();
+            assert_eq!(key, found_key);
+            assert_eq!(
+                ciphertext,
+                skinny.encrypt_with_tweak(plaintext.clone(), found_key, tweak.clone())
+            );
+        }
+    }
 }
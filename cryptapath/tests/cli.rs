@@ -0,0 +1,343 @@
+use std::process::Command;
+
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_main"))
+        .args(args)
+        .output()
+        .expect("failed to run cryptapath");
+    assert!(output.status.success(), "cryptapath exited with an error");
+    String::from_utf8(output.stdout).expect("output should be valid utf8")
+}
+
+#[test]
+fn quiet_solve_only_prints_solutions() {
+    let stdout = run(&["cipher", "-c", "miniaes2x2", "-r", "1", "--log-level", "quiet"]);
+    assert!(!stdout.contains('\x1B'));
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(!lines.is_empty());
+    for line in lines {
+        assert!(line.starts_with("valid solution : "), "unexpected line {:?}", line);
+    }
+}
+
+#[test]
+fn profile_times_every_phase() {
+    let args = ["cipher", "-c", "miniaes2x2", "-r", "1", "--log-level", "quiet"];
+    let stdout = run(&[&args[..], &["--profile"]].concat());
+    let lines: Vec<&str> = stdout.lines().collect();
+    let phases: Vec<&str> = lines[lines.len() - 4..]
+        .iter()
+        .map(|line| line.split(" : ").next().unwrap())
+        .collect();
+    assert_eq!(phases, vec!["build", "fix", "absorb", "solve"]);
+    let stdout = run(&[&args[..], &["--profile-json"]].concat());
+    let json = stdout.lines().last().unwrap();
+    assert!(json.starts_with("{\"profile\":{"), "unexpected line {:?}", json);
+    for phase in ["build", "fix", "absorb", "solve"].iter() {
+        assert!(json.contains(&format!("\"{}\":", phase)), "{} missing in {:?}", phase, json);
+    }
+}
+
+#[test]
+fn normal_solve_does_not_clear_the_screen() {
+    let stdout = run(&["cipher", "-c", "miniaes2x2", "-r", "1"]);
+    assert!(!stdout.contains('\x1B'));
+    assert!(stdout.lines().any(|line| line.starts_with("solved dependencies")));
+}
+
+#[test]
+fn explain_prints_one_line_per_resolution() {
+    let stdout = run(&["cipher", "-c", "miniaes2x2", "-r", "1", "-s", "drop", "--explain"]);
+    let resolved = stdout
+        .lines()
+        .filter(|line| line.starts_with("resolved "))
+        .count();
+    let counts: Vec<usize> = stdout
+        .lines()
+        .find(|line| line.starts_with("solved dependencies"))
+        .expect("the counts should be printed")
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|number| !number.is_empty())
+        .map(|number| number.parse().unwrap())
+        .collect();
+    assert_eq!(counts.len(), 2);
+    assert_eq!(resolved, counts[0] + counts[1]);
+    assert!(stdout.lines().any(|line| line.starts_with("resolved drop : distance ")));
+    assert!(stdout.lines().any(|line| line.starts_with("valid solution : ")));
+}
+
+#[test]
+fn reduced_katan32_accepts_ciphertext_bits_implied_by_the_plaintext() {
+    // after 16 rounds some ciphertext bits still are plaintext bits
+    let stdout = run(&["cipher", "-c", "katan32", "-r", "16", "--log-level", "quiet"]);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(!lines.is_empty());
+    for line in lines {
+        assert!(line.starts_with("valid solution : "), "unexpected line {:?}", line);
+    }
+}
+
+#[test]
+fn invalid_hex_input_is_reported() {
+    let stdout = run(&[
+        "cipher", "-c", "miniaes2x2", "-r", "1", "-p", "f721f4dg", "-p", "b1497afd",
+    ]);
+    assert_eq!(stdout.trim(), "Invalid plaintext: invalid digit 'g' at position 7");
+}
+
+#[test]
+fn invalid_sizes_are_reported() {
+    let stdout = run(&["cipher", "-c", "miniaes2x2", "-r", "1", "-k", "xx01"]);
+    assert_eq!(
+        stdout.trim(),
+        "The provided partial key has a size different from the key of 32 bits"
+    );
+    let stdout = run(&["cipher", "-c", "miniaes2x2", "-r", "1", "-p", "f721f4df"]);
+    assert_eq!(stdout.trim(), "You can only provide one plaintext and one ciphertext");
+    let stdout = run(&["recover-plaintext", "-c", "miniaes2x2", "-r", "1", "-k", "c9bd"]);
+    assert_eq!(
+        stdout.trim(),
+        "The provided key has a size different from the key of 32 bits"
+    );
+}
+
+#[test]
+fn known_key_recovers_the_plaintext() {
+    let stdout = run(&[
+        "recover-plaintext",
+        "-c",
+        "miniaes2x2",
+        "-r",
+        "1",
+        "-k",
+        "c9bd6550",
+        "--ciphertext",
+        "b1497afd",
+        "--log-level",
+        "quiet",
+    ]);
+    // the plaintext is checked to encrypt back to the ciphertext before being printed
+    assert_eq!(stdout, "valid solution : 5cbb5bec\n");
+}
+
+#[test]
+fn max_solutions_caps_the_printed_solutions() {
+    // this pair has 6 valid keys
+    let pair = ["-p", "f721f4df", "-p", "b1497afd"];
+    let mut args = vec!["cipher", "-c", "miniaes2x2", "-r", "1", "--log-level", "quiet"];
+    args.extend_from_slice(&pair);
+    args.extend_from_slice(&["--max-solutions", "2"]);
+    let stdout = run(&args);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    for line in lines.iter().take(2) {
+        assert!(line.starts_with("valid solution : "), "unexpected line {:?}", line);
+    }
+    assert_eq!(lines[2], "... and more");
+    // one round of present80 leaves about 2^16 valid keys, more than the solver enumerates
+    let stdout = run(&[
+        "cipher",
+        "-c",
+        "present80",
+        "-r",
+        "1",
+        "--log-level",
+        "quiet",
+        "--max-solutions",
+        "30",
+    ]);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 31);
+    let keys: std::collections::HashSet<&str> = lines[..30]
+        .iter()
+        .map(|line| line.trim_start_matches("valid solution : "))
+        .collect();
+    assert_eq!(keys.len(), 30);
+    assert_eq!(lines[30], "... and more");
+}
+
+#[test]
+fn cached_system_gives_the_same_solutions() {
+    let cache_dir =
+        std::env::temp_dir().join(format!("cryptapath_cli_cache_{}", std::process::id()));
+    let args = [
+        "cipher",
+        "-c",
+        "miniaes2x2",
+        "-r",
+        "1",
+        "--log-level",
+        "quiet",
+        "-p",
+        "f721f4df",
+        "-p",
+        "b1497afd",
+    ];
+    let sorted_lines = |stdout: String| {
+        let mut lines: Vec<String> = stdout.lines().map(String::from).collect();
+        lines.sort();
+        lines
+    };
+    let expected = sorted_lines(run(&args));
+    assert_eq!(expected.len(), 6);
+    let cached_args = [&args[..], &["--cache-dir", cache_dir.to_str().unwrap()]].concat();
+    // the first run saves the system, the second one loads it
+    for _ in 0..2 {
+        assert_eq!(sorted_lines(run(&cached_args)), expected);
+    }
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 2);
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+}
+
+#[test]
+fn full_solution_labels_every_variable() {
+    let args = [
+        "cipher", "-c", "miniaes2x2", "-r", "1", "--log-level", "quiet", "-p", "f721f4df", "-p",
+        "b1497afd", "--max-solutions", "1", "--full-solution",
+    ];
+    let stdout = run(&args);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0].len(), "key : ".len() + 32);
+    assert!(lines[0].starts_with("key : "));
+    assert_eq!(lines[1], "message : 11110111001000011111010011011111");
+    assert!(lines[2].starts_with("intermediate : "));
+    assert!(lines[3].starts_with("valid solution : "));
+}
+
+#[test]
+fn absorb_only_reports_without_solving() {
+    let args = [
+        "cipher", "-c", "miniaes2x2", "-r", "1", "-p", "f721f4df", "-p", "b1497afd",
+        "--absorb-only",
+    ];
+    let stdout = run(&args);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("linear equations found : "));
+    assert!(lines[1].starts_with("bdds remaining : "));
+    assert!(lines[2].starts_with("total nodes remaining : "));
+    assert_eq!(stdout, run(&args));
+}
+
+#[test]
+fn key_relations_hold_for_every_recovered_key() {
+    let relations = std::env::temp_dir().join("cryptapath_key_relations.txt");
+    std::fs::write(&relations, "# k0 = k1\n0+1 = 0\n2 = 0\n").unwrap();
+    let args = [
+        "cipher",
+        "-c",
+        "miniaes2x2",
+        "-r",
+        "1",
+        "--log-level",
+        "quiet",
+        "--key-bits",
+        "xxxx1xxxxxxxxxxxxxxxxxxxxxxxxxxx",
+        "--key-relations",
+        relations.to_str().unwrap(),
+        "--output-format",
+        "bin",
+    ];
+    let stdout = run(&args);
+    let keys: Vec<Vec<char>> = stdout
+        .lines()
+        .map(|line| line.trim_start_matches("valid solution : ").chars().collect())
+        .collect();
+    assert!(!keys.is_empty());
+    for key in keys.iter() {
+        assert_eq!(key.len(), 32);
+        assert_eq!(key[0], key[1]);
+        assert_eq!(key[2], '0');
+        assert_eq!(key[4], '1');
+    }
+}
+
+#[test]
+fn from_file_prints_the_solutions() {
+    // x2 = x0*x1 with x0 = 1 and x1 = 1
+    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/and.bdd");
+    let stdout = run(&["from-file", "-f", file, "--log-level", "quiet"]);
+    assert_eq!(stdout, "solution : e (bin 111)\n");
+    let stdout = run(&["from-file", "-f", file, "--log-level", "quiet", "--output-format", "bin"]);
+    assert_eq!(stdout, "solution : 111\n");
+    let stdout = run(&["from-file", "-f", file, "--json"]);
+    assert_eq!(stdout, "{\"solutions\":[{\"binary\":\"111\",\"hex\":\"e\"}]}\n");
+}
+
+#[test]
+fn from_anf_prints_the_solutions() {
+    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/quadratic.anf");
+    let stdout = run(&["from-anf", "-f", file, "--log-level", "quiet"]);
+    assert_eq!(stdout, "solution (x0..x2) : 011\n");
+    let stdout = run(&["from-anf", "-f", file, "--log-level", "quiet", "--output-format", "both"]);
+    assert_eq!(stdout, "solution (x0..x2) : 6 (bin 011)\n");
+}
+
+#[test]
+fn analyze_prints_the_sbox_properties() {
+    let stdout = run(&["analyze", "-c", "miniaes4x4", "-r", "2"]);
+    assert_eq!(
+        stdout,
+        concat!(
+            "sbox is a permutation : true\nsbox algebraic degree : 3\n",
+            "round 1 : 16 sbox relations, 64 linear equations\n",
+            "round 2 : 16 sbox relations, 64 linear equations\n",
+            "total : 32 sbox relations, 128 linear equations\n"
+        )
+    );
+}
+
+#[test]
+fn make_param_prints_the_key_in_both_formats() {
+    let stdout = run(&[
+        "make-cipher-param",
+        "-c",
+        "miniaes2x2",
+        "-r",
+        "1",
+        "--output-format",
+        "both",
+    ]);
+    let key = stdout
+        .lines()
+        .find(|line| line.starts_with("key : "))
+        .expect("the key should be printed");
+    // key : <hex> (bin <binary>)
+    let parts: Vec<&str> = key["key : ".len()..].split(" (bin ").collect();
+    assert_eq!(parts.len(), 2, "unexpected line {:?}", key);
+    let hex = parts[0];
+    let binary = parts[1].trim_end_matches(')');
+    assert_eq!(binary.len(), 32);
+    assert_eq!(
+        u64::from_str_radix(hex, 16).unwrap(),
+        u64::from_str_radix(binary, 2).unwrap()
+    );
+}
+
+#[test]
+fn single_thread_gives_the_same_solutions() {
+    // this pair has 6 valid keys
+    let args = [
+        "cipher", "-c", "miniaes2x2", "-r", "1", "--log-level", "quiet", "-p", "f721f4df", "-p",
+        "b1497afd",
+    ];
+    let sorted_lines = |stdout: String| {
+        let mut lines: Vec<String> = stdout.lines().map(|line| line.to_string()).collect();
+        lines.sort();
+        lines
+    };
+    let default = sorted_lines(run(&args));
+    let mut single_thread = vec!["--threads", "1"];
+    single_thread.extend_from_slice(&args);
+    assert_eq!(default.len(), 6);
+    assert_eq!(sorted_lines(run(&single_thread)), default);
+}
+
+#[test]
+fn list_shows_the_supported_targets_and_strategies() {
+    let stdout = run(&["list"]);
+    assert!(stdout.contains("present80 (block 64 bits, key 80 bits, 31 rounds) : "));
+    assert!(stdout.contains("lowmc64 (block 64 bits, key 80 bits, any number of rounds) : "));
+    assert!(stdout.lines().any(|line| line.trim_start().starts_with("keccak : ")));
+    assert!(stdout.lines().any(|line| line.trim_start().starts_with("no_drop : ")));
+}